@@ -1,6 +1,10 @@
 use crate::circle::CirclePointGadget;
+use crate::pcs::QuotientAccumulationGadget;
 use crate::treepp::*;
-use rust_bitcoin_m31::{qm31_add, qm31_roll, qm31_shift_by_i, qm31_shift_by_ij, qm31_shift_by_j};
+use rust_bitcoin_m31::{
+    qm31_add, qm31_copy, qm31_equalverify, qm31_from_bottom, qm31_roll, qm31_shift_by_i,
+    qm31_shift_by_ij, qm31_shift_by_j,
+};
 use stwo_prover::core::poly::circle::CanonicCoset;
 use stwo_prover::core::ColumnVec;
 
@@ -39,6 +43,48 @@ impl AirGadget {
         }
     }
 
+    /// Mask several points, which gives the shifted points in the order of
+    /// [`crate::oods::sampled_points`]: for each column, the mask of each point, in the order of
+    /// the points.
+    ///
+    /// Input:
+    /// -  points (n_points points in qm31, the last one on the top)
+    ///
+    /// Output:
+    /// -  shifted points (in qm31)
+    pub fn multi_shifted_mask_points(
+        mask: &Mask,
+        domains: &[CanonicCoset],
+        n_points: usize,
+    ) -> Script {
+        assert!(n_points > 0);
+
+        let mut shifted = vec![];
+        for (mask_entry, domain) in mask.iter().zip(domains.iter()) {
+            for j in 0..n_points {
+                for mask_item in mask_entry.iter() {
+                    shifted.push((j, domain.at(*mask_item)));
+                }
+            }
+        }
+
+        script! {
+            for (i, (j, elem)) in shifted.iter().enumerate() {
+                // copy the point j, which is below the i shifted points
+                { qm31_copy(2 * (i + n_points - 1 - j) + 1) }
+                { qm31_copy(2 * (i + n_points - 1 - j) + 1) }
+                { CirclePointGadget::add_constant_m31_point(elem) }
+            }
+
+            // drop the points, which are below all the shifted points
+            for _ in 0..n_points {
+                { qm31_roll(2 * shifted.len() + 1) }
+                { qm31_roll(2 * shifted.len() + 1) }
+                { CirclePointGadget::drop() }
+            }
+        }
+    }
+
     /// Combine the evaluation on four points into one.
     ///
     /// Input:
@@ -61,18 +107,67 @@ impl AirGadget {
             qm31_add
         }
     }
+
+    /// Recombine the hinted constraint quotients with `random_coeff` and check the result
+    /// against the composition OODS value, so that the composition equation is enforced by the
+    /// script rather than by the off-chain verifier that produces the hints.
+    ///
+    /// The quotients must be in the order in which the AIR accumulates them, see
+    /// [`crate::pcs::accumulate_quotients`], where the first one ends up with the highest power
+    /// of `random_coeff`. The check alone does not bind the quotients to the trace, so they are
+    /// returned for the AIR-specific gadgets to check each of them against the mask values.
+    ///
+    /// Hint:
+    /// - constraint quotients (n_constraints qm31)
+    ///
+    /// Input:
+    /// - composition oods value (qm31), see [`Self::eval_from_partial_evals`]
+    /// - random_coeff (qm31)
+    ///
+    /// Output:
+    /// - constraint quotients (n_constraints qm31, the last one on the top)
+    pub fn check_composition_with_hints(n_constraints: usize) -> Script {
+        assert!(n_constraints > 0);
+        script! {
+            for _ in 0..n_constraints {
+                qm31_from_bottom
+            }
+
+            // copy random_coeff and the quotients
+            for _ in 0..=n_constraints {
+                { qm31_copy(n_constraints) }
+            }
+            { QuotientAccumulationGadget::accumulate(n_constraints) }
+
+            { qm31_roll(n_constraints + 2) }
+            qm31_equalverify
+
+            // drop random_coeff
+            { qm31_roll(n_constraints) }
+            OP_2DROP OP_2DROP
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::air::AirGadget;
+    use crate::air::{AirGadget, CompositionHint};
     use crate::circle::CirclePointGadget;
+    use crate::oods::sampled_points;
+    use crate::pcs::accumulate_quotients;
     use crate::treepp::*;
+    use crate::utils::get_rand_qm31;
+    use itertools::Itertools;
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha20Rng;
+    use rust_bitcoin_m31::qm31_equalverify;
     use stwo_prover::core::air::mask::shifted_mask_points;
+    use stwo_prover::core::air::AirExt;
     use stwo_prover::core::circle::{CirclePoint, SECURE_FIELD_CIRCLE_ORDER};
+    use stwo_prover::core::fields::m31::M31;
     use stwo_prover::core::poly::circle::CanonicCoset;
+    use stwo_prover::core::ComponentVec;
+    use stwo_prover::examples::fibonacci::Fibonacci;
 
     #[test]
     fn test_shifted_mask_points() {
@@ -104,4 +199,105 @@ mod test {
             assert!(exec_result.success);
         }
     }
+
+    #[test]
+    fn test_multi_shifted_mask_points() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let mask = vec![vec![0, 1, 2], vec![0]];
+        let domains = [CanonicCoset::new(5), CanonicCoset::new(4)];
+
+        for n_points in 1..=3 {
+            let points = (0..n_points)
+                .map(|_| CirclePoint::get_point(prng.gen::<u128>() % SECURE_FIELD_CIRCLE_ORDER))
+                .collect::<Vec<_>>();
+
+            let expected = sampled_points(&points, |p| shifted_mask_points(&mask, &domains, p))
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            assert_eq!(expected.len(), 4 * n_points);
+
+            let script = script! {
+                for point in points.iter() {
+                    { *point }
+                }
+                { AirGadget::multi_shifted_mask_points(&mask, &domains, n_points) }
+                for point in expected.iter().rev() {
+                    { *point }
+                    { CirclePointGadget::equalverify() }
+                }
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
+
+    #[test]
+    fn test_check_composition_with_hints() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let fib = Fibonacci::new(5, M31::from_u32_unchecked(443693538));
+        let trace_poly = fib.get_trace().interpolate();
+
+        let script_check = AirGadget::check_composition_with_hints(2);
+
+        for _ in 0..10 {
+            let random_coeff = get_rand_qm31(&mut prng);
+            let z = CirclePoint {
+                x: get_rand_qm31(&mut prng),
+                y: get_rand_qm31(&mut prng),
+            };
+
+            let mask_values = ComponentVec(vec![vec![fib.air.mask_points(z)[0][0]
+                .iter()
+                .map(|p| trace_poly.eval_at_point(*p))
+                .collect_vec()]]);
+            let composition_oods_value =
+                fib.air
+                    .eval_composition_polynomial_at_point(z, &mask_values, random_coeff);
+
+            // the Fibonacci AIR accumulates the step constraint before the boundary constraint
+            let step = fib.air.component.step_constraint_eval_quotient_by_mask(
+                z,
+                mask_values[0][0][..].try_into().unwrap(),
+            );
+            let boundary = fib.air.component.boundary_constraint_eval_quotient_by_mask(
+                z,
+                mask_values[0][0][..1].try_into().unwrap(),
+            );
+            assert_eq!(
+                accumulate_quotients(random_coeff, &[step, boundary]),
+                composition_oods_value
+            );
+
+            let script = script! {
+                { CompositionHint { constraint_eval_quotients_by_mask: vec![step, boundary] } }
+                { composition_oods_value }
+                { random_coeff }
+                { script_check.clone() }
+                { boundary }
+                qm31_equalverify
+                { step }
+                qm31_equalverify
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            // quotients that do not satisfy the equation are rejected
+            let script = script! {
+                { CompositionHint { constraint_eval_quotients_by_mask: vec![boundary, step] } }
+                { composition_oods_value }
+                { random_coeff }
+                { script_check.clone() }
+                OP_2DROP OP_2DROP OP_2DROP OP_2DROP
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(!exec_result.success);
+        }
+    }
 }
@@ -0,0 +1,52 @@
+//! Export the verifier of a Fibonacci statement for review.
+//!
+//! Usage: `export <log_size> <claim> [asm|hex|annotated-hex]`, which prints the program ID and
+//! the leaves of the verifier program, in ASM by default. In ASM and in the annotated hex dump,
+//! each leaf is between `# begin leaf <i>` and `# end leaf <i>` comments.
+
+use bitcoin_circle_stark::export::{script_to_annotated_hex, script_to_asm, script_to_hex};
+use bitcoin_circle_stark::fibonacci::FibonacciConfig;
+use bitcoin_circle_stark::program::VerifierProgram;
+use bitcoin_circle_stark::simulator::concat_gadgets;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: export <log_size> <claim> [asm|hex|annotated-hex]";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let (Some(log_size), Some(claim)) = (
+        args.first().and_then(|arg| arg.parse::<u32>().ok()),
+        args.get(1).and_then(|arg| arg.parse::<u32>().ok()),
+    ) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    let format = args.get(2).map(String::as_str).unwrap_or("asm");
+    if args.len() > 3 || !["asm", "hex", "annotated-hex"].contains(&format) {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    }
+
+    let program = VerifierProgram::fibonacci(FibonacciConfig { log_size, claim });
+    let (script, gadgets) = concat_gadgets(
+        program
+            .leaves
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| (format!("leaf {}", i), leaf.clone()))
+            .collect(),
+    );
+
+    println!("# program id {}", program.program_id_hex());
+    match format {
+        // the raw hex of each leaf on its own line, as the leaves are separate scripts
+        "hex" => {
+            for leaf in program.leaves.iter() {
+                println!("{}", script_to_hex(leaf));
+            }
+        }
+        "annotated-hex" => print!("{}", script_to_annotated_hex(&script, &gadgets)),
+        _ => print!("{}", script_to_asm(&script, &gadgets)),
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,331 @@
+use crate::air::CompositionHint;
+use crate::channel::DrawHints;
+use crate::digest::CanonicalDigest;
+use crate::fibonacci::{verify_with_hints, CompositionCheck, FibonacciConfig, VerifierHints};
+use crate::fri::SortedQueriesHint;
+use crate::oods::OODSHint;
+use crate::pow::PoWHint;
+use crate::proof::{
+    digest_from_bytes, qm31_from_limbs, qm31_to_limbs, ProofFormat, SerializedProof,
+};
+use bitcoin::hex::DisplayHex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use stwo_prover::core::channel::BWSSha256Channel;
+use stwo_prover::core::prover::StarkProof;
+
+/// The version of the cached hints.
+///
+/// It is part of the cache key, so bumping it invalidates all existing entries.
+pub const HINT_CACHE_VERSION: u32 = 3;
+
+/// The verifier hints in a serializable layout, where every qm31 element is written as its four
+/// m31 limbs and every hash as its raw bytes.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SerializedVerifierHints {
+    /// Commitments from the proof.
+    pub commitments: [Vec<u8>; 2],
+    /// Hint for drawing random_coeff.
+    pub random_coeff_hint: DrawHints,
+    /// The OODS point x coordinate.
+    pub oods_x: [u32; 4],
+    /// The OODS point y coordinate.
+    pub oods_y: [u32; 4],
+    /// Hint for drawing the OODS point.
+    pub oods_draw_hint: DrawHints,
+    /// Trace oods values.
+    pub trace_oods_values: [[u32; 4]; 3],
+    /// Composition oods raw values.
+    pub composition_oods_values: [[u32; 4]; 4],
+    /// How the script checks the composition polynomial.
+    pub composition_check: CompositionCheck,
+    /// Constraint quotients for the composition hint.
+    pub constraint_eval_quotients_by_mask: Vec<[u32; 4]>,
+    /// Hint for drawing the second random_coeff.
+    pub random_coeff_hint2: DrawHints,
+    /// Hint for drawing circle_poly_alpha.
+    pub circle_poly_alpha_hint: DrawHints,
+    /// FRI commitments and hints for deriving the folding parameters.
+    pub fri_commitment_and_folding_hints: Vec<(Vec<u8>, DrawHints)>,
+    /// Last layer.
+    pub last_layer: [u32; 4],
+    /// PoW hint.
+    pub pow_hint: PoWHint,
+    /// Query sampling hints.
+    pub queries_hints: DrawHints,
+    /// Sorted and deduplicated queries.
+    pub sorted_queries: Vec<usize>,
+    /// Final channel value.
+    pub test_only: Vec<u8>,
+}
+
+impl From<&VerifierHints> for SerializedVerifierHints {
+    fn from(hints: &VerifierHints) -> Self {
+        Self {
+            commitments: [
+                hints.commitments[0].to_canonical_bytes().to_vec(),
+                hints.commitments[1].to_canonical_bytes().to_vec(),
+            ],
+            random_coeff_hint: hints.random_coeff_hint.clone(),
+            oods_x: qm31_to_limbs(&hints.oods_hint.x),
+            oods_y: qm31_to_limbs(&hints.oods_hint.y),
+            oods_draw_hint: hints.oods_hint.hint.clone(),
+            trace_oods_values: hints.trace_oods_values.map(|v| qm31_to_limbs(&v)),
+            composition_oods_values: hints.composition_oods_values.map(|v| qm31_to_limbs(&v)),
+            composition_check: hints.composition_check,
+            constraint_eval_quotients_by_mask: hints
+                .composition_hint
+                .constraint_eval_quotients_by_mask
+                .iter()
+                .map(qm31_to_limbs)
+                .collect(),
+            random_coeff_hint2: hints.random_coeff_hint2.clone(),
+            circle_poly_alpha_hint: hints.circle_poly_alpha_hint.clone(),
+            fri_commitment_and_folding_hints: hints
+                .fri_commitment_and_folding_hints
+                .iter()
+                .map(|(c, h)| (c.to_canonical_bytes().to_vec(), h.clone()))
+                .collect(),
+            last_layer: qm31_to_limbs(&hints.last_layer),
+            pow_hint: hints.pow_hint.clone(),
+            queries_hints: hints.queries_hints.clone(),
+            sorted_queries: hints.sorted_queries.queries.clone(),
+            test_only: hints.test_only.to_canonical_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<&SerializedVerifierHints> for VerifierHints {
+    type Error = io::Error;
+
+    fn try_from(hints: &SerializedVerifierHints) -> io::Result<Self> {
+        Ok(Self {
+            commitments: [
+                digest_from_bytes(&hints.commitments[0])?,
+                digest_from_bytes(&hints.commitments[1])?,
+            ],
+            random_coeff_hint: hints.random_coeff_hint.clone(),
+            oods_hint: OODSHint {
+                x: qm31_from_limbs(&hints.oods_x)?,
+                y: qm31_from_limbs(&hints.oods_y)?,
+                hint: hints.oods_draw_hint.clone(),
+            },
+            trace_oods_values: [
+                qm31_from_limbs(&hints.trace_oods_values[0])?,
+                qm31_from_limbs(&hints.trace_oods_values[1])?,
+                qm31_from_limbs(&hints.trace_oods_values[2])?,
+            ],
+            composition_oods_values: [
+                qm31_from_limbs(&hints.composition_oods_values[0])?,
+                qm31_from_limbs(&hints.composition_oods_values[1])?,
+                qm31_from_limbs(&hints.composition_oods_values[2])?,
+                qm31_from_limbs(&hints.composition_oods_values[3])?,
+            ],
+            composition_check: hints.composition_check,
+            composition_hint: CompositionHint {
+                constraint_eval_quotients_by_mask: hints
+                    .constraint_eval_quotients_by_mask
+                    .iter()
+                    .map(qm31_from_limbs)
+                    .collect::<io::Result<_>>()?,
+            },
+            random_coeff_hint2: hints.random_coeff_hint2.clone(),
+            circle_poly_alpha_hint: hints.circle_poly_alpha_hint.clone(),
+            fri_commitment_and_folding_hints: hints
+                .fri_commitment_and_folding_hints
+                .iter()
+                .map(|(c, h)| Ok((digest_from_bytes(c)?, h.clone())))
+                .collect::<io::Result<_>>()?,
+            last_layer: qm31_from_limbs(&hints.last_layer)?,
+            pow_hint: hints.pow_hint.clone(),
+            queries_hints: hints.queries_hints.clone(),
+            sorted_queries: SortedQueriesHint {
+                queries: hints.sorted_queries.clone(),
+            },
+            test_only: digest_from_bytes(&hints.test_only)?,
+        })
+    }
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+/// A content-addressed on-disk cache of the verifier hints.
+///
+/// An entry is keyed by the hash of the proof, the initial channel, and the configuration, so a
+/// cached entry can only be reused for exactly the same inputs.
+pub struct HintCache {
+    dir: PathBuf,
+}
+
+impl HintCache {
+    /// Open a cache stored in the given directory, which is created if it does not exist.
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    /// Compute the cache key of a proof, the initial channel, and the configuration.
+    pub fn key(
+        proof: &StarkProof,
+        channel: &BWSSha256Channel,
+        config: &FibonacciConfig,
+    ) -> io::Result<String> {
+        let proof_bytes = SerializedProof::new(proof, channel).to_bytes(ProofFormat::Bincode)?;
+        let config_bytes = bincode::serialize(config).map_err(invalid_data)?;
+
+        let mut hasher = Sha256::new();
+        Digest::update(&mut hasher, HINT_CACHE_VERSION.to_le_bytes());
+        Digest::update(&mut hasher, (proof_bytes.len() as u64).to_le_bytes());
+        Digest::update(&mut hasher, proof_bytes);
+        Digest::update(&mut hasher, config_bytes);
+
+        Ok(hasher.finalize().as_slice().to_lower_hex_string())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.bin", key))
+    }
+
+    /// Look up the hints of a key.
+    ///
+    /// An entry that cannot be decoded, e.g., one corrupted on disk, is treated as a miss, and it
+    /// is overwritten by the next [`HintCache::put`] of the key.
+    pub fn get(&self, key: &str) -> io::Result<Option<VerifierHints>> {
+        let path = self.path(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let bytes = fs::read(path)?;
+        Ok(bincode::deserialize::<SerializedVerifierHints>(&bytes)
+            .ok()
+            .and_then(|hints| VerifierHints::try_from(&hints).ok()))
+    }
+
+    /// Store the hints of a key.
+    pub fn put(&self, key: &str, hints: &VerifierHints) -> io::Result<()> {
+        let bytes =
+            bincode::serialize(&SerializedVerifierHints::from(hints)).map_err(invalid_data)?;
+
+        // write into a temporary file first so that an interrupted write never leaves a
+        // truncated entry behind
+        let tmp_path = self.dir.join(format!("{}.tmp", key));
+        fs::write(&tmp_path, bytes)?;
+        fs::rename(tmp_path, self.path(key))
+    }
+
+    /// Generate the verifier hints, reusing the cached ones if the same proof, initial channel,
+    /// and configuration have been seen before.
+    ///
+    /// Unlike [`verify_with_hints`], the channel is not advanced.
+    pub fn verify_with_hints(
+        &self,
+        proof: StarkProof,
+        config: &FibonacciConfig,
+        channel: &BWSSha256Channel,
+    ) -> io::Result<VerifierHints> {
+        let key = Self::key(&proof, channel, config)?;
+        if let Some(hints) = self.get(&key)? {
+            return Ok(hints);
+        }
+
+        let hints = verify_with_hints(proof, &config.air(), &mut channel.clone())
+            .map_err(|err| invalid_data(format!("{:?}", err)))?;
+        self.put(&key, &hints)?;
+
+        Ok(hints)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::HintCache;
+    use crate::fibonacci::FibonacciConfig;
+    use crate::proof::{export_proof, import_proof, ProofFormat};
+    use crate::tests_utils::temp_dir::TempDir;
+    use crate::treepp::*;
+    use std::fs;
+    use stwo_prover::core::channel::{BWSSha256Channel, Channel};
+    use stwo_prover::core::fields::m31::BaseField;
+    use stwo_prover::core::fields::IntoSlice;
+    use stwo_prover::core::prover::prove;
+    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hasher;
+    use stwo_prover::core::vcs::hasher::Hasher;
+    use stwo_prover::examples::fibonacci::Fibonacci;
+
+    #[test]
+    fn test_hint_cache() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let fib = Fibonacci::new(config.log_size, config.claim_m31());
+
+        let trace = fib.get_trace();
+        let channel =
+            &mut BWSSha256Channel::new(BWSSha256Hasher::hash(BaseField::into_slice(&[fib
+                .air
+                .component
+                .claim])));
+        let channel_clone = channel.clone();
+        let proof = prove(&fib.air, channel, vec![trace]).unwrap();
+
+        let temp_dir = TempDir::new("test-hint-cache");
+        let dir = temp_dir.path();
+        let cache = HintCache::new(dir).unwrap();
+
+        let key = HintCache::key(&proof, &channel_clone, &config).unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+
+        // a different configuration must not hit the same entry
+        let other_config = FibonacciConfig {
+            log_size: 5,
+            claim: 1,
+        };
+        assert_ne!(
+            HintCache::key(&proof, &channel_clone, &other_config).unwrap(),
+            key
+        );
+
+        let proof_bytes = export_proof(&proof, &channel_clone, ProofFormat::Bincode).unwrap();
+
+        // the first call generates the hints and the second call reads them from the cache
+        let generated = cache
+            .verify_with_hints(proof, &config, &channel_clone)
+            .unwrap();
+        assert!(cache.get(&key).unwrap().is_some());
+
+        let (proof, _) = import_proof(&proof_bytes, ProofFormat::Bincode).unwrap();
+        let cached = cache
+            .verify_with_hints(proof, &config, &channel_clone)
+            .unwrap();
+
+        assert_eq!(
+            script! { { generated } }.as_bytes(),
+            script! { { cached } }.as_bytes()
+        );
+
+        // a truncated entry is a miss, and the hints are generated again
+        let path = dir.join(format!("{}.bin", key));
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(cache.get(&key).unwrap().is_none());
+
+        let (proof, _) = import_proof(&proof_bytes, ProofFormat::Bincode).unwrap();
+        let regenerated = cache
+            .verify_with_hints(proof, &config, &channel_clone)
+            .unwrap();
+        assert_eq!(
+            script! { { generated } }.as_bytes(),
+            script! { { regenerated } }.as_bytes()
+        );
+        assert!(cache.get(&key).unwrap().is_some());
+    }
+}
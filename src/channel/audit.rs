@@ -0,0 +1,173 @@
+use crate::channel::{ChannelWithHint, DrawHints, Sha256Channel};
+use crate::simulator::simulate;
+use crate::treepp::*;
+use crate::utils::hash_qm31;
+use bitcoin::opcodes::all::OP_SHA256;
+use stwo_prover::core::channel::Channel;
+use stwo_prover::core::fields::qm31::QM31;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// An operation on the channel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelOp {
+    /// Absorb a digest.
+    MixDigest,
+    /// Absorb a qm31 element.
+    MixFelt,
+    /// Draw a qm31 element.
+    DrawFelt,
+    /// Draw queries.
+    DrawQueries,
+}
+
+/// An entry of the transcript.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// The operation.
+    pub op: ChannelOp,
+    /// The bytes concatenated with the channel digest: the digest for [`ChannelOp::MixDigest`],
+    /// the hash of the element for [`ChannelOp::MixFelt`], and nothing for the draws.
+    pub absorbed: Vec<u8>,
+    /// The channel digest after the operation.
+    pub digest: BWSSha256Hash,
+}
+
+/// A channel that records every operation in a transcript.
+pub struct AuditedChannel {
+    /// The channel.
+    pub channel: Sha256Channel,
+    /// The transcript, in order.
+    pub transcript: Vec<TranscriptEntry>,
+}
+
+impl AuditedChannel {
+    /// Start recording from the given channel.
+    pub fn new(channel: Sha256Channel) -> Self {
+        Self {
+            channel,
+            transcript: vec![],
+        }
+    }
+
+    fn record(&mut self, op: ChannelOp, absorbed: Vec<u8>) {
+        self.transcript.push(TranscriptEntry {
+            op,
+            absorbed,
+            digest: self.channel.digest,
+        });
+    }
+
+    /// Absorb a digest.
+    pub fn mix_digest(&mut self, digest: BWSSha256Hash) {
+        self.channel.mix_digest(digest);
+        self.record(ChannelOp::MixDigest, digest.as_ref().to_vec());
+    }
+
+    /// Absorb qm31 elements, one at a time.
+    pub fn mix_felts(&mut self, felts: &[QM31]) {
+        for felt in felts.iter() {
+            self.channel.mix_felts(&[*felt]);
+            self.record(ChannelOp::MixFelt, hash_qm31(felt).to_vec());
+        }
+    }
+
+    /// Draw a qm31 element and compute the hints.
+    pub fn draw_felt_and_hints(&mut self) -> (QM31, DrawHints) {
+        let res = self.channel.draw_felt_and_hints();
+        self.record(ChannelOp::DrawFelt, vec![]);
+        res
+    }
+
+    /// Draw queries and compute the hints.
+    pub fn draw_queries_and_hints(&mut self, m: usize, logn: usize) -> (Vec<usize>, DrawHints) {
+        let res = self.channel.draw_queries_and_hints(m, logn);
+        self.record(ChannelOp::DrawQueries, vec![]);
+        res
+    }
+}
+
+/// The outputs of all the `OP_SHA256` in the execution of a script with the witness, in order.
+///
+/// The channel digests of the script transcript are among them, along with the other hashes
+/// that the script computes.
+pub fn script_sha256_outputs(script: Script, witness: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    let mut outputs = vec![];
+    simulate(script, witness, |step| {
+        if step.opcode == Some(OP_SHA256) {
+            outputs.extend(step.top.clone());
+        }
+    });
+    outputs
+}
+
+/// The index of the first entry of the transcript whose digest the script never reaches, after
+/// the digest of the previous entry, or `None` if the script follows the whole transcript.
+pub fn first_divergence(
+    transcript: &[TranscriptEntry],
+    sha256_outputs: &[Vec<u8>],
+) -> Option<usize> {
+    let mut outputs = sha256_outputs.iter();
+    transcript
+        .iter()
+        .position(|entry| !outputs.any(|output| output.as_slice() == entry.digest.as_ref()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::channel::{
+        first_divergence, script_sha256_outputs, AuditedChannel, ChannelOp, Sha256Channel,
+        Sha256ChannelGadget,
+    };
+    use crate::treepp::*;
+    use crate::utils::{get_rand_bws_sha256_hash, get_rand_qm31};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_transcript_divergence() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let init_state = get_rand_bws_sha256_hash(&mut prng);
+        let commitment = get_rand_bws_sha256_hash(&mut prng);
+        let felt = get_rand_qm31(&mut prng);
+
+        let mut channel = AuditedChannel::new(Sha256Channel::new(init_state));
+        channel.mix_digest(commitment);
+        let (_, hint) = channel.draw_felt_and_hints();
+        channel.mix_felts(&[felt]);
+
+        assert_eq!(
+            channel
+                .transcript
+                .iter()
+                .map(|entry| entry.op)
+                .collect::<Vec<_>>(),
+            vec![
+                ChannelOp::MixDigest,
+                ChannelOp::DrawFelt,
+                ChannelOp::MixFelt
+            ]
+        );
+
+        let script_for = |commitment| {
+            script! {
+                { hint.clone() }
+                { felt }
+                { commitment }
+                { init_state }
+                { Sha256ChannelGadget::mix_digest() }
+                { Sha256ChannelGadget::draw_felt_with_hint() }
+                OP_2DROP OP_2DROP
+                { Sha256ChannelGadget::mix_felt() }
+            }
+        };
+
+        let outputs = script_sha256_outputs(script_for(commitment), vec![]);
+        assert_eq!(first_divergence(&channel.transcript, &outputs), None);
+
+        // the script absorbs a different commitment, so it diverges from the first operation
+        let outputs =
+            script_sha256_outputs(script_for(get_rand_bws_sha256_hash(&mut prng)), vec![]);
+        assert_eq!(first_divergence(&channel.transcript, &outputs), Some(0));
+    }
+}
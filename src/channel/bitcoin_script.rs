@@ -3,6 +3,11 @@ use crate::utils::{hash_felt_gadget, trim_m31_gadget};
 use rust_bitcoin_m31::MOD;
 
 /// Gadget for a channel.
+///
+/// Each absorption is a single `OP_CAT OP_SHA256` over the 64-byte input, which is already the
+/// cheapest way to hash in script: `OP_SHA256` does not expose the midstate, so a hinted midstate
+/// for the shared prefix could only be checked by emulating the compression function in script,
+/// which costs far more than it saves.
 pub struct Sha256ChannelGadget;
 
 impl Sha256ChannelGadget {
@@ -6,9 +6,12 @@ use stwo_prover::core::channel::Channel;
 use stwo_prover::core::fields::m31::M31;
 use stwo_prover::core::fields::qm31::QM31;
 
+mod audit;
 mod bitcoin_script;
 use crate::treepp::pushable::{Builder, Pushable};
+pub use audit::*;
 pub use bitcoin_script::*;
+use serde::{Deserialize, Serialize};
 
 pub use stwo_prover::core::channel::BWSSha256Channel as Sha256Channel;
 use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
@@ -91,7 +94,7 @@ fn generate_hints(m: usize, extract: &[u8]) -> (Vec<M31>, DrawHints) {
 }
 
 /// Basic hint structure for extracting a single qm31 element.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum BitcoinIntegerEncodedData {
     /// negative zero (will be represented by 0x80).
     NegativeZero,
@@ -116,7 +119,7 @@ impl Pushable for BitcoinIntegerEncodedData {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 /// Hints for drawing m31 elements.
 pub struct DrawHints(pub Vec<BitcoinIntegerEncodedData>, pub Vec<u8>);
 
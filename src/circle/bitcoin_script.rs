@@ -239,6 +239,7 @@ mod test {
     use std::ops::{Add, Neg};
     use stwo_prover::core::circle::CirclePoint;
 
+    use crate::tests_utils::gadget::run_gadget;
     use crate::{tests_utils::report::report_bitcoin_script_size, treepp::*};
     use rand::{RngCore, SeedableRng};
     use rand_chacha::ChaCha20Rng;
@@ -322,17 +323,7 @@ mod test {
             let add_script = CirclePointGadget::add_constant_point(&b);
             total_len += add_script.len();
 
-            let script = script! {
-                { a.x }
-                { a.y }
-                { add_script.clone() }
-                { c.x }
-                { c.y }
-                { CirclePointGadget::equalverify() }
-                OP_TRUE
-            };
-            let exec_result = execute_script(script);
-            assert!(exec_result.success);
+            run_gadget!(add_script, [a], [c]);
         }
         report_bitcoin_script_size("CirclePoint", "add_constant_point", total_len / 100);
     }
@@ -358,17 +349,7 @@ mod test {
             let add_script = CirclePointGadget::add_constant_m31_point(&b);
             total_len += add_script.len();
 
-            let script = script! {
-                { a.x }
-                { a.y }
-                { add_script.clone() }
-                { c.x }
-                { c.y }
-                { CirclePointGadget::equalverify() }
-                OP_TRUE
-            };
-            let exec_result = execute_script(script);
-            assert!(exec_result.success);
+            run_gadget!(add_script, [a], [c]);
         }
         report_bitcoin_script_size("CirclePoint", "add_constant_m31_point", total_len / 100);
     }
@@ -385,15 +366,7 @@ mod test {
             let a = get_rand_qm31(&mut prng);
             let double_a = a.square().double().add(QM31::one().neg());
 
-            let script = script! {
-                { a }
-                { double_x_script.clone() }
-                { double_a }
-                qm31_equalverify
-                OP_TRUE
-            };
-            let exec_result = execute_script(script);
-            assert!(exec_result.success);
+            run_gadget!(double_x_script.clone(), [a], [double_a]);
         }
     }
 }
@@ -0,0 +1,255 @@
+use crate::treepp::*;
+use bitcoin::hex::{DisplayHex, FromHex};
+use bitcoin::script::Instruction;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// The size of a digest, in bytes.
+pub const DIGEST_SIZE: usize = 32;
+
+/// A digest with a canonical encoding: the bytes in the order in which SHA256 outputs them.
+///
+/// This is the order in which the channel absorbs a digest, in which a Merkle node hashes its
+/// children, and in which a script pushes a digest, so that the same bytes are compared on both
+/// sides. It is never reversed, unlike the display of double-SHA256 hashes such as txids.
+pub trait CanonicalDigest {
+    /// The canonical bytes of the digest.
+    fn to_canonical_bytes(&self) -> [u8; DIGEST_SIZE];
+}
+
+impl CanonicalDigest for BWSSha256Hash {
+    fn to_canonical_bytes(&self) -> [u8; DIGEST_SIZE] {
+        self.as_ref()
+            .try_into()
+            .expect("a SHA256 digest has 32 bytes")
+    }
+}
+
+impl CanonicalDigest for [u8; DIGEST_SIZE] {
+    fn to_canonical_bytes(&self) -> [u8; DIGEST_SIZE] {
+        *self
+    }
+}
+
+/// Read a digest from its canonical bytes.
+pub fn digest_from_canonical_bytes(bytes: &[u8]) -> Result<BWSSha256Hash, String> {
+    if bytes.len() != DIGEST_SIZE {
+        return Err(format!(
+            "a digest has {} bytes, got {}",
+            DIGEST_SIZE,
+            bytes.len()
+        ));
+    }
+    Ok(BWSSha256Hash::from(bytes.to_vec()))
+}
+
+/// Push a digest in its canonical encoding.
+pub fn push_digest<D: CanonicalDigest>(digest: &D) -> Script {
+    script! {
+        { digest.to_canonical_bytes().to_vec() }
+    }
+}
+
+/// The canonical bytes of a digest, in lowercase hex.
+pub fn digest_to_hex<D: CanonicalDigest>(digest: &D) -> String {
+    digest.to_canonical_bytes().to_lower_hex_string()
+}
+
+/// Read a digest from its canonical bytes in hex.
+pub fn digest_from_hex(hex: &str) -> Result<BWSSha256Hash, String> {
+    digest_from_canonical_bytes(&Vec::<u8>::from_hex(hex).map_err(|err| err.to_string())?)
+}
+
+/// An issue with a push of 32 bytes, see [`check_digest_byte_order`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DigestPushIssue {
+    /// The push is the reversal of the expected digest of this index.
+    Reversed(usize),
+    /// The push is none of the expected digests, e.g., a key, or a digest that is computed
+    /// somewhere else.
+    Unexpected,
+}
+
+/// Check the byte order of the pushes of 32 bytes in a script against the digests that the
+/// script is expected to push, and return the index of each instruction that does not push one
+/// of them in its canonical encoding, with the issue.
+///
+/// This only looks at the bytes: a push of the canonical bytes that does not go through
+/// [`push_digest`] is indistinguishable from one that does, and is not reported.
+pub fn check_digest_byte_order<D: CanonicalDigest>(
+    script: &Script,
+    expected: &[D],
+) -> Vec<(usize, DigestPushIssue)> {
+    let expected = expected
+        .iter()
+        .map(|digest| digest.to_canonical_bytes())
+        .collect::<Vec<_>>();
+
+    let mut issues = vec![];
+    for (index, instruction) in script.instructions().enumerate() {
+        let Ok(Instruction::PushBytes(bytes)) = instruction else {
+            continue;
+        };
+        if bytes.len() != DIGEST_SIZE {
+            continue;
+        }
+        let bytes = bytes.as_bytes();
+        if expected.iter().any(|digest| digest.as_slice() == bytes) {
+            continue;
+        }
+
+        let reversed = expected
+            .iter()
+            .position(|digest| digest.iter().rev().eq(bytes.iter()));
+        issues.push((
+            index,
+            match reversed {
+                Some(i) => DigestPushIssue::Reversed(i),
+                None => DigestPushIssue::Unexpected,
+            },
+        ));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod test {
+    use crate::digest::{
+        check_digest_byte_order, digest_from_canonical_bytes, digest_from_hex, digest_to_hex,
+        push_digest, CanonicalDigest, DigestPushIssue, DIGEST_SIZE,
+    };
+    use crate::fibonacci::{FibonacciConfig, FibonacciVerifierGadget};
+    use crate::fri::FRIGadget;
+    use crate::merkle_tree::MerkleTree;
+    use crate::treepp::*;
+    use crate::twiddle_merkle_tree::{TwiddleMerkleTree, TWIDDLE_MERKLE_TREE_ROOT_4};
+    use crate::utils::{get_rand_bws_sha256_hash, get_rand_qm31};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_canonical_digest() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        let digest = get_rand_bws_sha256_hash(&mut prng);
+
+        let bytes = digest.to_canonical_bytes();
+        assert_eq!(bytes.as_slice(), digest.as_ref());
+        assert_eq!(digest_from_canonical_bytes(&bytes).unwrap(), digest);
+        assert!(digest_from_canonical_bytes(&bytes[1..]).is_err());
+        assert_eq!(digest_from_hex(&digest_to_hex(&digest)).unwrap(), digest);
+
+        // the pushable digests use the canonical encoding
+        assert_eq!(push_digest(&digest), script! { { digest } });
+        assert_eq!(push_digest(&bytes), push_digest(&digest));
+    }
+
+    #[test]
+    fn test_check_digest_byte_order() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        let digest = get_rand_bws_sha256_hash(&mut prng);
+        let other = get_rand_bws_sha256_hash(&mut prng);
+
+        let mut reversed = digest.to_canonical_bytes();
+        reversed.reverse();
+
+        let script = script! {
+            { push_digest(&digest) }
+            OP_DROP
+            { reversed.to_vec() }
+            { other }
+            OP_2DROP
+        };
+        assert_eq!(
+            check_digest_byte_order(&script, &[digest]),
+            vec![
+                (2, DigestPushIssue::Reversed(0)),
+                (3, DigestPushIssue::Unexpected)
+            ]
+        );
+        assert_eq!(
+            check_digest_byte_order(&script, &[digest, other]),
+            vec![(2, DigestPushIssue::Reversed(0))]
+        );
+
+        // the verifier pushes the initial channel in its canonical encoding
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let channel = config.initial_channel();
+        let verifier =
+            FibonacciVerifierGadget::run_verifier(&channel, config.log_size, config.claim_m31());
+        assert!(check_digest_byte_order(&verifier, &[channel.digest])
+            .iter()
+            .all(|(_, issue)| !matches!(issue, DigestPushIssue::Reversed(_))));
+    }
+
+    #[test]
+    fn test_gadgets_digest_byte_order() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        // every gadget and hint that pushes a digest, with the digests that it pushes, so that a
+        // push of one of them in another byte order shows up as an issue here
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let channel = config.initial_channel();
+        let channel_init_state = get_rand_bws_sha256_hash(&mut prng).to_canonical_bytes();
+
+        let merkle_tree = MerkleTree::new(
+            (0..1 << 4)
+                .map(|_| get_rand_qm31(&mut prng))
+                .collect::<Vec<_>>(),
+        );
+        let merkle_tree_proof = merkle_tree.query(prng.gen_range(0..1 << 4));
+
+        let twiddle_merkle_tree = TwiddleMerkleTree::new(4);
+        let twiddle_merkle_tree_proof = twiddle_merkle_tree.query(prng.gen_range(0..1 << 5));
+
+        let gadgets: Vec<(&str, Script, Vec<[u8; DIGEST_SIZE]>)> = vec![
+            (
+                "FibonacciVerifierGadget::run_verifier",
+                FibonacciVerifierGadget::run_verifier(
+                    &channel,
+                    config.log_size,
+                    config.claim_m31(),
+                ),
+                vec![channel.digest.to_canonical_bytes()],
+            ),
+            (
+                "FRIGadget::check_fiat_shamir",
+                FRIGadget::check_fiat_shamir(&channel_init_state, 5, 4),
+                vec![channel_init_state],
+            ),
+            (
+                "FRIGadget::check_twiddle_merkle_tree_proof",
+                FRIGadget::check_twiddle_merkle_tree_proof(5, TWIDDLE_MERKLE_TREE_ROOT_4),
+                vec![TWIDDLE_MERKLE_TREE_ROOT_4],
+            ),
+            (
+                "FRIGadget::verify_single_query",
+                FRIGadget::verify_single_query(5, TWIDDLE_MERKLE_TREE_ROOT_4),
+                vec![TWIDDLE_MERKLE_TREE_ROOT_4],
+            ),
+            (
+                "MerkleTreeProof",
+                script! { { merkle_tree_proof.clone() } },
+                merkle_tree_proof.siblings.clone(),
+            ),
+            (
+                "TwiddleMerkleTreeProof",
+                script! { { &twiddle_merkle_tree_proof } },
+                twiddle_merkle_tree_proof.siblings.clone(),
+            ),
+        ];
+
+        for (name, script, expected) in gadgets.iter() {
+            assert_eq!(
+                check_digest_byte_order(script, expected),
+                vec![],
+                "{} pushes a digest that is not in its canonical encoding",
+                name
+            );
+        }
+    }
+}
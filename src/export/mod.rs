@@ -0,0 +1,125 @@
+use crate::simulator::GadgetRange;
+use crate::treepp::*;
+
+// The instructions of the script, each as the range of its bytes, with the markers of the
+// gadget boundaries that come right before it, and the markers after the last instruction.
+fn annotate<F: FnMut(&mut String, usize, &[u8])>(
+    script: &Script,
+    gadgets: &[GadgetRange],
+    mut render: F,
+) -> String {
+    let mut markers = gadgets
+        .iter()
+        .enumerate()
+        .flat_map(|(i, gadget)| {
+            [
+                (gadget.start, 2 * i, format!("# begin {}", gadget.name)),
+                (gadget.end, 2 * i + 1, format!("# end {}", gadget.name)),
+            ]
+        })
+        .collect::<Vec<_>>();
+    markers.sort_by_key(|(index, order, _)| (*index, *order));
+    let mut markers = markers.into_iter().peekable();
+
+    let bytes = script.as_bytes();
+    let offsets = script
+        .instruction_indices()
+        .map(|res| res.map(|(offset, _)| offset))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("the script should be well-formed");
+
+    let mut res = String::new();
+    for (index, &offset) in offsets.iter().enumerate() {
+        while let Some((_, _, marker)) = markers.next_if(|(start, _, _)| *start <= index) {
+            res.push_str(&marker);
+            res.push('\n');
+        }
+        let end = offsets.get(index + 1).copied().unwrap_or(bytes.len());
+        render(&mut res, offset, &bytes[offset..end]);
+        res.push('\n');
+    }
+    for (_, _, marker) in markers {
+        res.push_str(&marker);
+        res.push('\n');
+    }
+    res
+}
+
+/// Render the script as ASM, one instruction per line, with comments at the boundaries of the
+/// gadgets (see [`crate::simulator::concat_gadgets`]).
+///
+/// The boundaries are the ones of the named pieces that the script is concatenated from, as an
+/// assembled script is a flat list of bytes. The `export` binary renders the leaves of a verifier
+/// program in this way, with one piece per leaf.
+pub fn script_to_asm(script: &Script, gadgets: &[GadgetRange]) -> String {
+    annotate(script, gadgets, |res, _, instruction| {
+        res.push_str(&Script::from_bytes(instruction.to_vec()).to_asm_string());
+    })
+}
+
+/// Render the script as a hex dump, one instruction per line with its offset and its ASM as a
+/// comment, and with comments at the boundaries of the gadgets.
+pub fn script_to_annotated_hex(script: &Script, gadgets: &[GadgetRange]) -> String {
+    annotate(script, gadgets, |res, offset, instruction| {
+        let instruction = Script::from_bytes(instruction.to_vec());
+        res.push_str(&format!(
+            "{:06x}  {}  # {}",
+            offset,
+            instruction.to_hex_string(),
+            instruction.to_asm_string()
+        ));
+    })
+}
+
+/// Render the script as raw hex.
+pub fn script_to_hex(script: &Script) -> String {
+    script.to_hex_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::export::{script_to_annotated_hex, script_to_asm, script_to_hex};
+    use crate::simulator::concat_gadgets;
+    use crate::treepp::*;
+
+    #[test]
+    fn test_export() {
+        let (script, gadgets) = concat_gadgets(vec![
+            ("push", script! { 2 { vec![0xab; 2] } }),
+            ("empty", script! {}),
+            ("check", script! { OP_DROP 2 OP_EQUAL }),
+        ]);
+
+        assert_eq!(
+            script_to_asm(&script, &gadgets),
+            "# begin push\n\
+             OP_PUSHNUM_2\n\
+             OP_PUSHBYTES_2 abab\n\
+             # end push\n\
+             # begin empty\n\
+             # end empty\n\
+             # begin check\n\
+             OP_DROP\n\
+             OP_PUSHNUM_2\n\
+             OP_EQUAL\n\
+             # end check\n"
+        );
+
+        assert_eq!(
+            script_to_annotated_hex(&script, &gadgets[2..]),
+            "000000  52  # OP_PUSHNUM_2\n\
+             000001  02abab  # OP_PUSHBYTES_2 abab\n\
+             # begin check\n\
+             000004  75  # OP_DROP\n\
+             000005  52  # OP_PUSHNUM_2\n\
+             000006  87  # OP_EQUAL\n\
+             # end check\n"
+        );
+
+        assert_eq!(script_to_hex(&script), "5202abab755287");
+        assert_eq!(
+            script_to_asm(&script, &[]).lines().count(),
+            script.instructions().count()
+        );
+    }
+}
@@ -29,8 +29,26 @@ impl FibonacciCompositionGadget {
     /// Output:
     /// - num/denom
     ///
-    #[allow(dead_code)]
     fn step_constraint_eval_quotient_by_mask(log_size: u32) -> Script {
+        script! {
+            { Self::step_constraint_num_and_denom(log_size) }
+            { Self::quotient_from_hint() }
+        }
+    }
+
+    /// Compute the numerator and the denominator of the step constraint.
+    ///
+    /// Input:
+    /// - f(z)
+    /// - f(Gz)
+    /// - f(G^2 z)
+    /// - z.x
+    /// - z.y
+    ///
+    /// Output:
+    /// - num
+    /// - denom
+    fn step_constraint_num_and_denom(log_size: u32) -> Script {
         let constraint_zero_domain = Coset::subgroup(log_size);
 
         script! {
@@ -71,15 +89,6 @@ impl FibonacciCompositionGadget {
             qm31_fromaltstack
             qm31_fromaltstack
             { ConstraintsGadget::coset_vanishing(constraint_zero_domain) } // denom
-
-            qm31_from_bottom // num/denom
-            qm31_dup
-            qm31_toaltstack
-
-            qm31_mul // denom*(num/denom)
-
-            qm31_equalverify
-            qm31_fromaltstack // num/denom
         }
     }
 
@@ -97,6 +106,23 @@ impl FibonacciCompositionGadget {
     /// - num/denom
     ///
     fn boundary_constraint_eval_quotient_by_mask(log_size: u32, claim: M31) -> Script {
+        script! {
+            { Self::boundary_constraint_num_and_denom(log_size, claim) }
+            { Self::quotient_from_hint() }
+        }
+    }
+
+    /// Compute the numerator and the denominator of the boundary constraint.
+    ///
+    /// Input:
+    /// - f(z)
+    /// - z.x
+    /// - z.y
+    ///
+    /// Output:
+    /// - num
+    /// - denom
+    fn boundary_constraint_num_and_denom(log_size: u32, claim: M31) -> Script {
         let constraint_zero_domain = Coset::subgroup(log_size);
         let p = constraint_zero_domain.at(constraint_zero_domain.size() - 1);
         script! {
@@ -116,7 +142,23 @@ impl FibonacciCompositionGadget {
             qm31_fromaltstack // bring back z.x from altstack
             qm31_fromaltstack // bring back z.y from altstack
             { ConstraintsGadget::pair_vanishing(p.into_ef(), CirclePoint::zero())} // denom
+        }
+    }
 
+    /// Pull the quotient num/denom from the hint and check it against the numerator and the
+    /// denominator.
+    ///
+    /// Hint:
+    /// - num/denom
+    ///
+    /// Input:
+    /// - num
+    /// - denom
+    ///
+    /// Output:
+    /// - num/denom
+    fn quotient_from_hint() -> Script {
+        script! {
             qm31_from_bottom // pull num/denom from hint
 
             qm31_dup
@@ -162,6 +204,43 @@ impl FibonacciCompositionGadget {
             qm31_add
         }
     }
+
+    /// Check the constraint quotients, which the script has already recombined against the
+    /// composition OODS value with [`crate::air::AirGadget::check_composition_with_hints`],
+    /// against the mask values.
+    ///
+    /// Input:
+    /// - f(z)
+    /// - f(Gz)
+    /// - f(G^2 z)
+    /// - z.x
+    /// - z.y
+    /// - step quotient
+    /// - boundary quotient
+    ///
+    /// Output:
+    ///
+    pub(crate) fn check_quotients_against_mask(log_size: u32, claim: M31) -> Script {
+        script! {
+            qm31_toaltstack // store the boundary quotient in altstack
+
+            // copy f(z), z.x, z.y
+            { qm31_copy(5) }
+            { qm31_copy(3) }
+            { qm31_copy(3) }
+            { Self::boundary_constraint_num_and_denom(log_size, claim) }
+            qm31_fromaltstack
+            qm31_mul // denom*boundary
+            qm31_equalverify // check that num == denom*boundary
+
+            qm31_toaltstack // store the step quotient in altstack
+
+            { Self::step_constraint_num_and_denom(log_size) }
+            qm31_fromaltstack
+            qm31_mul // denom*step
+            qm31_equalverify // check that num == denom*step
+        }
+    }
 }
 
 #[cfg(test)]
@@ -271,4 +350,73 @@ mod test {
             assert!(exec_result.success);
         }
     }
+
+    #[test]
+    fn test_check_quotients_against_mask() {
+        let log_size = 5;
+        let claim = M31::from_u32_unchecked(443693538);
+
+        let fib = Fibonacci::new(log_size, claim);
+        let trace_poly = fib.get_trace().interpolate();
+
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let check_script =
+            FibonacciCompositionGadget::check_quotients_against_mask(log_size, claim);
+        report_bitcoin_script_size(
+            "Fibonacci",
+            format!("check_quotients_against_mask(log_size={})", log_size).as_str(),
+            check_script.len(),
+        );
+
+        for _ in 0..20 {
+            let z = CirclePoint {
+                x: get_rand_qm31(&mut prng),
+                y: get_rand_qm31(&mut prng),
+            };
+
+            let mask_values = fib.air.mask_points(z)[0][0]
+                .iter()
+                .map(|p| trace_poly.eval_at_point(*p))
+                .collect_vec();
+
+            let step = fib
+                .air
+                .component
+                .step_constraint_eval_quotient_by_mask(z, mask_values[..].try_into().unwrap());
+            let boundary = fib
+                .air
+                .component
+                .boundary_constraint_eval_quotient_by_mask(z, mask_values[..1].try_into().unwrap());
+
+            let script = script! {
+                { mask_values[0] }
+                { mask_values[1] }
+                { mask_values[2] }
+                { z.x }
+                { z.y }
+                { step }
+                { boundary }
+                { check_script.clone() }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            // a quotient that does not match the mask values is rejected
+            let script = script! {
+                { mask_values[0] }
+                { mask_values[1] }
+                { mask_values[2] }
+                { z.x }
+                { z.y }
+                { step }
+                { boundary + step }
+                { check_script.clone() }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(!exec_result.success);
+        }
+    }
 }
@@ -2,10 +2,14 @@ use crate::air::AirGadget;
 use crate::channel::Sha256ChannelGadget;
 use crate::circle::CirclePointGadget;
 use crate::fibonacci::bitcoin_script::composition::FibonacciCompositionGadget;
+use crate::fibonacci::CompositionCheck;
+use crate::fri::QueriesGadget;
 use crate::oods::OODSGadget;
 use crate::pow::PowGadget;
 use crate::{treepp::*, OP_HINT};
-use rust_bitcoin_m31::{qm31_copy, qm31_drop, qm31_dup, qm31_equalverify, qm31_from_bottom};
+use rust_bitcoin_m31::{
+    qm31_copy, qm31_drop, qm31_dup, qm31_equalverify, qm31_from_bottom, qm31_roll,
+};
 use stwo_prover::core::channel::BWSSha256Channel;
 use stwo_prover::core::fields::m31::M31;
 use stwo_prover::core::poly::circle::CanonicCoset;
@@ -13,14 +17,30 @@ use stwo_prover::core::prover::{LOG_BLOWUP_FACTOR, N_QUERIES, PROOF_OF_WORK_BITS
 
 mod composition;
 
-const FIB_LOG_SIZE: u32 = 5;
-
 /// A verifier for the Fibonacci proof.
 pub struct FibonacciVerifierGadget;
 
 impl FibonacciVerifierGadget {
-    /// Run the verifier in the Bitcoin script.
-    pub fn run_verifier(channel: &BWSSha256Channel) -> Script {
+    /// Run the verifier in the Bitcoin script, for a Fibonacci trace of size 2^log_size ending
+    /// with `claim`.
+    pub fn run_verifier(channel: &BWSSha256Channel, log_size: u32, claim: M31) -> Script {
+        Self::run_verifier_with_composition_check(
+            channel,
+            log_size,
+            claim,
+            CompositionCheck::default(),
+        )
+    }
+
+    /// Run the verifier in the Bitcoin script, checking the composition polynomial as set by
+    /// `composition_check`. The hints are generated by
+    /// [`crate::fibonacci::verify_with_hints_and_composition_check`] with the same option.
+    pub fn run_verifier_with_composition_check(
+        channel: &BWSSha256Channel,
+        log_size: u32,
+        claim: M31,
+        composition_check: CompositionCheck,
+    ) -> Script {
         script! {
             // push the initial channel
             { channel.digest }
@@ -40,14 +60,14 @@ impl FibonacciVerifierGadget {
             OP_DUP OP_ROT
             { Sha256ChannelGadget::mix_digest() }
 
-            // draw the OODS point
+            // draw the OODS point, as `FibonacciConfig::N_OODS_POINTS` is one
             { OODSGadget::get_random_point() }
 
             // stack: c1, random_coeff (4), c2, channel_digest, oods point (8)
             { CirclePointGadget::dup() }
 
             // mask the points
-            { AirGadget::shifted_mask_points(&vec![vec![0, 1, 2]], &[CanonicCoset::new(FIB_LOG_SIZE)]) }
+            { AirGadget::shifted_mask_points(&vec![vec![0, 1, 2]], &[CanonicCoset::new(log_size)]) }
 
             // pull trace oods values from the hint
             for _ in 0..3 {
@@ -102,30 +122,56 @@ impl FibonacciVerifierGadget {
             //    channel_digest
 
             64 OP_ROLL OP_TOALTSTACK
-            { qm31_copy(16) }
-            { qm31_copy(8) }
-            { qm31_copy(8) }
-            { qm31_copy(8) }
-            { qm31_copy(19) }
-            { qm31_copy(19) }
-
-            // stack:
-            //    c1, random_coeff (4), oods point (8),
-            //    masked points (3 * 8 = 24)
-            //    trace oods values (3 * 4 = 12)
-            //    composition odds raw values (4 * 4 = 16)
-            //    composition odds value (4)
-            //
-            //    random_coeff (4)
-            //    trace oods values (3 * 4 = 12)
-            //    oods point (8)
-            //
-            // altstack:
-            //    channel_digest, c2
 
-            { FibonacciCompositionGadget::eval_composition_polynomial_at_point(FIB_LOG_SIZE, M31::from_u32_unchecked(443693538)) }
-
-            qm31_equalverify
+            if composition_check == CompositionCheck::Evaluated {
+                { qm31_copy(16) }
+                { qm31_copy(8) }
+                { qm31_copy(8) }
+                { qm31_copy(8) }
+                { qm31_copy(19) }
+                { qm31_copy(19) }
+
+                // stack:
+                //    c1, random_coeff (4), oods point (8),
+                //    masked points (3 * 8 = 24)
+                //    trace oods values (3 * 4 = 12)
+                //    composition odds raw values (4 * 4 = 16)
+                //    composition odds value (4)
+                //
+                //    random_coeff (4)
+                //    trace oods values (3 * 4 = 12)
+                //    oods point (8)
+                //
+                // altstack:
+                //    channel_digest, c2
+
+                { FibonacciCompositionGadget::eval_composition_polynomial_at_point(log_size, claim) }
+
+                qm31_equalverify
+            } else {
+                { qm31_copy(16) }
+                { AirGadget::check_composition_with_hints(2) }
+
+                // stack:
+                //    c1, random_coeff (4), oods point (8),
+                //    masked points (3 * 8 = 24)
+                //    trace oods values (3 * 4 = 12)
+                //    composition odds raw values (4 * 4 = 16)
+                //    step quotient (4), boundary quotient (4)
+                //
+                // altstack:
+                //    channel_digest, c2
+
+                { qm31_copy(8) }
+                { qm31_copy(8) }
+                { qm31_copy(8) }
+                { qm31_copy(19) }
+                { qm31_copy(19) }
+                { qm31_roll(6) }
+                { qm31_roll(6) }
+
+                { FibonacciCompositionGadget::check_quotients_against_mask(log_size, claim) }
+            }
 
             OP_FROMALTSTACK OP_FROMALTSTACK
 
@@ -144,7 +190,7 @@ impl FibonacciVerifierGadget {
             //    circle_poly_alpha (4)
             //    channel_digest
 
-            for _ in 0..FIB_LOG_SIZE {
+            for _ in 0..log_size {
                 OP_HINT OP_DUP OP_ROT { Sha256ChannelGadget::mix_digest() }
                 { Sha256ChannelGadget::draw_felt_with_hint() }
                 4 OP_ROLL
@@ -157,17 +203,19 @@ impl FibonacciVerifierGadget {
 
             { PowGadget::verify_pow(PROOF_OF_WORK_BITS) }
 
-            { Sha256ChannelGadget::draw_numbers_with_hint(N_QUERIES, (FIB_LOG_SIZE + LOG_BLOWUP_FACTOR + 1) as usize) }
+            { Sha256ChannelGadget::draw_numbers_with_hint(N_QUERIES, (log_size + LOG_BLOWUP_FACTOR + 1) as usize) }
 
-            { N_QUERIES } OP_ROLL
+            // sort and deduplicate the queries
+            { N_QUERIES } OP_ROLL OP_TOALTSTACK
+            { QueriesGadget::sort_with_hint(N_QUERIES) }
+
+            OP_FROMALTSTACK
             OP_HINT OP_EQUALVERIFY
 
             // test-only: clean up the stack
-            for _ in 0..N_QUERIES {
-                OP_DROP // drop the queries (out of order)
-            }
+            { QueriesGadget::drop_sorted(N_QUERIES) } // drop the sorted queries
             qm31_drop // drop the last layer eval
-            for _ in 0..FIB_LOG_SIZE {
+            for _ in 0..log_size {
                 qm31_drop // drop the derived folding_alpha
                 OP_DROP // drop the commitment
             }
@@ -189,8 +237,10 @@ impl FibonacciVerifierGadget {
 
 #[cfg(test)]
 mod test {
-    use crate::fibonacci::bitcoin_script::FIB_LOG_SIZE;
-    use crate::fibonacci::{verify_with_hints, FibonacciVerifierGadget};
+    use crate::fibonacci::{
+        prove_and_hint, verify_with_hints, verify_with_hints_and_composition_check,
+        CompositionCheck, FibonacciConfig, FibonacciVerifierGadget,
+    };
     use crate::treepp::*;
     use bitcoin_scriptexec::execute_script;
     use stwo_prover::core::channel::{BWSSha256Channel, Channel};
@@ -201,6 +251,8 @@ mod test {
     use stwo_prover::core::vcs::hasher::Hasher;
     use stwo_prover::examples::fibonacci::Fibonacci;
 
+    const FIB_LOG_SIZE: u32 = 5;
+
     #[test]
     fn test_verifier() {
         let fib = Fibonacci::new(FIB_LOG_SIZE, M31::reduce(443693538));
@@ -224,7 +276,41 @@ mod test {
 
         let script = script! {
             { hint }
-            { FibonacciVerifierGadget::run_verifier(&channel_clone) }
+            { FibonacciVerifierGadget::run_verifier(&channel_clone, FIB_LOG_SIZE, M31::reduce(443693538)) }
+            OP_TRUE
+        };
+
+        let exec_result = execute_script(script);
+        assert!(exec_result.success);
+    }
+
+    #[test]
+    fn test_verifier_with_recombined_composition() {
+        let config = FibonacciConfig {
+            log_size: FIB_LOG_SIZE,
+            claim: 443693538,
+        };
+        let (proof, _) = prove_and_hint(&config).unwrap();
+
+        let channel = config.initial_channel();
+        let hints = verify_with_hints_and_composition_check(
+            proof,
+            &config.air(),
+            &mut channel.clone(),
+            CompositionCheck::Recombined,
+        )
+        .unwrap();
+
+        let script = script! {
+            { hints }
+            {
+                FibonacciVerifierGadget::run_verifier_with_composition_check(
+                    &channel,
+                    config.log_size,
+                    config.claim_m31(),
+                    CompositionCheck::Recombined,
+                )
+            }
             OP_TRUE
         };
 
@@ -0,0 +1,392 @@
+use crate::channel::{ChannelWithHint, DrawHints};
+use crate::fibonacci::{FibonacciConfig, VerifierHints};
+use crate::fri::SortedQueriesHint;
+use crate::merkle_tree::try_split_decommitment;
+use crate::oods::OODS;
+use crate::pow::PoWHint;
+use crate::proof::SerializedStarkProof;
+use crate::treepp::pushable::Pushable;
+use crate::treepp::*;
+use stwo_prover::core::air::AirExt;
+use stwo_prover::core::backend::CpuBackend;
+use stwo_prover::core::channel::{BWSSha256Channel, Channel};
+use stwo_prover::core::circle::CirclePoint;
+use stwo_prover::core::fields::qm31::SecureField;
+use stwo_prover::core::fri::{get_opening_positions, FriVerificationError};
+use stwo_prover::core::poly::circle::SecureCirclePoly;
+use stwo_prover::core::proof_of_work::{ProofOfWork, ProofOfWorkProof};
+use stwo_prover::core::prover::{
+    verify, StarkProof, VerificationError, LOG_BLOWUP_FACTOR, N_QUERIES, PROOF_OF_WORK_BITS,
+};
+use stwo_prover::core::queries::Queries;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+use stwo_prover::core::ComponentVec;
+
+/// A step of the cross-check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CrossCheckStep {
+    /// The name of the step, following the order in which the verifier script consumes the
+    /// hints.
+    pub name: String,
+    /// Whether the hints of the step are the ones that the script expects.
+    pub passed: bool,
+}
+
+/// The report of the cross-check, see [`cross_check_hints`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CrossCheckReport {
+    /// All the steps, in order.
+    pub steps: Vec<CrossCheckStep>,
+    /// The channel digest after every event of the transcript, with the name of the event, in
+    /// order.
+    pub digests: Vec<(String, BWSSha256Hash)>,
+}
+
+impl CrossCheckReport {
+    fn check(&mut self, name: impl ToString, passed: bool) {
+        self.steps.push(CrossCheckStep {
+            name: name.to_string(),
+            passed,
+        });
+    }
+
+    fn record(&mut self, name: impl ToString, channel: &BWSSha256Channel) {
+        self.digests.push((name.to_string(), channel.digest));
+    }
+
+    /// Whether all the steps pass.
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.passed)
+    }
+
+    /// The first step that fails.
+    pub fn first_failure(&self) -> Option<&CrossCheckStep> {
+        self.steps.iter().find(|step| !step.passed)
+    }
+}
+
+// Two hints are the same if they push the same script.
+fn same_push<T: Pushable>(a: T, b: T) -> bool {
+    script! { { a } }.as_bytes() == script! { { b } }.as_bytes()
+}
+
+fn same_draw_hints(a: &DrawHints, b: &DrawHints) -> bool {
+    same_push(a, b)
+}
+
+/// Re-execute natively the verification that [`crate::fibonacci::FibonacciVerifierGadget`]
+/// performs, consuming the hints in the same order and with the same arithmetic.
+///
+/// The replay only starts from the initial channel and the configuration, so a hint that does
+/// not match the script, which would otherwise only show up as a failed script execution, is
+/// pinpointed before any funds are at stake. The replay continues after a failure, which may
+/// then fail the following steps as well.
+///
+/// The hints are also checked against the proof: the commitments, the sampled values, the FRI
+/// layers and the proof of work must be the ones of the proof, the Merkle path of every query
+/// must open the hinted commitments, and the FRI decommitments must be accepted at every layer.
+///
+/// The report also records the channel digest after every event of the transcript, which
+/// pinpoints where a change of stwo or of the channel shifts the transcript.
+pub fn cross_check_hints(
+    channel: &BWSSha256Channel,
+    config: &FibonacciConfig,
+    proof: &StarkProof,
+    hints: &VerifierHints,
+) -> CrossCheckReport {
+    let mut report = CrossCheckReport::default();
+    let initial_channel = channel.clone();
+    let mut channel = channel.clone();
+    let air = config.air();
+    let pcs_proof = &proof.commitment_scheme_proof;
+
+    report.check(
+        "commitments",
+        proof.commitments.0 == hints.commitments.to_vec(),
+    );
+
+    channel.mix_digest(hints.commitments[0]);
+    report.record("mix_trace_commitment", &channel);
+    let (random_coeff, random_coeff_hint) = channel.draw_felt_and_hints();
+    report.record("draw_random_coeff", &channel);
+    report.check(
+        "random_coeff",
+        same_draw_hints(&random_coeff_hint, &hints.random_coeff_hint),
+    );
+
+    channel.mix_digest(hints.commitments[1]);
+    report.record("mix_composition_commitment", &channel);
+    let (oods_point, oods_hint) =
+        CirclePoint::<SecureField>::get_random_point_with_hint(&mut channel);
+    report.record("draw_oods_point", &channel);
+    report.check(
+        "oods_point",
+        oods_hint.x == hints.oods_hint.x
+            && oods_hint.y == hints.oods_hint.y
+            && same_draw_hints(&oods_hint.hint, &hints.oods_hint.hint),
+    );
+
+    report.check(
+        "sampled_values",
+        pcs_proof.sampled_values.0.len() == 2
+            && pcs_proof.sampled_values.0[0]
+                .iter()
+                .flatten()
+                .eq(hints.trace_oods_values.iter())
+            && pcs_proof.sampled_values.0[1]
+                .iter()
+                .flatten()
+                .eq(hints.composition_oods_values.iter()),
+    );
+
+    for (i, v) in hints.trace_oods_values.iter().enumerate() {
+        channel.mix_felts(&[*v]);
+        report.record(format!("mix_trace_oods_value_{}", i), &channel);
+    }
+    for (i, v) in hints.composition_oods_values.iter().enumerate() {
+        channel.mix_felts(&[*v]);
+        report.record(format!("mix_composition_oods_value_{}", i), &channel);
+    }
+
+    let [t0, t1, t2] = hints.trace_oods_values;
+    let composition_oods_value =
+        SecureCirclePoly::<CpuBackend>::eval_from_partial_evals(hints.composition_oods_values);
+    report.check(
+        "composition_oods_value",
+        composition_oods_value
+            == air.eval_composition_polynomial_at_point(
+                oods_point,
+                &ComponentVec(vec![vec![vec![t0, t1, t2]]]),
+                random_coeff,
+            ),
+    );
+
+    let composition_hint = hints.composition_check.composition_hint(
+        air.component
+            .boundary_constraint_eval_quotient_by_mask(oods_point, &[t0]),
+        air.component
+            .step_constraint_eval_quotient_by_mask(oods_point, &[t0, t1, t2]),
+    );
+    report.check(
+        "composition_hint",
+        composition_hint.constraint_eval_quotients_by_mask
+            == hints.composition_hint.constraint_eval_quotients_by_mask,
+    );
+
+    let (_, random_coeff_hint2) = channel.draw_felt_and_hints();
+    report.record("draw_random_coeff2", &channel);
+    report.check(
+        "random_coeff2",
+        same_draw_hints(&random_coeff_hint2, &hints.random_coeff_hint2),
+    );
+
+    let (_, circle_poly_alpha_hint) = channel.draw_felt_and_hints();
+    report.record("draw_circle_poly_alpha", &channel);
+    report.check(
+        "circle_poly_alpha",
+        same_draw_hints(&circle_poly_alpha_hint, &hints.circle_poly_alpha_hint),
+    );
+
+    report.check(
+        "fri_n_layers",
+        hints.fri_commitment_and_folding_hints.len() == config.log_size as usize
+            && pcs_proof.fri_proof.inner_layers.len() == config.log_size as usize,
+    );
+    for (i, (commitment, folding_hint)) in hints.fri_commitment_and_folding_hints.iter().enumerate()
+    {
+        report.check(
+            format!("fri_commitment_{}", i),
+            pcs_proof
+                .fri_proof
+                .inner_layers
+                .get(i)
+                .map(|layer| layer.commitment)
+                == Some(*commitment),
+        );
+        channel.mix_digest(*commitment);
+        report.record(format!("mix_fri_commitment_{}", i), &channel);
+        let (_, expected_folding_hint) = channel.draw_felt_and_hints();
+        report.record(format!("draw_fri_folding_alpha_{}", i), &channel);
+        report.check(
+            format!("fri_folding_alpha_{}", i),
+            same_draw_hints(&expected_folding_hint, folding_hint),
+        );
+    }
+
+    let last_layer_poly = &pcs_proof.fri_proof.last_layer_poly;
+    report.check(
+        "last_layer",
+        last_layer_poly.len() == 1 && last_layer_poly.to_vec()[0] == hints.last_layer,
+    );
+    channel.mix_felts(&[hints.last_layer]);
+    report.record("mix_last_layer", &channel);
+
+    let pow_hint = PoWHint::new(channel.digest, hints.pow_hint.nonce, PROOF_OF_WORK_BITS);
+    let pow_verified = ProofOfWork::new(PROOF_OF_WORK_BITS)
+        .verify(
+            &mut channel,
+            &ProofOfWorkProof {
+                nonce: hints.pow_hint.nonce,
+            },
+        )
+        .is_ok();
+    report.record("mix_pow_nonce", &channel);
+    report.check(
+        "pow",
+        pow_verified
+            && hints.pow_hint.nonce == pcs_proof.proof_of_work.nonce
+            && same_push(pow_hint, hints.pow_hint.clone()),
+    );
+
+    let (drawn, queries_hints) = channel.draw_queries_and_hints(
+        N_QUERIES,
+        (config.log_size + LOG_BLOWUP_FACTOR + 1) as usize,
+    );
+    report.record("draw_queries", &channel);
+    report.check(
+        "queries",
+        same_draw_hints(&queries_hints, &hints.queries_hints),
+    );
+    report.check(
+        "sorted_queries",
+        SortedQueriesHint::new(&drawn) == hints.sorted_queries,
+    );
+
+    report.check("final_channel", channel.digest == hints.test_only);
+
+    // replay the Merkle path of every opening position, as the queries are sorted in the script
+    let trace_log_size = config.log_size + LOG_BLOWUP_FACTOR;
+    let composition_log_size = air.composition_log_degree_bound() + LOG_BLOWUP_FACTOR;
+    let positions = get_opening_positions(
+        &Queries {
+            positions: hints.sorted_queries.queries.clone(),
+            log_domain_size: composition_log_size,
+        },
+        &[composition_log_size, trace_log_size],
+    );
+    for (tree, name, log_size) in [
+        (0, "trace", trace_log_size),
+        (1, "composition", composition_log_size),
+    ] {
+        let tree_positions = positions
+            .get(&log_size)
+            .map(|domain| domain.flatten())
+            .unwrap_or_default();
+        let paths = pcs_proof
+            .queried_values
+            .0
+            .get(tree)
+            .zip(pcs_proof.decommitments.0.get(tree))
+            .and_then(|(values, decommitment)| {
+                try_split_decommitment(log_size, &tree_positions, values, decommitment)
+            });
+        match paths {
+            Some(paths) => {
+                for (j, (position, path)) in tree_positions.iter().zip(paths.iter()).enumerate() {
+                    report.check(
+                        format!("{}_decommitment_{}", name, j),
+                        path.verify(&hints.commitments[tree], *position),
+                    );
+                }
+            }
+            None => report.check(format!("{}_decommitment", name), false),
+        }
+    }
+
+    // replay the FRI decommitments, which are attributed to the layer that rejects them
+    let verification = match StarkProof::try_from(&SerializedStarkProof::from(proof)) {
+        Ok(proof) => verify(proof, &air, &mut initial_channel.clone()),
+        Err(err) => Err(VerificationError::InvalidStructure(err.to_string())),
+    };
+    for i in 0..pcs_proof.fri_proof.inner_layers.len() {
+        report.check(
+            format!("fri_decommitment_{}", i),
+            !matches!(
+                &verification,
+                Err(VerificationError::Fri(
+                    FriVerificationError::InnerLayerCommitmentInvalid { layer, .. }
+                        | FriVerificationError::InnerLayerEvaluationsInvalid { layer, .. }
+                )) if *layer == i
+            ),
+        );
+    }
+    report.check(
+        "fri_last_layer_decommitment",
+        !matches!(
+            &verification,
+            Err(VerificationError::Fri(
+                FriVerificationError::LastLayerEvaluationsInvalid
+            ))
+        ),
+    );
+    report.check("verification", verification.is_ok());
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::{cross_check_hints, prove_and_hint, FibonacciConfig};
+    use crate::tests_utils::snapshot::assert_snapshot;
+    use bitcoin::hex::DisplayHex;
+    use stwo_prover::core::fields::m31::M31;
+
+    #[test]
+    fn test_cross_check_hints() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let channel = config.initial_channel();
+        let (mut proof, mut hints) = prove_and_hint(&config).unwrap();
+
+        let report = cross_check_hints(&channel, &config, &proof, &hints);
+        assert!(report.passed());
+        assert!(report
+            .steps
+            .iter()
+            .any(|step| step.name == format!("fri_decommitment_{}", config.log_size - 1)));
+        assert!(report
+            .steps
+            .iter()
+            .any(|step| step.name == "composition_decommitment_0"));
+
+        // tampering with a folding hint is pinpointed
+        let folding_hint = hints.fri_commitment_and_folding_hints[1].1.clone();
+        hints.fri_commitment_and_folding_hints[1].1 = hints.queries_hints.clone();
+
+        let report = cross_check_hints(&channel, &config, &proof, &hints);
+        assert!(!report.passed());
+        assert_eq!(report.first_failure().unwrap().name, "fri_folding_alpha_1");
+
+        // tampering with a queried value is pinpointed at its decommitment
+        hints.fri_commitment_and_folding_hints[1].1 = folding_hint;
+        proof.commitment_scheme_proof.queried_values.0[0][0][0] += M31::from(1);
+
+        let report = cross_check_hints(&channel, &config, &proof, &hints);
+        assert!(!report.passed());
+        assert_eq!(report.first_failure().unwrap().name, "trace_decommitment_0");
+    }
+
+    #[test]
+    fn test_channel_digest_snapshots() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let (proof, hints) = prove_and_hint(&config).unwrap();
+
+        let report = cross_check_hints(&config.initial_channel(), &config, &proof, &hints);
+        assert!(report.passed());
+        assert_eq!(report.digests.len(), 16 + 2 * config.log_size as usize);
+
+        assert_snapshot(
+            "fibonacci_channel_digests",
+            &report
+                .digests
+                .iter()
+                .map(|(name, digest)| (name.clone(), digest.as_ref().to_lower_hex_string()))
+                .collect::<Vec<_>>(),
+        );
+    }
+}
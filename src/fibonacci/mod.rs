@@ -1,35 +1,153 @@
 mod bitcoin_script;
 
 pub use bitcoin_script::*;
+
+mod cross_check;
+pub use cross_check::*;
 use itertools::Itertools;
 
 use crate::air::CompositionHint;
 use crate::channel::{ChannelWithHint, DrawHints};
-use crate::fri::QueriesWithHint;
-use crate::oods::{OODSHint, OODS};
+use crate::fri::{QueriesWithHint, SortedQueriesHint};
+use crate::oods::{self, OODSHint, OODS};
 use crate::pow::PoWHint;
+use crate::proof::SerializedStarkProof;
 use crate::treepp::pushable::{Builder, Pushable};
+use crate::treepp::*;
+use crate::witness::HintKind;
+use serde::{Deserialize, Serialize};
 use stwo_prover::core::air::{Air, AirExt};
 use stwo_prover::core::backend::CpuBackend;
 use stwo_prover::core::channel::{BWSSha256Channel, Channel};
 use stwo_prover::core::circle::{CirclePoint, Coset};
+use stwo_prover::core::fields::m31::{BaseField, M31};
 use stwo_prover::core::fields::qm31::{SecureField, QM31};
+use stwo_prover::core::fields::IntoSlice;
 use stwo_prover::core::fri::{
-    get_opening_positions, CirclePolyDegreeBound, FriConfig, FriLayerVerifier,
-    FriVerificationError, FOLD_STEP,
+    get_opening_positions, CirclePolyDegreeBound, FriConfig, FriVerificationError, FOLD_STEP,
 };
 use stwo_prover::core::pcs::{CommitmentSchemeVerifier, TreeVec};
 use stwo_prover::core::poly::circle::SecureCirclePoly;
 use stwo_prover::core::poly::line::LineDomain;
 use stwo_prover::core::proof_of_work::ProofOfWork;
 use stwo_prover::core::prover::{
-    InvalidOodsSampleStructure, StarkProof, VerificationError, LOG_BLOWUP_FACTOR,
-    LOG_LAST_LAYER_DEGREE_BOUND, N_QUERIES, PROOF_OF_WORK_BITS,
+    prove, InvalidOodsSampleStructure, ProvingError, StarkProof, VerificationError,
+    LOG_BLOWUP_FACTOR, LOG_LAST_LAYER_DEGREE_BOUND, N_QUERIES, PROOF_OF_WORK_BITS,
 };
 use stwo_prover::core::queries::Queries;
-use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+use stwo_prover::core::vcs::bws_sha256_hash::{BWSSha256Hash, BWSSha256Hasher};
+use stwo_prover::core::vcs::hasher::Hasher;
 use stwo_prover::core::{ColumnVec, ComponentVec};
 use stwo_prover::examples::fibonacci::air::FibonacciAir;
+use stwo_prover::examples::fibonacci::Fibonacci;
+
+/// The statement that a Fibonacci verifier checks: the sequence a_{i+2} = a_i^2 + a_{i+1}^2 of
+/// 2^log_size elements starting from (1, 1) ends with `claim`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FibonacciConfig {
+    /// The log size of the trace.
+    pub log_size: u32,
+    /// The claimed last element, as a m31 element.
+    pub claim: u32,
+}
+
+impl FibonacciConfig {
+    /// The number of OODS points at which the columns are sampled.
+    ///
+    /// It is set by the prover, and `stwo-prover` samples at a single OODS point.
+    pub const N_OODS_POINTS: usize = 1;
+
+    /// The claim as a m31 element.
+    pub fn claim_m31(&self) -> M31 {
+        M31::reduce(self.claim as u64)
+    }
+
+    /// The AIR of the statement.
+    pub fn air(&self) -> FibonacciAir {
+        Fibonacci::new(self.log_size, self.claim_m31()).air
+    }
+
+    /// The channel at its initial state, which is seeded with the claim.
+    pub fn initial_channel(&self) -> BWSSha256Channel {
+        BWSSha256Channel::new(BWSSha256Hasher::hash(BaseField::into_slice(&[
+            self.claim_m31()
+        ])))
+    }
+}
+
+/// How the verifier script checks the composition polynomial at the OODS point against the
+/// composition OODS value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompositionCheck {
+    /// The Fibonacci gadget evaluates the composition polynomial from the mask values, pulling
+    /// each constraint quotient from the hint as it goes.
+    #[default]
+    Evaluated,
+    /// The script recombines the hinted constraint quotients with random_coeff and checks the
+    /// result against the composition OODS value with
+    /// [`crate::air::AirGadget::check_composition_with_hints`], and then checks each quotient
+    /// against the mask values.
+    Recombined,
+}
+
+impl CompositionCheck {
+    /// The hint of the constraint quotients, in the order in which the script pulls them.
+    pub fn composition_hint(&self, boundary: SecureField, step: SecureField) -> CompositionHint {
+        let constraint_eval_quotients_by_mask = match self {
+            CompositionCheck::Evaluated => vec![boundary, step],
+            // the Fibonacci AIR accumulates the step constraint before the boundary constraint
+            CompositionCheck::Recombined => vec![step, boundary],
+        };
+        CompositionHint {
+            constraint_eval_quotients_by_mask,
+        }
+    }
+}
+
+/// The error of [`prove_and_hint`].
+#[derive(Debug)]
+pub enum ProveAndHintError {
+    /// The prover fails, e.g., the claim is not the last element of the sequence.
+    Proving(ProvingError),
+    /// The proof does not pass the verifier.
+    Verification(VerificationError),
+}
+
+/// Prove the statement and generate the hints for the verifier script, starting from
+/// [`FibonacciConfig::initial_channel`].
+///
+/// This is the common path from a statement to what the script needs, and it does not require
+/// building the trace, the AIR, or the channel with stwo directly.
+///
+/// The proof is produced with [`CpuBackend`]. The Fibonacci example of stwo only implements the
+/// prover for this backend, so proving with the SIMD backend needs a SIMD implementation of the
+/// AIR first, and is not offered here.
+pub fn prove_and_hint(
+    config: &FibonacciConfig,
+) -> Result<(StarkProof, VerifierHints), ProveAndHintError> {
+    #[cfg(feature = "profiling")]
+    let _scope = crate::profiling::scope("prove_and_hint");
+
+    let fib = Fibonacci::new(config.log_size, config.claim_m31());
+    let proof = {
+        #[cfg(feature = "profiling")]
+        let _scope = crate::profiling::scope("prove");
+        prove(
+            &fib.air,
+            &mut config.initial_channel(),
+            vec![fib.get_trace()],
+        )
+        .map_err(ProveAndHintError::Proving)?
+    };
+
+    // the proof is consumed by the verifier, and it is cloned through its serialized layout
+    let proof_copy = StarkProof::try_from(&SerializedStarkProof::from(&proof))
+        .expect("a serialized proof should be valid");
+    let hints = verify_with_hints(proof_copy, &fib.air, &mut config.initial_channel())
+        .map_err(ProveAndHintError::Verification)?;
+
+    Ok((proof, hints))
+}
 
 /// All the hints for the verifier (note: proof is also provided as a hint).
 pub struct VerifierHints {
@@ -48,6 +166,10 @@ pub struct VerifierHints {
     /// composition odds raw values.
     pub composition_oods_values: [SecureField; 4],
 
+    /// How the script checks the composition polynomial, which sets the order of the
+    /// quotients in the composition hint.
+    pub composition_check: CompositionCheck,
+
     /// Composition hint.
     pub composition_hint: CompositionHint,
 
@@ -58,6 +180,11 @@ pub struct VerifierHints {
     pub circle_poly_alpha_hint: DrawHints,
 
     /// fri commit and hints for deriving the folding parameter
+    ///
+    /// They do not carry alpha^2: every fold in this crate (see [`crate::fri::FRIGadget`]) uses
+    /// folding_alpha only, as there is a single column per layer. A layer that needs alpha^2
+    /// computes it once with [`crate::powers::PowersGadget`], since checking a hinted square costs
+    /// the same multiplication.
     pub fri_commitment_and_folding_hints: Vec<(BWSSha256Hash, DrawHints)>,
 
     /// last layer poly (assuming only one element)
@@ -69,34 +196,121 @@ pub struct VerifierHints {
     /// Query sampling hints
     pub queries_hints: DrawHints,
 
+    /// Hint for sorting and deduplicating the queries
+    pub sorted_queries: SortedQueriesHint,
+
     /// Testing purpose: final channel values.
     pub test_only: BWSSha256Hash,
 }
 
+impl VerifierHints {
+    /// All the fields with their type and the script pushing them, in the order in which the
+    /// verifier consumes them.
+    ///
+    /// This is the only place where the order is defined: the hints are pushed in this order,
+    /// and [`crate::witness::partition_hints`] derives the layout of the witness from it.
+    pub fn fields(&self) -> Vec<HintField> {
+        let mut fields = vec![
+            hash_field("commitment_0", self.commitments[0]),
+            draw_hints_field("random_coeff_hint", &self.random_coeff_hint),
+            hash_field("commitment_1", self.commitments[1]),
+            oods_hint_field(&self.oods_hint),
+            qm31_field("trace_oods_values", &self.trace_oods_values),
+            qm31_field("composition_oods_values", &self.composition_oods_values),
+            composition_hint_field(&self.composition_hint),
+            draw_hints_field("random_coeff_hint2", &self.random_coeff_hint2),
+            draw_hints_field("circle_poly_alpha_hint", &self.circle_poly_alpha_hint),
+        ];
+        for (i, (commitment, folding_hint)) in
+            self.fri_commitment_and_folding_hints.iter().enumerate()
+        {
+            fields.push(hash_field(format!("fri_commitment_{}", i), *commitment));
+            fields.push(draw_hints_field(
+                format!("fri_folding_hint_{}", i),
+                folding_hint,
+            ));
+        }
+        fields.extend([
+            qm31_field("last_layer", &[self.last_layer]),
+            pow_hint_field(&self.pow_hint),
+            draw_hints_field("queries_hints", &self.queries_hints),
+            sorted_queries_field(&self.sorted_queries),
+            hash_field("test_only", self.test_only),
+        ]);
+        fields
+    }
+}
+
+/// A field of the hints: its name, its type, and the script pushing it.
+pub type HintField = (String, HintKind, Script);
+
+// The fields of the hints, shared by `VerifierHints::fields` and `verify_with_hints_streaming`.
+
+fn hash_field(name: impl ToString, hash: BWSSha256Hash) -> HintField {
+    (name.to_string(), HintKind::Hash, script! { { hash } })
+}
+
+fn draw_hints_field(name: impl ToString, hints: &DrawHints) -> HintField {
+    (name.to_string(), HintKind::DrawHints, script! { { hints } })
+}
+
+fn qm31_field(name: impl ToString, values: &[QM31]) -> HintField {
+    (
+        name.to_string(),
+        HintKind::Qm31,
+        script! {
+            for v in values.iter() {
+                { *v }
+            }
+        },
+    )
+}
+
+fn oods_hint_field(oods_hint: &OODSHint) -> HintField {
+    (
+        "oods_hint".to_string(),
+        HintKind::OodsHint,
+        script! { { oods_hint.clone() } },
+    )
+}
+
+fn composition_hint_field(composition_hint: &CompositionHint) -> HintField {
+    (
+        "composition_hint".to_string(),
+        HintKind::Qm31,
+        script! {
+            {
+                CompositionHint {
+                    constraint_eval_quotients_by_mask: composition_hint
+                        .constraint_eval_quotients_by_mask
+                        .clone(),
+                }
+            }
+        },
+    )
+}
+
+fn pow_hint_field(pow_hint: &PoWHint) -> HintField {
+    (
+        "pow_hint".to_string(),
+        HintKind::PowHint,
+        script! { { pow_hint.clone() } },
+    )
+}
+
+fn sorted_queries_field(sorted_queries: &SortedQueriesHint) -> HintField {
+    (
+        "sorted_queries".to_string(),
+        HintKind::Queries,
+        script! { { sorted_queries } },
+    )
+}
+
 impl Pushable for VerifierHints {
     fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
-        builder = self.commitments[0].bitcoin_script_push(builder);
-        builder = self.random_coeff_hint.bitcoin_script_push(builder);
-        builder = self.commitments[1].bitcoin_script_push(builder);
-        builder = self.oods_hint.bitcoin_script_push(builder);
-        for v in self.trace_oods_values.iter() {
-            builder = v.bitcoin_script_push(builder);
-        }
-        for v in self.composition_oods_values.iter() {
-            builder = v.bitcoin_script_push(builder);
-        }
-        builder = self.composition_hint.bitcoin_script_push(builder);
-        builder = self.random_coeff_hint2.bitcoin_script_push(builder);
-        builder = self.circle_poly_alpha_hint.bitcoin_script_push(builder);
-        for (c, h) in self.fri_commitment_and_folding_hints.iter() {
-            builder = c.bitcoin_script_push(builder);
-            builder = h.bitcoin_script_push(builder);
+        for (_, _, hint) in self.fields() {
+            builder = hint.bitcoin_script_push(builder);
         }
-        builder = self.last_layer.bitcoin_script_push(builder);
-        builder = self.pow_hint.bitcoin_script_push(builder);
-        builder = self.queries_hints.bitcoin_script_push(builder);
-        builder = self.test_only.bitcoin_script_push(builder);
-
         builder
     }
 }
@@ -107,10 +321,56 @@ pub fn verify_with_hints(
     air: &FibonacciAir,
     channel: &mut BWSSha256Channel,
 ) -> Result<VerifierHints, VerificationError> {
+    verify_with_hints_and_composition_check(proof, air, channel, CompositionCheck::default())
+}
+
+/// A verifier program that generates hints for a script that checks the composition
+/// polynomial as set by `composition_check`, see
+/// [`FibonacciVerifierGadget::run_verifier_with_composition_check`].
+pub fn verify_with_hints_and_composition_check(
+    proof: StarkProof,
+    air: &FibonacciAir,
+    channel: &mut BWSSha256Channel,
+    composition_check: CompositionCheck,
+) -> Result<VerifierHints, VerificationError> {
+    verify_and_hand_out_hints(proof, air, channel, composition_check, |_| {})
+}
+
+/// A verifier program that generates the same hints as
+/// [`verify_with_hints_and_composition_check`], but hands out each field, in the order of
+/// [`VerifierHints::fields`], as soon as it is final, so that the hints can be written out while
+/// the proof is still being verified.
+///
+/// If the verification fails, the fields handed out so far must be discarded.
+pub fn verify_with_hints_streaming<F: FnMut(HintField)>(
+    proof: StarkProof,
+    air: &FibonacciAir,
+    channel: &mut BWSSha256Channel,
+    composition_check: CompositionCheck,
+    on_field: F,
+) -> Result<(), VerificationError> {
+    verify_and_hand_out_hints(proof, air, channel, composition_check, on_field).map(|_| ())
+}
+
+// The verifier, which hands out each field of the hints as soon as it is final, and returns
+// them all. The FRI layers of the proof are processed one at a time: the decommitment of a layer
+// is dropped as soon as its commitment is in the channel.
+fn verify_and_hand_out_hints<F: FnMut(HintField)>(
+    proof: StarkProof,
+    air: &FibonacciAir,
+    channel: &mut BWSSha256Channel,
+    composition_check: CompositionCheck,
+    mut on_field: F,
+) -> Result<VerifierHints, VerificationError> {
+    #[cfg(feature = "profiling")]
+    let _scope = crate::profiling::scope("verify_with_hints");
+
     // Read trace commitment.
     let mut commitment_scheme = CommitmentSchemeVerifier::new();
     commitment_scheme.commit(proof.commitments[0], air.column_log_sizes(), channel);
     let (random_coeff, random_coeff_hint) = channel.draw_felt_and_hints();
+    on_field(hash_field("commitment_0", proof.commitments[0]));
+    on_field(draw_hints_field("random_coeff_hint", &random_coeff_hint));
 
     // Read composition polynomial commitment.
     commitment_scheme.commit(
@@ -118,19 +378,34 @@ pub fn verify_with_hints(
         vec![air.composition_log_degree_bound(); 4],
         channel,
     );
+    on_field(hash_field("commitment_1", proof.commitments[1]));
 
-    // Draw OODS point.
-    let (oods_point, oods_hint) = CirclePoint::<SecureField>::get_random_point_with_hint(channel);
-
-    // Get mask sample points relative to oods point.
-    let trace_sample_points = air.mask_points(oods_point);
-    let masked_points = trace_sample_points.clone();
+    // Draw OODS points.
+    let (oods_points, oods_hints) = CirclePoint::<SecureField>::get_random_points_with_hints(
+        channel,
+        FibonacciConfig::N_OODS_POINTS,
+    );
 
     // TODO(spapini): Change when we support multiple interactions.
     // First tree - trace.
-    let mut sampled_points = TreeVec::new(vec![trace_sample_points.flatten()]);
+    let mut sampled_points = TreeVec::new(vec![oods::sampled_points(&oods_points, |p| {
+        air.mask_points(p).flatten()
+    })]);
     // Second tree - composition polynomial.
-    sampled_points.push(vec![vec![oods_point]; 4]);
+    sampled_points.push(oods::sampled_points(&oods_points, |p| vec![vec![p]; 4]));
+
+    // the proof carries the sampled values at a single OODS point
+    if oods_points.len() != 1 {
+        return Err(VerificationError::InvalidStructure(
+            "Expected a single OODS point".to_string(),
+        ));
+    }
+    let oods_point = oods_points[0];
+    let oods_hint = oods_hints[0].clone();
+    on_field(oods_hint_field(&oods_hint));
+
+    // Get mask sample points relative to oods point.
+    let masked_points = air.mask_points(oods_point);
 
     // this step is just a reorganization of the data
     assert_eq!(sampled_points.0[0][0][0], masked_points[0][0][0]);
@@ -157,29 +432,39 @@ pub fn verify_with_hints(
         return Err(VerificationError::OodsNotMatching);
     }
 
-    let composition_hint = CompositionHint {
-        constraint_eval_quotients_by_mask: vec![
-            air.component.boundary_constraint_eval_quotient_by_mask(
-                oods_point,
-                trace_oods_values[0][0][..1].try_into().unwrap(),
-            ),
-            air.component.step_constraint_eval_quotient_by_mask(
-                oods_point,
-                trace_oods_values[0][0][..].try_into().unwrap(),
-            ),
-        ],
-    };
+    let composition_hint = composition_check.composition_hint(
+        air.component.boundary_constraint_eval_quotient_by_mask(
+            oods_point,
+            trace_oods_values[0][0][..1].try_into().unwrap(),
+        ),
+        air.component.step_constraint_eval_quotient_by_mask(
+            oods_point,
+            trace_oods_values[0][0][..].try_into().unwrap(),
+        ),
+    );
 
     let sample_values = &proof.commitment_scheme_proof.sampled_values.0;
+    let trace_oods_values_hint = [
+        sample_values[0][0][0],
+        sample_values[0][0][1],
+        sample_values[0][0][2],
+    ];
+    let composition_oods_values_hint = [
+        sample_values[1][0][0],
+        sample_values[1][1][0],
+        sample_values[1][2][0],
+        sample_values[1][3][0],
+    ];
+    on_field(qm31_field("trace_oods_values", &trace_oods_values_hint));
+    on_field(qm31_field(
+        "composition_oods_values",
+        &composition_oods_values_hint,
+    ));
+    on_field(composition_hint_field(&composition_hint));
 
-    channel.mix_felts(
-        &proof
-            .commitment_scheme_proof
-            .sampled_values
-            .clone()
-            .flatten_cols(),
-    );
+    channel.mix_felts(&proof.commitment_scheme_proof.sampled_values.flatten_cols());
     let (random_coeff, random_coeff_hint2) = channel.draw_felt_and_hints();
+    on_field(draw_hints_field("random_coeff_hint2", &random_coeff_hint2));
 
     let bounds = commitment_scheme
         .column_log_sizes()
@@ -203,8 +488,11 @@ pub fn verify_with_hints(
 
     // Circle polynomials can all be folded with the same alpha.
     let (circle_poly_alpha, circle_poly_alpha_hint) = channel.draw_felt_and_hints();
+    on_field(draw_hints_field(
+        "circle_poly_alpha_hint",
+        &circle_poly_alpha_hint,
+    ));
 
-    let mut inner_layers = Vec::new();
     let mut layer_bound = max_column_bound.fold_to_line();
     let mut layer_domain = LineDomain::new(Coset::half_odds(
         layer_bound.log_degree_bound + fri_config.log_blowup_factor,
@@ -221,18 +509,19 @@ pub fn verify_with_hints(
     {
         channel.mix_digest(proof.commitment);
 
-        let (folding_alpha, folding_alpha_hint) = channel.draw_felt_and_hints();
+        let (_, folding_alpha_hint) = channel.draw_felt_and_hints();
+        on_field(hash_field(
+            format!("fri_commitment_{}", layer_index),
+            proof.commitment,
+        ));
+        on_field(draw_hints_field(
+            format!("fri_folding_hint_{}", layer_index),
+            &folding_alpha_hint,
+        ));
 
+        // the decommitment of the layer is not needed for the hints, and is dropped here
         fri_commitment_and_folding_hints.push((proof.commitment, folding_alpha_hint));
 
-        inner_layers.push(FriLayerVerifier {
-            degree_bound: layer_bound,
-            domain: layer_domain,
-            folding_alpha,
-            layer_index,
-            proof,
-        });
-
         layer_bound = layer_bound
             .fold(FOLD_STEP)
             .ok_or(FriVerificationError::InvalidNumFriLayers)?;
@@ -255,6 +544,8 @@ pub fn verify_with_hints(
     }
 
     channel.mix_felts(&last_layer_poly);
+    let last_layer = last_layer_poly.to_vec()[0];
+    on_field(qm31_field("last_layer", &[last_layer]));
 
     let pow_hint = PoWHint::new(
         channel.digest,
@@ -265,6 +556,7 @@ pub fn verify_with_hints(
     // Verify proof of work.
     ProofOfWork::new(PROOF_OF_WORK_BITS)
         .verify(channel, &proof.commitment_scheme_proof.proof_of_work)?;
+    on_field(pow_hint_field(&pow_hint));
 
     let column_log_sizes = bounds
         .iter()
@@ -275,6 +567,10 @@ pub fn verify_with_hints(
     let (queries, queries_hints) =
         Queries::generate_with_hints(channel, column_log_sizes[0], fri_config.n_queries);
     let positions = get_opening_positions(&queries, &column_log_sizes);
+    let sorted_queries = SortedQueriesHint::from(&queries);
+    on_field(draw_hints_field("queries_hints", &queries_hints));
+    on_field(sorted_queries_field(&sorted_queries));
+    on_field(hash_field("test_only", channel.digest));
 
     let _ = positions;
     let _ = column_log_sizes;
@@ -289,24 +585,17 @@ pub fn verify_with_hints(
         commitments: [proof.commitments[0], proof.commitments[1]],
         random_coeff_hint,
         oods_hint,
-        trace_oods_values: [
-            sample_values[0][0][0],
-            sample_values[0][0][1],
-            sample_values[0][0][2],
-        ],
-        composition_oods_values: [
-            sample_values[1][0][0],
-            sample_values[1][1][0],
-            sample_values[1][2][0],
-            sample_values[1][3][0],
-        ],
+        trace_oods_values: trace_oods_values_hint,
+        composition_oods_values: composition_oods_values_hint,
+        composition_check,
         composition_hint,
         random_coeff_hint2,
         circle_poly_alpha_hint,
         fri_commitment_and_folding_hints,
-        last_layer: last_layer_poly.to_vec()[0],
+        last_layer,
         pow_hint,
         queries_hints,
+        sorted_queries,
         test_only: channel.digest,
     })
 }
@@ -348,10 +637,15 @@ fn sampled_values_to_mask(
 
 #[cfg(test)]
 mod test {
+    use crate::fibonacci::{
+        cross_check_hints, prove_and_hint, verify_with_hints_streaming, CompositionCheck,
+        FibonacciConfig,
+    };
+    use crate::proof::SerializedStarkProof;
     use stwo_prover::core::channel::{BWSSha256Channel, Channel};
     use stwo_prover::core::fields::m31::{BaseField, M31};
     use stwo_prover::core::fields::IntoSlice;
-    use stwo_prover::core::prover::{prove, verify};
+    use stwo_prover::core::prover::{prove, verify, StarkProof};
     use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hasher;
     use stwo_prover::core::vcs::hasher::Hasher;
     use stwo_prover::examples::fibonacci::Fibonacci;
@@ -376,4 +670,38 @@ mod test {
                 .claim])));
         verify(proof, &fib.air, channel).unwrap()
     }
+
+    #[test]
+    fn test_prove_and_hint() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+
+        let (proof, hints) = prove_and_hint(&config).unwrap();
+        assert!(cross_check_hints(&config.initial_channel(), &config, &proof, &hints).passed());
+        verify(proof, &config.air(), &mut config.initial_channel()).unwrap();
+    }
+
+    #[test]
+    fn test_verify_with_hints_streaming() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let (proof, hints) = prove_and_hint(&config).unwrap();
+        let proof = StarkProof::try_from(&SerializedStarkProof::from(&proof)).unwrap();
+
+        // the fields are handed out in the order in which the verifier consumes them
+        let mut fields = vec![];
+        verify_with_hints_streaming(
+            proof,
+            &config.air(),
+            &mut config.initial_channel(),
+            CompositionCheck::default(),
+            |field| fields.push(field),
+        )
+        .unwrap();
+        assert_eq!(fields, hints.fields());
+    }
 }
@@ -1,15 +1,20 @@
 use crate::channel::{ChannelWithHint, DrawHints, Sha256Channel, Sha256ChannelGadget};
+use crate::digest::{push_digest, DIGEST_SIZE};
 use crate::fri::{FriProof, N_QUERIES};
+use crate::line::{LineDomainGadget, LinePolyGadget};
 use crate::merkle_tree::MerkleTreeGadget;
+use crate::tradeoff::Placement;
 use crate::treepp::*;
-use crate::twiddle_merkle_tree::TwiddleMerkleTreeGadget;
+use crate::twiddle_merkle_tree::{TwiddleMerkleTreeGadget, TwiddleTableGadget};
 use crate::utils::copy_to_altstack_top_item_first_in;
 use crate::utils::{limb_to_be_bits, limb_to_be_bits_toaltstack};
+use crate::OP_HINT;
 use rust_bitcoin_m31::{
-    qm31_add, qm31_equalverify, qm31_fromaltstack, qm31_mul, qm31_mul_m31, qm31_over, qm31_roll,
-    qm31_sub, qm31_swap, qm31_toaltstack,
+    qm31_add, qm31_copy, qm31_equalverify, qm31_from_bottom, qm31_fromaltstack, qm31_mul,
+    qm31_mul_m31, qm31_over, qm31_roll, qm31_sub, qm31_swap, qm31_toaltstack,
 };
 use stwo_prover::core::channel::Channel;
+use stwo_prover::core::poly::line::LineDomain;
 
 /// Gadget for FRI.
 pub struct FRIGadget;
@@ -43,10 +48,12 @@ impl FRIGadget {
 
     /// Check the Fiat-Shamir computation.
     pub fn check_fiat_shamir(channel_init_state: &[u8], logn: usize, n_layers: usize) -> Script {
-        assert_eq!(channel_init_state.len(), 32);
+        let channel_init_state: [u8; DIGEST_SIZE] = channel_init_state
+            .try_into()
+            .expect("the channel state is a digest");
         let n_last_layer = 1 << (logn - n_layers);
         script! {
-            { channel_init_state.to_vec() }
+            { push_digest(&channel_init_state) }
 
             for _ in 0..n_layers {
                 { Sha256ChannelGadget::mix_digest() }
@@ -93,13 +100,31 @@ impl FRIGadget {
             }
 
             for _ in 0..N_QUERIES {
-                { twiddle_merkle_tree_root.to_vec() }
+                { push_digest(&twiddle_merkle_tree_root) }
                 OP_FROMALTSTACK
                 { TwiddleMerkleTreeGadget::query_and_verify(logn) }
             }
         }
     }
 
+    /// Look up the twiddle factors of all the queries in tables baked into the script, which
+    /// replaces [`FRIGadget::check_twiddle_merkle_tree_proof`] without hints.
+    ///
+    /// input: pos * N_QUERIES
+    /// output: leaves * N_QUERIES
+    pub fn check_twiddle_table(logn: usize) -> Script {
+        script! {
+            for _ in 0..N_QUERIES {
+                OP_TOALTSTACK
+            }
+
+            for _ in 0..N_QUERIES {
+                OP_FROMALTSTACK
+                { TwiddleTableGadget::query(logn) }
+            }
+        }
+    }
+
     /// Push the several Merkle tree proofs for a specific query.
     pub fn push_single_query_merkle_tree_proof(idx: usize, fri_proof: &FriProof) -> Script {
         script! {
@@ -107,7 +132,7 @@ impl FRIGadget {
                 { proof.leaf }
 
                 for elem in proof.siblings.iter() {
-                    { elem.to_vec() }
+                    { push_digest(elem) }
                 }
             }
         }
@@ -166,6 +191,346 @@ impl FRIGadget {
         }
     }
 
+    /// Verify one layer of an incremental FRI verification.
+    ///
+    /// Hint:
+    /// - channel digest
+    /// - layers digest
+    /// - commitment of the layer
+    /// - hint for drawing the folding factor
+    ///
+    /// Output:
+    /// - commitment of the old state
+    /// - commitment of the new state
+    pub fn verify_layer_step() -> Script {
+        script! {
+            OP_HINT OP_HINT
+            OP_2DUP OP_CAT OP_SHA256 OP_TOALTSTACK
+
+            // absorb the commitment into both the layers digest and the channel
+            OP_HINT
+            OP_DUP OP_ROT
+            { Sha256ChannelGadget::mix_digest() }
+            OP_TOALTSTACK
+            OP_SWAP
+            { Sha256ChannelGadget::mix_digest() }
+
+            // draw the folding factor and absorb it into the layers digest
+            { Sha256ChannelGadget::draw_felt_with_hint() }
+            OP_FROMALTSTACK
+            { Sha256ChannelGadget::mix_felt() }
+
+            OP_CAT OP_SHA256
+            OP_FROMALTSTACK OP_SWAP
+        }
+    }
+
+    /// Verify the last layer of an incremental FRI verification and draw the queries.
+    ///
+    /// Hint:
+    /// - channel digest
+    /// - layers digest
+    /// - last layer (2 qm31)
+    /// - hint for drawing the queries
+    ///
+    /// Output:
+    /// - commitment of the old state
+    /// - commitment of the new state
+    pub fn verify_last_layer_step(logn: usize) -> Script {
+        script! {
+            OP_HINT OP_HINT
+            OP_2DUP OP_CAT OP_SHA256 OP_TOALTSTACK
+            OP_TOALTSTACK
+
+            // only work for last layer with 2 elements
+            qm31_from_bottom
+            qm31_from_bottom
+
+            // check it's of half degree
+            { qm31_copy(1) }
+            { qm31_copy(1) }
+            qm31_equalverify
+
+            // absorb the first element into the layers digest and the channel
+            qm31_swap
+            { qm31_copy(0) }
+            OP_FROMALTSTACK
+            { Sha256ChannelGadget::mix_felt() }
+            OP_TOALTSTACK
+            8 OP_ROLL
+            { Sha256ChannelGadget::mix_felt() }
+
+            // absorb the second element into the layers digest and the channel
+            OP_FROMALTSTACK
+            for _ in 0..4 {
+                5 OP_PICK
+            }
+            4 OP_ROLL
+            { Sha256ChannelGadget::mix_felt() }
+            OP_TOALTSTACK
+            { Sha256ChannelGadget::mix_felt() }
+
+            { Sha256ChannelGadget::draw_numbers_with_hint(N_QUERIES, logn) }
+
+            // absorb the queries into the layers digest
+            for _ in 0..N_QUERIES {
+                { N_QUERIES - 1 } OP_ROLL
+                OP_SHA256
+                OP_FROMALTSTACK
+                { Sha256ChannelGadget::mix_digest() }
+                OP_TOALTSTACK
+            }
+
+            OP_FROMALTSTACK OP_CAT OP_SHA256
+            OP_FROMALTSTACK OP_SWAP
+        }
+    }
+
+    /// Verify one query of an incremental FRI verification.
+    ///
+    /// Hint:
+    /// - channel digest
+    /// - commitment and folding factor of each layer
+    /// - last layer (2 qm31)
+    /// - queries
+    /// - leaf of the query
+    /// - the decommitment of the query, see [`crate::fri::SingleQueryHint`]
+    ///
+    /// Output:
+    /// - commitment of the state
+    pub fn verify_query_step(
+        logn: usize,
+        query_idx: usize,
+        twiddle_merkle_tree_root: [u8; 32],
+    ) -> Script {
+        Self::verify_query_step_with_placement(
+            logn,
+            query_idx,
+            twiddle_merkle_tree_root,
+            Placement::Hinted,
+        )
+    }
+
+    /// Verify one query of an incremental FRI verification, with the twiddle factors placed as
+    /// given, see [`FRIGadget::verify_single_query_with_placement`].
+    ///
+    /// Hint and output: as in [`FRIGadget::verify_query_step`], without the twiddle Merkle tree
+    /// proof if the twiddle factors are inline.
+    pub fn verify_query_step_with_placement(
+        logn: usize,
+        query_idx: usize,
+        twiddle_merkle_tree_root: [u8; 32],
+        twiddle_placement: Placement,
+    ) -> Script {
+        let n_layers = logn - 1;
+        let state_len = 5 * n_layers + 8;
+
+        script! {
+            OP_HINT
+            { vec![0u8; 32] } OP_TOALTSTACK
+
+            // pull the state while recomputing the layers digest
+            for _ in 0..n_layers {
+                OP_HINT
+                OP_DUP OP_FROMALTSTACK
+                { Sha256ChannelGadget::mix_digest() }
+                OP_TOALTSTACK
+
+                qm31_from_bottom
+                { qm31_copy(0) }
+                OP_FROMALTSTACK
+                { Sha256ChannelGadget::mix_felt() }
+                OP_TOALTSTACK
+            }
+
+            for _ in 0..2 {
+                qm31_from_bottom
+                { qm31_copy(0) }
+                OP_FROMALTSTACK
+                { Sha256ChannelGadget::mix_felt() }
+                OP_TOALTSTACK
+            }
+            // the last layer is kept with its first element on the top
+            qm31_swap
+
+            for _ in 0..N_QUERIES {
+                OP_HINT
+                OP_DUP OP_SHA256
+                OP_FROMALTSTACK
+                { Sha256ChannelGadget::mix_digest() }
+                OP_TOALTSTACK
+            }
+
+            // compute the state commitment
+            OP_FROMALTSTACK
+            { state_len + N_QUERIES + 1 } OP_ROLL
+            OP_SWAP OP_CAT OP_SHA256
+            OP_TOALTSTACK
+
+            // keep only the requested query
+            { N_QUERIES - 1 - query_idx } OP_ROLL
+            OP_TOALTSTACK
+            for _ in 0..(N_QUERIES - 1) {
+                OP_DROP
+            }
+
+            qm31_from_bottom
+            OP_FROMALTSTACK
+            { Self::verify_single_query_with_placement(logn, twiddle_merkle_tree_root, twiddle_placement) }
+
+            // drop the state
+            for _ in 0..(state_len / 2) {
+                OP_2DROP
+            }
+            if state_len % 2 == 1 {
+                OP_DROP
+            }
+
+            OP_FROMALTSTACK
+        }
+    }
+
+    /// Verify one query across all the layers: check the value of the query against its leaf
+    /// in the first layer, look up the twiddle factors and the siblings of the query with their
+    /// Merkle tree proofs, fold the value through every inner layer with the folding factors,
+    /// and compare the result with the last layer.
+    ///
+    /// The value of the query is an input rather than a hint, so that a verifier of the
+    /// commitment scheme can pass the combined quotient that it computes from the openings, see
+    /// [`crate::pcs::QuotientAccumulationGadget`], and have it bound to the commitment of the
+    /// first layer.
+    ///
+    /// The input is left on the stack except for the value and the query, so that the gadget can
+    /// be repeated for each query.
+    ///
+    /// Hint:
+    /// - the decommitment of the query, see [`crate::fri::SingleQueryHint`]
+    ///
+    /// Input:
+    /// - commitment and folding factor (qm31) of each layer, the first layer's first
+    /// - last layer (2 qm31, the first element on the top)
+    /// - value of the query in the first layer, i.e. the combined quotient (qm31)
+    /// - query
+    ///
+    /// Output:
+    /// - commitment and folding factor (qm31) of each layer, the first layer's first
+    /// - last layer (2 qm31, the first element on the top)
+    pub fn verify_single_query(logn: usize, twiddle_merkle_tree_root: [u8; 32]) -> Script {
+        Self::verify_single_query_with_placement(logn, twiddle_merkle_tree_root, Placement::Hinted)
+    }
+
+    /// Verify one query across all the layers, with the twiddle factors either looked up in a
+    /// table baked into the script, or hinted with their twiddle Merkle tree proof, see
+    /// [`crate::tradeoff::analyze`] for which one is cheaper.
+    ///
+    /// Hint, input, and output: as in [`FRIGadget::verify_single_query`], without the twiddle
+    /// Merkle tree proof if the twiddle factors are inline.
+    pub fn verify_single_query_with_placement(
+        logn: usize,
+        twiddle_merkle_tree_root: [u8; 32],
+        twiddle_placement: Placement,
+    ) -> Script {
+        let n_layers = logn - 1;
+        let query_twiddle_factors = match twiddle_placement {
+            Placement::Inline => TwiddleTableGadget::query(logn),
+            Placement::Hinted => TwiddleMerkleTreeGadget::query_and_verify(logn),
+        };
+
+        script! {
+            // check the value against the leaf of the query in the first layer
+            OP_DUP
+            { 5 * n_layers + 13 } OP_PICK
+            OP_SWAP
+            { MerkleTreeGadget::query_and_verify(logn) }
+            4 OP_ROLL OP_TOALTSTACK
+            { qm31_copy(1) }
+            qm31_equalverify
+
+            // keep the value and the query in the altstack, the query on the top
+            OP_FROMALTSTACK
+            for _ in 0..4 {
+                4 OP_ROLL
+            }
+            qm31_toaltstack
+            OP_TOALTSTACK
+
+            // twiddle factors
+            if twiddle_placement == Placement::Hinted {
+                { push_digest(&twiddle_merkle_tree_root) }
+            }
+            OP_FROMALTSTACK OP_DUP OP_TOALTSTACK
+            { query_twiddle_factors }
+
+            // copy the folding factors, the last layer's first
+            for i in 0..n_layers {
+                for _ in 0..4 {
+                    { n_layers + 8 + 9 * i + 3 } OP_PICK
+                }
+            }
+
+            // copy the roots, the last layer's first
+            for i in 0..n_layers {
+                { 5 * n_layers + 12 + 6 * i } OP_PICK
+            }
+            OP_FROMALTSTACK OP_DUP OP_TOALTSTACK
+            { Self::check_single_query_merkle_tree_proof(logn) }
+
+            OP_FROMALTSTACK
+            qm31_fromaltstack
+            4 OP_ROLL
+            { Self::check_single_query_ibutterfly(logn, 8) }
+        }
+    }
+
+    /// Check a folded value of a query against the last layer of FRI given as a line polynomial,
+    /// as in stwo, by deriving the point of the query in the last layer domain and evaluating
+    /// the polynomial there.
+    ///
+    /// The last layer is left on the stack, so that the gadget can be repeated for each query.
+    ///
+    /// Input:
+    /// - last layer (2^log_degree qm31 coefficients, the first one on the top)
+    /// - folded value (qm31)
+    /// - position of the query in the last layer domain
+    ///
+    /// Output:
+    /// - last layer (2^log_degree qm31 coefficients, the first one on the top)
+    pub fn check_last_layer_poly(last_layer_domain: &LineDomain, log_degree: usize) -> Script {
+        let n_coeffs = 1 << log_degree;
+        script! {
+            { LineDomainGadget::derive_at(last_layer_domain) }
+
+            // copy the coefficients over the folded value and the point
+            for _ in 0..4 * n_coeffs {
+                { 4 * n_coeffs + 4 } OP_PICK
+            }
+            { 4 * n_coeffs } OP_ROLL
+            { LinePolyGadget::eval_at_point(log_degree) }
+
+            qm31_equalverify
+        }
+    }
+
+    /// Fold a pair of values over the circle domain into the line domain with the folding
+    /// factor, which is the first fold of FRI.
+    ///
+    /// Input:
+    /// - alpha (qm31)
+    /// - f(p) (qm31)
+    /// - f(-p) (qm31)
+    /// - the inverse of the y coordinate of p (m31)
+    ///
+    /// Output:
+    /// - folded value (qm31)
+    pub fn fold_circle_into_line() -> Script {
+        script! {
+            { FFTGadget::ibutterfly() }
+            { qm31_roll(2) }
+            qm31_mul
+            qm31_add
+        }
+    }
+
     /// Check the ibutterfly stage for one single query.
     ///
     ///  input:
@@ -225,6 +590,126 @@ impl FRIGadget {
     }
 }
 
+/// Gadget for the queries.
+pub struct QueriesGadget;
+
+impl QueriesGadget {
+    /// Check that the sorted queries are strictly increasing.
+    ///
+    /// Input:
+    /// - sorted queries (k, the largest one on the top), k, where k is at most m
+    ///
+    /// Output:
+    /// - sorted queries (k, the largest one on the top), k
+    pub fn check_strictly_increasing(m: usize) -> Script {
+        script! {
+            for t in 0..(m.max(1) - 1) {
+                OP_DUP { t + 1 } OP_GREATERTHAN
+                OP_IF
+                    { t + 2 } OP_PICK
+                    { t + 2 } OP_PICK
+                    OP_LESSTHAN OP_VERIFY
+                OP_ENDIF
+            }
+        }
+    }
+
+    /// Sort and deduplicate the drawn queries using a hint.
+    ///
+    /// The hint is checked to hold exactly the values of the drawn queries, once each, so the
+    /// per-query loop follows the same order as the decommitments of [`Queries`]. Since repeated
+    /// queries are dropped, the number of sorted queries k is only known at execution time, and
+    /// it is left on the top of the stack.
+    ///
+    /// Hint:
+    /// - sorted queries, see [`crate::fri::SortedQueriesHint`]
+    ///
+    /// Input:
+    /// - drawn queries (m, the last one on the top)
+    ///
+    /// Output:
+    /// - sorted queries (k, the largest one on the top), k
+    ///
+    /// [`Queries`]: stwo_prover::core::queries::Queries
+    pub fn sort_with_hint(m: usize) -> Script {
+        script! {
+            for _ in 0..m {
+                OP_TOALTSTACK
+            }
+
+            // pull the number of sorted queries, which is between 1 and m
+            OP_HINT
+            OP_DUP 1 { m + 1 } OP_WITHIN OP_VERIFY
+
+            // pull the sorted queries, the smallest one first
+            for i in 0..m {
+                OP_DUP { i } OP_GREATERTHAN
+                OP_IF
+                    OP_HINT OP_SWAP
+                OP_ENDIF
+            }
+
+            { Self::check_strictly_increasing(m) }
+
+            for _ in 0..m {
+                OP_FROMALTSTACK
+            }
+
+            // stack: sorted queries (k), k, drawn queries (m)
+            // the t-th sorted query from the top is at depth m + 1 + t
+
+            // each sorted query is one of the drawn queries
+            for t in 0..m {
+                { m } OP_PICK { t } OP_GREATERTHAN
+                OP_IF
+                    OP_FALSE
+                    for i in 0..m {
+                        { m + 2 + t } OP_PICK
+                        { m + 1 - i } OP_PICK
+                        OP_EQUAL OP_BOOLOR
+                    }
+                    OP_VERIFY
+                OP_ENDIF
+            }
+
+            // each drawn query is one of the sorted queries
+            for i in 0..m {
+                OP_FALSE
+                for t in 0..m {
+                    { m + 1 } OP_PICK { t } OP_GREATERTHAN
+                    OP_IF
+                        { m - i } OP_PICK
+                        { m + 3 + t } OP_PICK
+                        OP_EQUAL OP_BOOLOR
+                    OP_ENDIF
+                }
+                OP_VERIFY
+            }
+
+            // drop the drawn queries
+            for _ in 0..m {
+                OP_DROP
+            }
+        }
+    }
+
+    /// Drop the sorted queries and their number.
+    ///
+    /// Input:
+    /// - sorted queries (k, the largest one on the top), k, where k is at most m
+    pub fn drop_sorted(m: usize) -> Script {
+        script! {
+            for t in 0..m {
+                OP_DUP { t } OP_GREATERTHAN
+                OP_IF
+                    OP_NIP
+                OP_ENDIF
+            }
+            OP_DROP
+        }
+    }
+}
+
 /// Gadget for FFT.
 pub struct FFTGadget;
 
@@ -260,35 +745,38 @@ impl FFTGadget {
 
 #[cfg(test)]
 mod test {
-    use crate::channel::{ChannelWithHint, Sha256Channel};
+    use crate::channel::{ChannelWithHint, Sha256Channel, Sha256ChannelGadget};
     use crate::fri;
-    use crate::fri::{FFTGadget, FRIGadget, N_QUERIES};
+    use crate::fri::{
+        FFTGadget, FRIGadget, QueriesGadget, QueriesWithHint, SingleQueryHint, N_QUERIES,
+    };
+    use crate::pcs::{accumulate_quotients, QuotientAccumulationGadget};
     use crate::tests_utils::report::report_bitcoin_script_size;
+    use crate::tradeoff::Placement;
     use crate::treepp::*;
-    use crate::twiddle_merkle_tree::{TwiddleMerkleTree, TWIDDLE_MERKLE_TREE_ROOT_18};
-    use crate::utils::{get_rand_qm31, permute_eval};
+    use crate::twiddle_merkle_tree::{
+        TwiddleMerkleTree, TWIDDLE_MERKLE_TREE_ROOT_18, TWIDDLE_MERKLE_TREE_ROOT_4,
+    };
+    use crate::utils::{bit_reverse_index, get_rand_bws_sha256_hash, get_rand_qm31, permute_eval};
     use bitcoin_scriptexec::execute_script_with_witness_unlimited_stack;
     use num_traits::One;
-    use rand::{Rng, RngCore, SeedableRng};
+    use rand::{RngCore, SeedableRng};
     use rand_chacha::ChaCha20Rng;
-    use rust_bitcoin_m31::qm31_equalverify;
+    use rust_bitcoin_m31::{qm31_equalverify, qm31_roll};
     use stwo_prover::core::channel::Channel;
-    use stwo_prover::core::circle::CirclePointIndex;
+    use stwo_prover::core::circle::{CirclePointIndex, Coset};
     use stwo_prover::core::fft::ibutterfly;
     use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::fields::qm31::QM31;
     use stwo_prover::core::fields::FieldExpOps;
-    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+    use stwo_prover::core::poly::line::{LineDomain, LinePoly};
+    use stwo_prover::core::queries::Queries;
 
     #[test]
     fn test_twiddle_merkle_tree() {
         let logn = 19;
 
-        let mut prng = ChaCha20Rng::seed_from_u64(0);
-        let mut channel_init_state = [0u8; 32];
-        channel_init_state.iter_mut().for_each(|v| *v = prng.gen());
-        let channel_init_state = BWSSha256Hash::from(channel_init_state.to_vec());
-
-        let proof = {
+        let (channel_init_state, proof) = {
             let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
 
             let evaluation = (0..(1 << logn))
@@ -296,7 +784,7 @@ mod test {
                 .collect();
             let evaluation = permute_eval(evaluation);
 
-            fri::fri_prove(&mut Sha256Channel::new(channel_init_state), evaluation)
+            fri::fri_prove_with_seed(0, evaluation)
         };
 
         let queries = {
@@ -345,12 +833,7 @@ mod test {
     fn test_single_query_merkle_tree() {
         let logn = 19;
 
-        let mut prng = ChaCha20Rng::seed_from_u64(0);
-        let mut channel_init_state = [0u8; 32];
-        channel_init_state.iter_mut().for_each(|v| *v = prng.gen());
-        let channel_init_state = BWSSha256Hash::from(channel_init_state.to_vec());
-
-        let proof = {
+        let (channel_init_state, proof) = {
             let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
 
             let evaluation = (0..(1 << logn))
@@ -358,7 +841,7 @@ mod test {
                 .collect();
             let evaluation = permute_eval(evaluation);
 
-            fri::fri_prove(&mut Sha256Channel::new(channel_init_state), evaluation)
+            fri::fri_prove_with_seed(0, evaluation)
         };
 
         let queries = {
@@ -405,12 +888,7 @@ mod test {
     fn test_single_query_butterfly() {
         let logn = 19;
 
-        let mut prng = ChaCha20Rng::seed_from_u64(0);
-        let mut channel_init_state = [0u8; 32];
-        channel_init_state.iter_mut().for_each(|v| *v = prng.gen());
-        let channel_init_state = BWSSha256Hash::from(channel_init_state.to_vec());
-
-        let proof = {
+        let (channel_init_state, proof) = {
             let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
 
             let evaluation = (0..(1 << logn))
@@ -418,7 +896,7 @@ mod test {
                 .collect();
             let evaluation = permute_eval(evaluation);
 
-            fri::fri_prove(&mut Sha256Channel::new(channel_init_state), evaluation)
+            fri::fri_prove_with_seed(0, evaluation)
         };
 
         let (alphas, queries) = {
@@ -477,35 +955,21 @@ mod test {
 
     #[test]
     fn test_end_to_end() {
-        let channel_init_state = {
-            let mut prng = ChaCha20Rng::seed_from_u64(0);
-
-            let mut channel_init_state = [0u8; 32];
-            channel_init_state.iter_mut().for_each(|v| *v = prng.gen());
-            BWSSha256Hash::from(channel_init_state.to_vec())
-        };
-
-        let mut channel = Sha256Channel::new(channel_init_state);
         let logn = 19;
 
-        let proof = {
+        let (channel_init_state, proof) = {
             let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
 
-            let mut prng = ChaCha20Rng::seed_from_u64(0);
-
-            let mut channel_init_state = [0u8; 32];
-            channel_init_state.iter_mut().for_each(|v| *v = prng.gen());
-
-            let channel_init_state = BWSSha256Hash::from(channel_init_state.to_vec());
-
             let evaluation = (0..(1 << logn))
                 .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
                 .collect();
             let evaluation = permute_eval(evaluation);
 
-            fri::fri_prove(&mut Sha256Channel::new(channel_init_state), evaluation)
+            fri::fri_prove_with_seed(0, evaluation)
         };
 
+        let mut channel = Sha256Channel::new(channel_init_state);
+
         let expected_fiat_shamir = {
             let mut channel = Sha256Channel::new(channel_init_state);
             let mut expected_1 = vec![];
@@ -670,6 +1134,393 @@ mod test {
         assert!(exec_result.success);
     }
 
+    #[test]
+    fn test_incremental_verification() {
+        let logn = 5;
+
+        let (channel_init_state, proof) = {
+            let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
+
+            let evaluation = (0..(1 << logn))
+                .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
+                .collect();
+            let evaluation = permute_eval(evaluation);
+
+            fri::fri_prove_with_seed(0, evaluation)
+        };
+
+        let mut verifier = fri::FriIncrementalVerifier::new(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+        );
+
+        let mut steps = vec![];
+        for i in 0..(logn - 1) {
+            steps.push(verifier.verify_fri_layer(i));
+        }
+        steps.push(verifier.verify_last_layer());
+
+        let mut old_state_commitment = fri::FriVerifierState::new(channel_init_state).commitment();
+        for step in steps {
+            let script = script! {
+                { step.hints }
+                { step.script }
+                { step.state_commitment }
+                OP_EQUALVERIFY
+                { old_state_commitment }
+                OP_EQUAL
+            };
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            old_state_commitment = step.state_commitment;
+        }
+
+        for j in 0..N_QUERIES {
+            let step = verifier.verify_query(j);
+            assert_eq!(step.state_commitment, old_state_commitment);
+
+            let script = script! {
+                { step.hints }
+                { step.script.clone() }
+                { old_state_commitment }
+                OP_EQUAL
+            };
+            if j == 0 {
+                report_bitcoin_script_size("FRI", "Incremental-Query", step.script.len());
+            }
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+
+        // with the twiddle factors baked into the script, the query steps have no twiddle hints
+        let verifier = verifier.with_twiddle_placement(Placement::Inline);
+        for j in 0..N_QUERIES {
+            let step = verifier.verify_query(j);
+            let script = script! {
+                { step.hints }
+                { step.script.clone() }
+                { old_state_commitment }
+                OP_EQUAL
+            };
+            if j == 0 {
+                report_bitcoin_script_size("FRI", "Incremental-Query-Inline", step.script.len());
+            }
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
+
+    #[test]
+    fn test_verify_single_query() {
+        let logn = 5;
+
+        let (channel_init_state, proof) = {
+            let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
+
+            let evaluation = (0..(1 << logn))
+                .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
+                .collect();
+            let evaluation = permute_eval(evaluation);
+
+            fri::fri_prove_with_seed(0, evaluation)
+        };
+
+        let (alphas, queries) = {
+            let mut alphas = vec![];
+
+            let mut channel = Sha256Channel::new(channel_init_state);
+
+            for c in proof.commitments.iter() {
+                channel.mix_digest(*c);
+                let res = channel.draw_felt_and_hints();
+                alphas.push(res.0);
+            }
+            channel.mix_felts(&proof.last_layer);
+
+            let queries = channel.draw_queries_and_hints(N_QUERIES, logn).0;
+
+            (alphas, queries)
+        };
+
+        let verify_script = FRIGadget::verify_single_query(logn, TWIDDLE_MERKLE_TREE_ROOT_4);
+        report_bitcoin_script_size("FRI", "Single-Query", verify_script.len());
+
+        let state = script! {
+            for (c, alpha) in proof.commitments.iter().zip(alphas.iter()) {
+                { *c }
+                { *alpha }
+            }
+            { proof.last_layer[1] }
+            { proof.last_layer[0] }
+        };
+        let state_len = 5 * (logn - 1) + 8;
+
+        for j in [0, N_QUERIES - 1] {
+            let hint = SingleQueryHint::new(&proof, j);
+
+            let script = script! {
+                { hint.clone() }
+                { state.clone() }
+                { hint.leaf() }
+                { queries[j] }
+                { verify_script.clone() }
+                for _ in 0..(state_len / 2) {
+                    OP_2DROP
+                }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            // the decommitment of another query is rejected
+            let script = script! {
+                { hint.clone() }
+                { state.clone() }
+                { hint.leaf() }
+                { queries[j] ^ 1 }
+                { verify_script.clone() }
+                for _ in 0..(state_len / 2) {
+                    OP_2DROP
+                }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(!exec_result.success);
+
+            // a value that is not the leaf of the query is rejected
+            let script = script! {
+                { hint.clone() }
+                { state.clone() }
+                { hint.leaf() + QM31::one() }
+                { queries[j] }
+                { verify_script.clone() }
+                for _ in 0..(state_len / 2) {
+                    OP_2DROP
+                }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(!exec_result.success);
+        }
+
+        // the value is the combined quotient of a verifier of the commitment scheme
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        let hint = SingleQueryHint::new(&proof, 0);
+        let random_coeff = get_rand_qm31(&mut prng);
+        let mut quotients = (0..3).map(|_| get_rand_qm31(&mut prng)).collect::<Vec<_>>();
+        quotients[2] =
+            hint.leaf() - accumulate_quotients(random_coeff, &quotients[..2]) * random_coeff;
+        assert_eq!(accumulate_quotients(random_coeff, &quotients), hint.leaf());
+
+        let script = script! {
+            { hint }
+            { state.clone() }
+            { random_coeff }
+            for quotient in quotients.iter() {
+                { *quotient }
+            }
+            { QuotientAccumulationGadget::accumulate(quotients.len()) }
+            { queries[0] }
+            { verify_script.clone() }
+            for _ in 0..(state_len / 2) {
+                OP_2DROP
+            }
+            OP_TRUE
+        };
+        let exec_result = execute_script(script);
+        assert!(exec_result.success);
+    }
+
+    #[test]
+    fn test_fold_circle_into_line() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let logn = 6;
+        let evaluation = (0..(1 << logn))
+            .map(|_| get_rand_qm31(&mut prng))
+            .collect::<Vec<_>>();
+        let alpha = get_rand_qm31(&mut prng);
+
+        let folded = fri::fold_circle_into_line(&evaluation, alpha);
+
+        let fold_script = FRIGadget::fold_circle_into_line();
+        report_bitcoin_script_size("FRI", "Fold-Circle-Into-Line", fold_script.len());
+
+        for query in 0..(1 << logn) {
+            let hint = fri::CircleFoldHint::new(&evaluation, query);
+            assert_eq!(hint.fold(alpha), folded[query >> 1]);
+
+            let script = script! {
+                { alpha }
+                { hint }
+                { fold_script.clone() }
+                { folded[query >> 1] }
+                qm31_equalverify
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
+
+    #[test]
+    fn test_check_last_layer_poly() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let log_degree = 2;
+        let log_size = 5;
+        let domain = LineDomain::new(Coset::half_odds(log_size));
+        let last_layer = LinePoly::new(
+            (0..1 << log_degree)
+                .map(|_| get_rand_qm31(&mut prng))
+                .collect(),
+        );
+
+        let check_script = FRIGadget::check_last_layer_poly(&domain, log_degree);
+        report_bitcoin_script_size("FRI", "Check-Last-Layer-Poly", check_script.len());
+
+        for pos in 0..(1 << log_size) {
+            let folded = last_layer
+                .eval_at_point(domain.at(bit_reverse_index(pos, log_size as usize)).into());
+
+            let script = script! {
+                { &last_layer }
+                { folded }
+                { pos }
+                { check_script.clone() }
+                { &last_layer }
+                for i in 0..(1 << log_degree) {
+                    { qm31_roll((1 << log_degree) - i) }
+                    qm31_equalverify
+                }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            let script = script! {
+                { &last_layer }
+                { folded + QM31::one() }
+                { pos }
+                { check_script.clone() }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(!exec_result.success);
+        }
+    }
+
+    #[test]
+    fn test_sort_queries() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let logn = 20;
+        let sort_script = QueriesGadget::sort_with_hint(N_QUERIES);
+        report_bitcoin_script_size("FRI", "Sort-Queries", sort_script.len());
+
+        for _ in 0..10 {
+            let channel_init_state = get_rand_bws_sha256_hash(&mut prng);
+
+            let mut channel = Sha256Channel::new(channel_init_state);
+            let (drawn, draw_hint) = channel.draw_queries_and_hints(N_QUERIES, logn);
+
+            let sorted = fri::SortedQueriesHint::new(&drawn);
+            let queries = Queries::generate_with_hints(
+                &mut Sha256Channel::new(channel_init_state),
+                logn as u32,
+                N_QUERIES,
+            )
+            .0;
+            assert_eq!(sorted, fri::SortedQueriesHint::from(&queries));
+
+            let script = script! {
+                { draw_hint.clone() }
+                { sorted.clone() }
+                { channel_init_state }
+                { Sha256ChannelGadget::draw_numbers_with_hint(N_QUERIES, logn) }
+                { N_QUERIES } OP_ROLL OP_DROP
+                { sort_script.clone() }
+                { sorted.queries.len() }
+                OP_EQUALVERIFY
+                for query in sorted.queries.iter().rev() {
+                    { *query }
+                    OP_EQUALVERIFY
+                }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            // a hint that is not sorted, or does not match the drawn queries, is rejected
+            let mut unsorted = sorted.clone();
+            unsorted.queries.swap(0, 1);
+            let mut unrelated = sorted.clone();
+            unrelated.queries[0] = (unrelated.queries[0] + unrelated.queries[1]) / 2;
+            let mut missing = sorted.clone();
+            missing.queries.pop();
+
+            for hint in [unsorted, unrelated, missing] {
+                let script = script! {
+                    { draw_hint.clone() }
+                    { hint }
+                    { channel_init_state }
+                    { Sha256ChannelGadget::draw_numbers_with_hint(N_QUERIES, logn) }
+                    { N_QUERIES } OP_ROLL OP_DROP
+                    { sort_script.clone() }
+                    { QueriesGadget::drop_sorted(N_QUERIES) }
+                    OP_TRUE
+                };
+                let exec_result = execute_script(script);
+                assert!(!exec_result.success);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort_repeated_queries() {
+        let drawn = [7usize, 3, 7, 1];
+        let m = drawn.len();
+
+        let sorted = fri::SortedQueriesHint::new(&drawn);
+        assert_eq!(sorted.queries, vec![1, 3, 7]);
+
+        let script = script! {
+            { sorted.clone() }
+            for query in drawn.iter() {
+                { *query }
+            }
+            { QueriesGadget::sort_with_hint(m) }
+            3 OP_EQUALVERIFY
+            7 OP_EQUALVERIFY
+            3 OP_EQUALVERIFY
+            1 OP_EQUALVERIFY
+            OP_TRUE
+        };
+        let exec_result = execute_script(script);
+        assert!(exec_result.success);
+
+        // keeping the repeated query, or dropping a drawn one, is rejected
+        for queries in [vec![1, 3, 7, 7], vec![1, 7]] {
+            let script = script! {
+                { fri::SortedQueriesHint { queries } }
+                for query in drawn.iter() {
+                    { *query }
+                }
+                { QueriesGadget::sort_with_hint(m) }
+                { QueriesGadget::drop_sorted(m) }
+                OP_TRUE
+            };
+            let exec_result = execute_script(script);
+            assert!(!exec_result.success);
+        }
+    }
+
     #[test]
     fn test_ibutterfly() {
         let mut prng = ChaCha20Rng::seed_from_u64(0);
@@ -1,9 +1,16 @@
 use crate::channel::{ChannelWithHint, DrawHints, Sha256Channel};
 use crate::merkle_tree::{MerkleTree, MerkleTreeProof};
+use crate::tradeoff::Placement;
+use crate::treepp::pushable::{Builder, Pushable};
+use crate::treepp::*;
 use crate::twiddle_merkle_tree::{TwiddleMerkleTree, TwiddleMerkleTreeProof};
-use crate::utils::get_twiddles;
+use crate::utils::{get_rand_bws_sha256_hash, get_twiddles, hash_qm31, num_to_bytes};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::{Digest, Sha256};
 use stwo_prover::core::channel::Channel;
 use stwo_prover::core::fft::ibutterfly;
+use stwo_prover::core::fields::m31::M31;
 use stwo_prover::core::fields::qm31::QM31;
 use stwo_prover::core::fields::FieldExpOps;
 use stwo_prover::core::prover::N_QUERIES;
@@ -13,6 +20,9 @@ use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
 mod bitcoin_script;
 pub use bitcoin_script::*;
 
+mod streaming;
+pub use streaming::*;
+
 /// A trait for generating the queries with hints.
 pub trait QueriesWithHint: Sized {
     /// Generate the queries and the corresponding hints.
@@ -29,17 +39,61 @@ impl QueriesWithHint for Queries {
         log_domain_size: u32,
         n_queries: usize,
     ) -> (Self, DrawHints) {
-        let res = channel.draw_queries_and_hints(n_queries, log_domain_size as usize);
+        let (drawn, hints) = channel.draw_queries_and_hints(n_queries, log_domain_size as usize);
         (
             Self {
-                positions: res.0.into_iter().collect(),
+                positions: SortedQueriesHint::new(&drawn).queries,
                 log_domain_size,
             },
-            res.1,
+            hints,
         )
     }
 }
 
+/// The hint for sorting the drawn queries, see [`QueriesGadget::sort_with_hint`].
+///
+/// The queries are sorted and deduplicated, as in [`Queries::generate`], so there may be fewer
+/// of them than the drawn queries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SortedQueriesHint {
+    /// The sorted and deduplicated queries.
+    pub queries: Vec<usize>,
+}
+
+impl SortedQueriesHint {
+    /// Sort and deduplicate the drawn queries.
+    pub fn new(drawn: &[usize]) -> Self {
+        let mut queries = drawn.to_vec();
+        queries.sort_unstable();
+        queries.dedup();
+        Self { queries }
+    }
+}
+
+impl From<&Queries> for SortedQueriesHint {
+    fn from(queries: &Queries) -> Self {
+        Self {
+            queries: queries.positions.clone(),
+        }
+    }
+}
+
+impl Pushable for &SortedQueriesHint {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        builder = self.queries.len().bitcoin_script_push(builder);
+        for query in self.queries.iter() {
+            builder = query.bitcoin_script_push(builder);
+        }
+        builder
+    }
+}
+
+impl Pushable for SortedQueriesHint {
+    fn bitcoin_script_push(self, builder: Builder) -> Builder {
+        (&self).bitcoin_script_push(builder)
+    }
+}
+
 /// A FRI proof.
 #[derive(Clone, Debug)]
 pub struct FriProof {
@@ -50,8 +104,137 @@ pub struct FriProof {
     twiddle_merkle_proofs: Vec<TwiddleMerkleTreeProof>,
 }
 
+/// The hint for verifying one query across all the layers, see
+/// [`FRIGadget::verify_single_query`].
+#[derive(Clone, Debug)]
+pub struct SingleQueryHint {
+    /// The Merkle tree proof of the query in the first layer, whose leaf is the value of the
+    /// query.
+    pub leaf_merkle_proof: MerkleTreeProof,
+    /// The twiddle Merkle tree proof of the query, or `None` if the twiddle factors are baked
+    /// into the script.
+    pub twiddle_merkle_proof: Option<TwiddleMerkleTreeProof>,
+    /// The Merkle tree proofs of the siblings in each layer, the first layer's first.
+    pub merkle_proofs: Vec<MerkleTreeProof>,
+}
+
+impl SingleQueryHint {
+    /// Extract the decommitment of the i-th query from the proof.
+    ///
+    /// The proof of the query in the first layer is derived from the proof of its sibling,
+    /// which shares the path above the leaves.
+    pub fn new(proof: &FriProof, i: usize) -> Self {
+        Self::with_placement(proof, i, Placement::Hinted)
+    }
+
+    /// Extract the decommitment of the i-th query from the proof, for a verifier that places the
+    /// twiddle factors as given, see [`FRIGadget::verify_single_query_with_placement`].
+    pub fn with_placement(proof: &FriProof, i: usize, twiddle_placement: Placement) -> Self {
+        let sibling_proof = &proof.merkle_proofs[i][0];
+        let mut siblings = sibling_proof.siblings.clone();
+        siblings[0] = hash_qm31(&sibling_proof.leaf);
+
+        Self {
+            leaf_merkle_proof: MerkleTreeProof {
+                leaf: proof.leaves[i],
+                siblings,
+            },
+            twiddle_merkle_proof: match twiddle_placement {
+                Placement::Inline => None,
+                Placement::Hinted => Some(proof.twiddle_merkle_proofs[i].clone()),
+            },
+            merkle_proofs: proof.merkle_proofs[i].clone(),
+        }
+    }
+
+    /// The value of the query in the first layer.
+    pub fn leaf(&self) -> QM31 {
+        self.leaf_merkle_proof.leaf
+    }
+}
+
+impl Pushable for &SingleQueryHint {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        builder = (&self.leaf_merkle_proof).bitcoin_script_push(builder);
+        if let Some(twiddle_merkle_proof) = &self.twiddle_merkle_proof {
+            builder = twiddle_merkle_proof.bitcoin_script_push(builder);
+        }
+        for proof in self.merkle_proofs.iter() {
+            builder = proof.bitcoin_script_push(builder);
+        }
+        builder
+    }
+}
+
+impl Pushable for SingleQueryHint {
+    fn bitcoin_script_push(self, builder: Builder) -> Builder {
+        (&self).bitcoin_script_push(builder)
+    }
+}
+
+fn fold_layer(layer: &[QM31], twiddles: &[M31], alpha: QM31) -> Vec<QM31> {
+    layer
+        .chunks_exact(2)
+        .zip(twiddles)
+        .map(|(f, twid)| fold_pair(f[0], f[1], twid.inverse(), alpha))
+        .collect()
+}
+
+fn fold_pair(mut f0: QM31, mut f1: QM31, itwid: M31, alpha: QM31) -> QM31 {
+    ibutterfly(&mut f0, &mut f1, itwid);
+    f0 + alpha * f1
+}
+
+/// Fold an evaluation over the circle domain (in the bit-reversed order) into the line domain,
+/// which is the first fold of FRI.
+pub fn fold_circle_into_line(evaluation: &[QM31], alpha: QM31) -> Vec<QM31> {
+    let logn = evaluation.len().ilog2() as usize;
+    fold_layer(evaluation, &get_twiddles(logn)[0], alpha)
+}
+
+/// The hint for folding one query from the circle domain into the line domain.
+#[derive(Clone, Copy, Debug)]
+pub struct CircleFoldHint {
+    /// The value at the point p, which is at the even position of the pair.
+    pub f_p: QM31,
+    /// The value at the point -p, which is at the odd position of the pair.
+    pub f_neg_p: QM31,
+    /// The inverse of the y coordinate of p.
+    pub itwid: M31,
+}
+
+impl CircleFoldHint {
+    /// Compute the hint for a query of an evaluation over the circle domain (in the bit-reversed
+    /// order).
+    pub fn new(evaluation: &[QM31], query: usize) -> Self {
+        let logn = evaluation.len().ilog2() as usize;
+        let pair = query >> 1;
+        Self {
+            f_p: evaluation[pair << 1],
+            f_neg_p: evaluation[(pair << 1) | 1],
+            itwid: get_twiddles(logn)[0][pair].inverse(),
+        }
+    }
+
+    /// Compute the folded value.
+    pub fn fold(&self, alpha: QM31) -> QM31 {
+        fold_pair(self.f_p, self.f_neg_p, self.itwid, alpha)
+    }
+}
+
+impl Pushable for CircleFoldHint {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        builder = self.f_p.bitcoin_script_push(builder);
+        builder = self.f_neg_p.bitcoin_script_push(builder);
+        self.itwid.bitcoin_script_push(builder)
+    }
+}
+
 /// Generate a FRI proof.
 pub fn fri_prove(channel: &mut Sha256Channel, evaluation: Vec<QM31>) -> FriProof {
+    #[cfg(feature = "profiling")]
+    let _scope = crate::profiling::scope("fri_prove");
+
     let logn = evaluation.len().ilog2() as usize;
     let n_layers = logn - 1;
     let twiddles = get_twiddles(logn);
@@ -74,15 +257,7 @@ pub fn fri_prove(channel: &mut Sha256Channel, evaluation: Vec<QM31>) -> FriProof
 
         let (alpha, _) = channel.draw_felt_and_hints();
 
-        layer = layer
-            .chunks_exact(2)
-            .zip(layer_twiddles)
-            .map(|(f, twid)| {
-                let (mut f0, mut f1) = (f[0], f[1]);
-                ibutterfly(&mut f0, &mut f1, twid.inverse());
-                f0 + alpha * f1
-            })
-            .collect();
+        layer = fold_layer(&layer, layer_twiddles, alpha);
     }
 
     // Last layer.
@@ -118,6 +293,17 @@ pub fn fri_prove(channel: &mut Sha256Channel, evaluation: Vec<QM31>) -> FriProof
     }
 }
 
+/// Generate a FRI proof with the channel initial state derived from a seed.
+///
+/// Return the channel initial state together with the proof, which can be used for verification.
+pub fn fri_prove_with_seed(seed: u64, evaluation: Vec<QM31>) -> (BWSSha256Hash, FriProof) {
+    let mut prng = ChaCha20Rng::seed_from_u64(seed);
+    let channel_init_state = get_rand_bws_sha256_hash(&mut prng);
+
+    let proof = fri_prove(&mut Sha256Channel::new(channel_init_state), evaluation);
+    (channel_init_state, proof)
+}
+
 /// Verify the FRI proof.
 pub fn fri_verify(
     channel: &mut Sha256Channel,
@@ -186,3 +372,228 @@ pub fn fri_verify(
         assert_eq!(leaf, proof.last_layer[query]);
     }
 }
+
+/// The state carried between the steps of an incremental FRI verification.
+///
+/// The state is committed as `sha256(channel_digest || layers_digest)`, where the layers digest
+/// absorbs, in order, the commitment and the folding factor of each layer, then the last layer,
+/// then the queries. Each step script recomputes the commitment of the state it starts from and
+/// the one it ends with, so that the steps can be verified one at a time.
+#[derive(Clone, Debug)]
+pub struct FriVerifierState {
+    /// The channel digest.
+    pub channel_digest: BWSSha256Hash,
+    /// The commitment and the folding factor of each layer verified so far.
+    pub layers: Vec<(BWSSha256Hash, QM31)>,
+    /// The last layer, once verified.
+    pub last_layer: Vec<QM31>,
+    /// The queries, once drawn.
+    pub queries: Vec<usize>,
+}
+
+impl FriVerifierState {
+    /// Start from the channel initial state.
+    pub fn new(channel_init_state: BWSSha256Hash) -> Self {
+        Self {
+            channel_digest: channel_init_state,
+            layers: vec![],
+            last_layer: vec![],
+            queries: vec![],
+        }
+    }
+
+    /// Compute the digest of the layers, the last layer, and the queries.
+    pub fn layers_digest(&self) -> BWSSha256Hash {
+        let mut acc = Sha256Channel::new(BWSSha256Hash::from(vec![0u8; 32]));
+        for (commitment, alpha) in self.layers.iter() {
+            acc.mix_digest(*commitment);
+            acc.mix_felts(&[*alpha]);
+        }
+        acc.mix_felts(&self.last_layer);
+        for query in self.queries.iter() {
+            let mut hasher = Sha256::new();
+            Digest::update(&mut hasher, num_to_bytes(M31::from(*query as u32)));
+            acc.mix_digest(BWSSha256Hash::from(hasher.finalize().as_slice()));
+        }
+        acc.digest
+    }
+
+    /// Compute the commitment of the state.
+    pub fn commitment(&self) -> BWSSha256Hash {
+        let mut hasher = Sha256::new();
+        Digest::update(&mut hasher, self.channel_digest.as_ref());
+        Digest::update(&mut hasher, self.layers_digest().as_ref());
+        BWSSha256Hash::from(hasher.finalize().as_slice())
+    }
+}
+
+/// A step of an incremental FRI verification.
+pub struct FriVerificationStep {
+    /// The script of the step.
+    pub script: Script,
+    /// The hints of the step, which are pushed before the script.
+    pub hints: Script,
+    /// The commitment of the state after the step.
+    pub state_commitment: BWSSha256Hash,
+}
+
+/// Verifier that splits the FRI verification into resumable steps.
+///
+/// The steps are [`Self::verify_fri_layer`] for each layer in order, then
+/// [`Self::verify_last_layer`], then [`Self::verify_query`] for each query in any order. Each step
+/// comes with its own script and hints, which can be executed separately to locate a failure.
+pub struct FriIncrementalVerifier<'a> {
+    logn: usize,
+    proof: &'a FriProof,
+    twiddle_merkle_tree_root: [u8; 32],
+    twiddle_placement: Placement,
+    state: FriVerifierState,
+}
+
+impl<'a> FriIncrementalVerifier<'a> {
+    /// Start verifying a FRI proof.
+    pub fn new(
+        channel_init_state: BWSSha256Hash,
+        logn: usize,
+        proof: &'a FriProof,
+        twiddle_merkle_tree_root: [u8; 32],
+    ) -> Self {
+        assert_eq!(proof.commitments.len(), logn - 1);
+        Self {
+            logn,
+            proof,
+            twiddle_merkle_tree_root,
+            twiddle_placement: Placement::Hinted,
+            state: FriVerifierState::new(channel_init_state),
+        }
+    }
+
+    /// Resume the verification of a FRI proof from a state stored by a previous verifier, e.g.,
+    /// to rebuild the hints of a step after the previous ones are verified on-chain.
+    pub fn resume(
+        state: FriVerifierState,
+        logn: usize,
+        proof: &'a FriProof,
+        twiddle_merkle_tree_root: [u8; 32],
+    ) -> Self {
+        assert_eq!(proof.commitments.len(), logn - 1);
+        Self {
+            logn,
+            proof,
+            twiddle_merkle_tree_root,
+            twiddle_placement: Placement::Hinted,
+            state,
+        }
+    }
+
+    /// Place the twiddle factors of the query steps as given, e.g., the cheaper placement
+    /// reported by [`crate::tradeoff::cheaper_twiddle_placement`], instead of hinting them.
+    pub fn with_twiddle_placement(mut self, twiddle_placement: Placement) -> Self {
+        self.twiddle_placement = twiddle_placement;
+        self
+    }
+
+    /// The current state.
+    pub fn state(&self) -> &FriVerifierState {
+        &self.state
+    }
+
+    /// Absorb the commitment of the i-th layer and draw its folding factor.
+    pub fn verify_fri_layer(&mut self, i: usize) -> FriVerificationStep {
+        assert_eq!(
+            i,
+            self.state.layers.len(),
+            "the layers must be verified in order"
+        );
+
+        let commitment = self.proof.commitments[i];
+        let layers_digest = self.state.layers_digest();
+
+        let mut channel = Sha256Channel::new(self.state.channel_digest);
+        let state_digest = channel.digest;
+        channel.mix_digest(commitment);
+        let (alpha, alpha_hint) = channel.draw_felt_and_hints();
+
+        self.state.channel_digest = channel.digest;
+        self.state.layers.push((commitment, alpha));
+
+        FriVerificationStep {
+            script: FRIGadget::verify_layer_step(),
+            hints: script! {
+                { state_digest }
+                { layers_digest }
+                { commitment }
+                { alpha_hint }
+            },
+            state_commitment: self.state.commitment(),
+        }
+    }
+
+    /// Absorb the last layer, check that it is of half degree, and draw the queries.
+    pub fn verify_last_layer(&mut self) -> FriVerificationStep {
+        assert_eq!(
+            self.state.layers.len(),
+            self.logn - 1,
+            "all the layers must be verified first"
+        );
+        assert!(self.state.queries.is_empty());
+
+        let layers_digest = self.state.layers_digest();
+
+        let mut channel = Sha256Channel::new(self.state.channel_digest);
+        let state_digest = channel.digest;
+        channel.mix_felts(&self.proof.last_layer);
+        let (queries, queries_hint) = channel.draw_queries_and_hints(N_QUERIES, self.logn);
+
+        self.state.channel_digest = channel.digest;
+        self.state.last_layer = self.proof.last_layer.clone();
+        self.state.queries = queries;
+
+        FriVerificationStep {
+            script: FRIGadget::verify_last_layer_step(self.logn),
+            hints: script! {
+                { state_digest }
+                { layers_digest }
+                for elem in self.proof.last_layer.iter() {
+                    { *elem }
+                }
+                { queries_hint }
+            },
+            state_commitment: self.state.commitment(),
+        }
+    }
+
+    /// Check the decommitment and the folding of the j-th query, which leaves the state unchanged.
+    pub fn verify_query(&self, j: usize) -> FriVerificationStep {
+        assert_eq!(
+            self.state.queries.len(),
+            N_QUERIES,
+            "the last layer must be verified first"
+        );
+
+        FriVerificationStep {
+            script: FRIGadget::verify_query_step_with_placement(
+                self.logn,
+                j,
+                self.twiddle_merkle_tree_root,
+                self.twiddle_placement,
+            ),
+            hints: script! {
+                { self.state.channel_digest }
+                for (commitment, alpha) in self.state.layers.iter() {
+                    { *commitment }
+                    { *alpha }
+                }
+                for elem in self.state.last_layer.iter() {
+                    { *elem }
+                }
+                for query in self.state.queries.iter() {
+                    { *query }
+                }
+                { self.proof.leaves[j] }
+                { SingleQueryHint::with_placement(self.proof, j, self.twiddle_placement) }
+            },
+            state_commitment: self.state.commitment(),
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::channel::{ChannelWithHint, Sha256Channel};
+use crate::fri::{fold_layer, FriProof};
+use crate::merkle_tree::{MerkleTree, MerkleTreeProof};
+use crate::twiddle_merkle_tree::{TwiddleMerkleTree, TwiddleMerkleTreeProof};
+use crate::utils::get_twiddles;
+use stwo_prover::core::channel::Channel;
+use stwo_prover::core::fields::qm31::QM31;
+use stwo_prover::core::prover::N_QUERIES;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// A part of a FRI proof, which is final as soon as it is produced, see [`fri_prove_streaming`].
+#[derive(Clone, Debug)]
+pub enum FriProofSegment {
+    /// The commitment of the next layer.
+    Commitment(BWSSha256Hash),
+    /// The last layer.
+    LastLayer(Vec<QM31>),
+    /// The leaf of the `query`-th query.
+    Leaf {
+        /// The index of the query.
+        query: usize,
+        /// The leaf.
+        leaf: QM31,
+    },
+    /// The Merkle tree proof of the sibling of the `query`-th query in a layer.
+    Decommitment {
+        /// The index of the query.
+        query: usize,
+        /// The index of the layer.
+        layer: usize,
+        /// The Merkle tree proof.
+        proof: MerkleTreeProof,
+    },
+    /// The twiddle Merkle tree proof of the `query`-th query.
+    TwiddleMerkleProof {
+        /// The index of the query.
+        query: usize,
+        /// The twiddle Merkle tree proof.
+        proof: TwiddleMerkleTreeProof,
+    },
+}
+
+/// Generate the same FRI proof as [`crate::fri::fri_prove`], but hand it out in segments as soon
+/// as they are final, so that they can be written out instead of being held in memory.
+///
+/// Only one layer and its Merkle tree are held at a time, next to the evaluation: the commit
+/// phase keeps the folding factors and drops each tree once its root is in the channel, and the
+/// decommit phase folds the layers again, one at a time, to open them at the queries. This
+/// trades one more round of folding and hashing for the memory of all the layers and trees.
+///
+/// The segments come in this order: the commitments, the last layer, the leaves, the
+/// decommitments layer by layer (the queries in order within a layer), and the twiddle Merkle
+/// tree proofs. They can be put back together with [`FriProof::from_segments`].
+pub fn fri_prove_streaming<F: FnMut(FriProofSegment)>(
+    channel: &mut Sha256Channel,
+    evaluation: Vec<QM31>,
+    mut on_segment: F,
+) {
+    let logn = evaluation.len().ilog2() as usize;
+    let n_layers = logn - 1;
+    let twiddles = get_twiddles(logn);
+
+    // Commit.
+    let mut alphas = Vec::with_capacity(n_layers);
+    let mut layer = evaluation.clone();
+    for layer_twiddles in twiddles.iter().take(n_layers) {
+        let tree = MerkleTree::new(layer);
+
+        channel.mix_digest(tree.root_hash);
+        on_segment(FriProofSegment::Commitment(tree.root_hash));
+
+        let (alpha, _) = channel.draw_felt_and_hints();
+        alphas.push(alpha);
+
+        layer = fold_layer(&tree.leaf_layer, layer_twiddles, alpha);
+    }
+
+    // Last layer.
+    channel.mix_felts(&layer);
+    on_segment(FriProofSegment::LastLayer(layer));
+
+    // Queries.
+    let queries = channel.draw_queries_and_hints(N_QUERIES, logn).0.to_vec();
+
+    // Decommit.
+    for (query, &position) in queries.iter().enumerate() {
+        on_segment(FriProofSegment::Leaf {
+            query,
+            leaf: evaluation[position],
+        });
+    }
+
+    let mut positions = queries.clone();
+    let mut layer = evaluation;
+    for (i, (layer_twiddles, alpha)) in twiddles.iter().zip(alphas).enumerate() {
+        let tree = MerkleTree::new(layer);
+        for (query, position) in positions.iter_mut().enumerate() {
+            on_segment(FriProofSegment::Decommitment {
+                query,
+                layer: i,
+                proof: tree.query(*position ^ 1),
+            });
+            *position >>= 1;
+        }
+        layer = fold_layer(&tree.leaf_layer, layer_twiddles, alpha);
+    }
+    drop(layer);
+
+    let twiddle_merkle_tree = TwiddleMerkleTree::new(n_layers);
+    for (query, &position) in queries.iter().enumerate() {
+        on_segment(FriProofSegment::TwiddleMerkleProof {
+            query,
+            proof: twiddle_merkle_tree.query(position),
+        });
+    }
+}
+
+impl FriProof {
+    /// Put the segments from [`fri_prove_streaming`] back together, in the same order.
+    pub fn from_segments<I: IntoIterator<Item = FriProofSegment>>(segments: I) -> Self {
+        let mut proof = FriProof {
+            commitments: vec![],
+            last_layer: vec![],
+            leaves: vec![],
+            merkle_proofs: vec![],
+            twiddle_merkle_proofs: vec![],
+        };
+
+        for segment in segments {
+            match segment {
+                FriProofSegment::Commitment(commitment) => proof.commitments.push(commitment),
+                FriProofSegment::LastLayer(last_layer) => proof.last_layer = last_layer,
+                FriProofSegment::Leaf { query, leaf } => {
+                    assert_eq!(query, proof.leaves.len());
+                    proof.leaves.push(leaf);
+                }
+                FriProofSegment::Decommitment {
+                    query,
+                    layer,
+                    proof: merkle_proof,
+                } => {
+                    if layer == 0 {
+                        assert_eq!(query, proof.merkle_proofs.len());
+                        proof.merkle_proofs.push(vec![]);
+                    }
+                    assert_eq!(layer, proof.merkle_proofs[query].len());
+                    proof.merkle_proofs[query].push(merkle_proof);
+                }
+                FriProofSegment::TwiddleMerkleProof {
+                    query,
+                    proof: twiddle_merkle_proof,
+                } => {
+                    assert_eq!(query, proof.twiddle_merkle_proofs.len());
+                    proof.twiddle_merkle_proofs.push(twiddle_merkle_proof);
+                }
+            }
+        }
+        proof
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::channel::Sha256Channel;
+    use crate::fri::{fri_prove, fri_prove_streaming, FRIGadget, FriProof, FriProofSegment};
+    use crate::utils::{get_rand_bws_sha256_hash, permute_eval};
+    use num_traits::One;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use stwo_prover::core::circle::CirclePointIndex;
+    use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::fields::FieldExpOps;
+    use stwo_prover::core::prover::N_QUERIES;
+
+    #[test]
+    fn test_fri_prove_streaming() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+        let channel_init_state = get_rand_bws_sha256_hash(&mut prng);
+
+        let logn = 10;
+        let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
+        let evaluation = (0..(1 << logn))
+            .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
+            .collect();
+        let evaluation = permute_eval(evaluation);
+
+        let expected = fri_prove(
+            &mut Sha256Channel::new(channel_init_state),
+            evaluation.clone(),
+        );
+
+        let mut segments = vec![];
+        fri_prove_streaming(
+            &mut Sha256Channel::new(channel_init_state),
+            evaluation,
+            |segment| segments.push(segment),
+        );
+        assert!(matches!(segments[0], FriProofSegment::Commitment(_)));
+        let proof = FriProof::from_segments(segments);
+
+        assert_eq!(proof.commitments, expected.commitments);
+        assert_eq!(proof.last_layer, expected.last_layer);
+        assert_eq!(proof.leaves, expected.leaves);
+        assert_eq!(
+            FRIGadget::push_twiddle_merkle_tree_proof(&proof),
+            FRIGadget::push_twiddle_merkle_tree_proof(&expected)
+        );
+        for i in 0..N_QUERIES {
+            assert_eq!(
+                FRIGadget::push_single_query_merkle_tree_proof(i, &proof),
+                FRIGadget::push_single_query_merkle_tree_proof(i, &expected)
+            );
+        }
+    }
+}
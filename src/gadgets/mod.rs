@@ -0,0 +1,121 @@
+pub use crate::Script;
+
+/// Gadgets for the M31, CM31, and QM31 fields, from rust-bitcoin-m31.
+pub mod field {
+    pub use rust_bitcoin_m31::{
+        m31_add_n31, m31_sub, push_m31_one, push_n31_one, push_qm31_one, qm31_add, qm31_copy,
+        qm31_double, qm31_drop, qm31_dup, qm31_equalverify, qm31_from_bottom, qm31_fromaltstack,
+        qm31_mul, qm31_mul_by_constant, qm31_mul_m31, qm31_mul_m31_by_constant, qm31_neg,
+        qm31_over, qm31_roll, qm31_rot, qm31_shift_by_i, qm31_shift_by_ij, qm31_shift_by_j,
+        qm31_square, qm31_sub, qm31_swap, qm31_toaltstack, MOD,
+    };
+}
+
+/// Gadgets for points of the circle curve over QM31.
+pub mod circle {
+    pub use crate::circle::CirclePointGadget;
+}
+
+/// Gadgets for the Fiat-Shamir channel, with the native channel that produces their hints.
+pub mod channel {
+    pub use crate::channel::{ChannelWithHint, DrawHints, Sha256Channel, Sha256ChannelGadget};
+}
+
+/// Gadgets for the canonical encoding of digests.
+pub mod digest {
+    pub use crate::digest::{
+        digest_from_canonical_bytes, digest_from_hex, digest_to_hex, push_digest, CanonicalDigest,
+        DIGEST_SIZE,
+    };
+}
+
+/// Gadgets for the proof-of-work check.
+pub mod pow {
+    pub use crate::pow::{grind_find_nonce, hash_with_nonce, PoWHint, PowGadget};
+}
+
+/// Gadgets for the binary Merkle trees over QM31 leaves, over the columns of the commitment
+/// scheme, and over twiddle factors.
+pub mod merkle {
+    pub use crate::merkle_tree::{
+        hash_column_values, split_decommitment, try_split_decommitment, ColumnMerkleProof,
+        ColumnMerkleTreeGadget, MerkleTree, MerkleTreeGadget, MerkleTreeProof,
+    };
+    pub use crate::twiddle_merkle_tree::{
+        TwiddleMerkleTree, TwiddleMerkleTreeGadget, TwiddleMerkleTreeProof,
+    };
+}
+
+/// Gadgets for the openings of the polynomial commitment scheme.
+pub mod pcs {
+    pub use crate::pcs::{accumulate_quotients, QuotientAccumulationGadget};
+    // kept at this path since the openings moved into the Merkle module, and the table of
+    // powers into its own module
+    pub use crate::merkle_tree::{
+        hash_column_values, split_decommitment, ColumnMerkleProof, ColumnMerkleTreeGadget,
+    };
+    pub use crate::powers::{powers, PowersGadget};
+}
+
+/// Gadgets for the table of powers of a challenge, computed or hinted.
+pub mod powers {
+    pub use crate::powers::{powers, PowersGadget, PowersHint};
+}
+
+/// Gadgets for the line domain and line polynomials of FRI.
+pub mod line {
+    pub use crate::line::{line_domain_bit_reversed_points, LineDomainGadget, LinePolyGadget};
+}
+
+/// Gadgets for the FRI protocol.
+pub mod fri {
+    pub use crate::fri::{
+        fold_circle_into_line, CircleFoldHint, FFTGadget, FRIGadget, FriProof, QueriesWithHint,
+        SingleQueryHint,
+    };
+}
+
+/// Gadgets for out-of-domain sampling.
+pub mod oods {
+    pub use crate::oods::{OODSGadget, OODSHint, OODS};
+}
+
+/// Gadgets for evaluating an AIR at the sampled points.
+pub mod air {
+    pub use crate::air::{AirGadget, CompositionHint};
+    pub use crate::constraints::ConstraintsGadget;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::gadgets::field::qm31_equalverify;
+    use crate::gadgets::merkle::{MerkleTree, MerkleTreeGadget};
+    use crate::treepp::*;
+    use crate::utils::get_rand_qm31;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+
+    #[test]
+    fn test_gadgets_merkle_tree() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let logn = 10;
+        let last_layer = (0..1 << logn)
+            .map(|_| get_rand_qm31(&mut prng))
+            .collect::<Vec<_>>();
+        let merkle_tree = MerkleTree::new(last_layer.clone());
+
+        let pos = prng.gen::<u32>() & ((1 << logn) - 1);
+        let script = script! {
+            { merkle_tree.query(pos as usize) }
+            { merkle_tree.root_hash }
+            { pos }
+            { MerkleTreeGadget::query_and_verify(logn) }
+            { last_layer[pos as usize] }
+            qm31_equalverify
+            OP_TRUE
+        };
+        let exec_result = execute_script(script);
+        assert!(exec_result.success);
+    }
+}
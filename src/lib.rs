@@ -3,6 +3,7 @@
 
 #![deny(missing_docs)]
 
+use crate::digest::CanonicalDigest;
 use crate::treepp::pushable::{Builder, Pushable};
 use stwo_prover::core::circle::CirclePoint;
 use stwo_prover::core::fields::m31::M31;
@@ -11,28 +12,77 @@ use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
 
 /// Module for AIR-related features.
 pub mod air;
+/// Module for caching the hints on disk.
+pub mod cache;
 /// Module for absorbing and squeezing of the channel.
 pub mod channel;
 /// Module for the circle curve over the qm31 field.
 pub mod circle;
 /// Module for constraints over the circle curve
 pub mod constraints;
+/// Module for the canonical encoding of digests.
+pub mod digest;
+/// Module for rendering scripts as annotated ASM and hex for auditing.
+pub mod export;
 /// Module for Fibonacci end-to-end test.
 pub mod fibonacci;
 /// Module for FRI.
 pub mod fri;
-/// Module for the Merkle tree.
+/// Module for the stable public surface of the gadgets, grouped by primitive with their hint
+/// types, for projects that reuse the gadgets without the verifier of this crate.
+///
+/// The paths under this module do not change when the other modules are reorganized, and an
+/// item is only removed or changed in a breaking way with a bump of the major version.
+pub mod gadgets;
+/// Module for the line domain and line polynomials.
+pub mod line;
+/// Module for the Merkle trees, including the trees of the commitment scheme.
 pub mod merkle_tree;
 /// Module for out-of-domain sampling.
 pub mod oods;
+/// Module for the chain of transactions that verifies a proof step by step.
+pub mod orchestrator;
+/// Module for combining the quotients of the openings of the polynomial commitment scheme.
+pub mod pcs;
+/// Module for the file-based pipeline producing the artifacts for a locked output.
+pub mod pipeline;
 /// Module for PoW.
 pub mod pow;
+/// Module for the table of powers of a challenge.
+pub mod powers;
+/// Module for timing scopes and flamegraphs of the hint generation.
+#[cfg(feature = "profiling")]
+pub mod profiling;
+/// Module for the identifier of a verifier program.
+pub mod program;
+/// Module for importing and exporting proofs.
+pub mod proof;
+/// Module for the client of a bitcoind node.
+#[cfg(feature = "rpc")]
+pub mod rpc;
+/// Module for stepping through the execution of a script.
+pub mod simulator;
+/// Module for the taproot outputs holding the verifier.
+pub mod taproot;
 /// Module for test utils.
 pub mod tests_utils;
+/// Module for choosing between baking values into the script and hinting them.
+pub mod tradeoff;
 /// Module for the twiddle Merkle tree.
 pub mod twiddle_merkle_tree;
 /// Module for utility functions.
 pub mod utils;
+/// Module for a vault spent by a beneficiary with a proof of a designated statement.
+pub mod vault;
+/// Module for the watchtower that follows a verification chain on-chain.
+#[cfg(feature = "watchtower")]
+pub mod watchtower;
+/// Module for converting between hints and witnesses.
+pub mod witness;
+
+/// The script type of all the gadgets, which is [`bitcoin::ScriptBuf`], so that scripts from this
+/// crate can be passed to rust-bitcoin without any conversion.
+pub use treepp::Script;
 
 pub(crate) mod treepp {
     pub use bitcoin_script::{define_pushable, script};
@@ -60,7 +110,9 @@ impl Pushable for QM31 {
 
 impl Pushable for BWSSha256Hash {
     fn bitcoin_script_push(self, builder: Builder) -> Builder {
-        self.as_ref().to_vec().bitcoin_script_push(builder)
+        self.to_canonical_bytes()
+            .to_vec()
+            .bitcoin_script_push(builder)
     }
 }
 
@@ -91,14 +143,13 @@ mod test {
     use crate::twiddle_merkle_tree::TWIDDLE_MERKLE_TREE_ROOT_4;
     use crate::utils::{get_rand_qm31, permute_eval};
     use num_traits::One;
-    use rand::{Rng, RngCore, SeedableRng};
+    use rand::{RngCore, SeedableRng};
     use rand_chacha::ChaCha20Rng;
     use stwo_prover::core::channel::Channel;
     use stwo_prover::core::circle::CirclePointIndex;
     use stwo_prover::core::fields::m31::M31;
     use stwo_prover::core::fields::qm31::QM31;
     use stwo_prover::core::fields::FieldExpOps;
-    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
 
     #[test]
     fn test_pushable() {
@@ -123,13 +174,6 @@ mod test {
         let logn = 5;
         let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
 
-        let mut prng = ChaCha20Rng::seed_from_u64(0);
-
-        let mut channel_init_state = [0u8; 32];
-        channel_init_state.iter_mut().for_each(|v| *v = prng.gen());
-
-        let channel_init_state = BWSSha256Hash::from(channel_init_state.to_vec());
-
         // Note: Add another .square() to make the proof fail.
         let evaluation = (0..(1 << logn))
             .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
@@ -137,7 +181,7 @@ mod test {
         let evaluation = permute_eval(evaluation);
 
         // FRI.
-        let proof = fri::fri_prove(&mut Sha256Channel::new(channel_init_state), evaluation);
+        let (channel_init_state, proof) = fri::fri_prove_with_seed(0, evaluation);
         fri::fri_verify(
             &mut Sha256Channel::new(channel_init_state),
             logn,
@@ -0,0 +1,363 @@
+use crate::treepp::*;
+use crate::utils::limb_to_be_bits;
+use rust_bitcoin_m31::{m31_add, m31_mul, m31_sub, qm31_add, qm31_mul_m31};
+use stwo_prover::core::circle::CirclePoint;
+use stwo_prover::core::fields::m31::M31;
+use stwo_prover::core::poly::line::LineDomain;
+
+/// Gadget for the line domain.
+pub struct LineDomainGadget;
+
+impl LineDomainGadget {
+    /// Check that the position on the top of the stack is in a domain of size 2^log_size.
+    ///
+    /// Input:
+    /// - pos
+    ///
+    /// Output:
+    /// - pos
+    pub fn check_position(log_size: usize) -> Script {
+        script! {
+            OP_DUP 0 { 1 << log_size } OP_WITHIN OP_VERIFY
+        }
+    }
+
+    /// Look up the x coordinate of the point at a (bit-reversed) position from the pushed line
+    /// domain, which is kept on the stack.
+    ///
+    /// Input:
+    /// - line domain (pushed as in [`crate::line`], 2^log_size m31)
+    /// - pos
+    ///
+    /// Output:
+    /// - line domain
+    /// - x (m31)
+    pub fn at(log_size: usize) -> Script {
+        script! {
+            { Self::check_position(log_size) }
+            OP_PICK
+        }
+    }
+
+    /// Drop the pushed line domain under the top element.
+    pub fn drop_domain(log_size: usize) -> Script {
+        script! {
+            OP_TOALTSTACK
+            for _ in 0..(1 << log_size) / 2 {
+                OP_2DROP
+            }
+            if log_size == 0 {
+                OP_DROP
+            }
+            OP_FROMALTSTACK
+        }
+    }
+
+    /// Compute the x coordinate of the point at a (bit-reversed) position of a line domain
+    /// known when building the script, by looking it up in the pushed domain.
+    ///
+    /// Input:
+    /// - pos
+    ///
+    /// Output:
+    /// - x (m31)
+    pub fn at_constant(domain: &LineDomain) -> Script {
+        let log_size = domain.log_size() as usize;
+        script! {
+            { domain }
+            { 1 << log_size } OP_ROLL
+            { Self::at(log_size) }
+            { Self::drop_domain(log_size) }
+        }
+    }
+
+    /// Derive the x coordinate of the point at a (bit-reversed) position of a line domain known
+    /// when building the script, without pushing the domain.
+    ///
+    /// The point at the position is `initial + step * bit_reverse(pos)`, where the bit `j` of
+    /// the position adds `step * 2^(log_size - 1 - j)`, so the script adds one constant point of
+    /// the circle for each bit that is set.
+    ///
+    /// Input:
+    /// - pos
+    ///
+    /// Output:
+    /// - x (m31)
+    pub fn derive_at(domain: &LineDomain) -> Script {
+        let log_size = domain.log_size() as usize;
+        let coset = domain.coset();
+        let shifts = (0..log_size)
+            .map(|j| (coset.step_size * (1 << (log_size - 1 - j))).to_point())
+            .collect::<Vec<_>>();
+
+        script! {
+            { Self::check_position(log_size) }
+
+            // split the position into bits, the bit 0 ending up on the top of the altstack
+            if log_size >= 2 {
+                { limb_to_be_bits(log_size as u32) }
+            }
+            if log_size == 0 {
+                OP_DROP
+            }
+            for _ in 0..log_size {
+                OP_TOALTSTACK
+            }
+
+            { coset.initial.x }
+            { coset.initial.y }
+            for shift in shifts.iter() {
+                OP_FROMALTSTACK
+                OP_IF
+                    { Self::add_constant_point(shift) }
+                OP_ENDIF
+            }
+            OP_DROP
+        }
+    }
+
+    /// Add a constant point to a point of the circle over M31.
+    ///
+    /// Input:
+    /// - x (m31)
+    /// - y (m31)
+    ///
+    /// Output:
+    /// - x * p.x - y * p.y (m31)
+    /// - x * p.y + y * p.x (m31)
+    fn add_constant_point(p: &CirclePoint<M31>) -> Script {
+        script! {
+            OP_2DUP
+            { p.y } m31_mul
+            OP_SWAP { p.x } m31_mul
+            OP_SWAP m31_sub
+            OP_ROT { p.y } m31_mul
+            OP_ROT { p.x } m31_mul
+            m31_add
+        }
+    }
+}
+
+/// Gadget for line polynomials.
+pub struct LinePolyGadget;
+
+impl LinePolyGadget {
+    /// Evaluate a line polynomial with 2^log_size coefficients at a point of the line, in the
+    /// same way as `LinePoly::eval_at_point`: the coefficients are folded pairwise with the
+    /// repeated doublings of x, the last doubling first.
+    ///
+    /// Input:
+    /// - coefficients (pushed as in [`crate::line`], 2^log_size qm31, the first one on the top)
+    /// - x (m31)
+    ///
+    /// Output:
+    /// - value (qm31)
+    pub fn eval_at_point(log_size: usize) -> Script {
+        script! {
+            // store x and its doublings in the altstack, the last doubling on the top
+            for j in 0..log_size {
+                OP_DUP OP_TOALTSTACK
+                if j + 1 < log_size {
+                    { Self::double_x() }
+                }
+            }
+            OP_DROP
+
+            for level in 0..log_size {
+                { Self::fold_level(log_size - level) }
+            }
+        }
+    }
+
+    /// Fold the 2^log_size values on the stack with the folding factor on the top of the
+    /// altstack, where the value at an even index `2i` and the next one become
+    /// `values[2i] + values[2i + 1] * factor`.
+    ///
+    /// Input:
+    /// - values (2^log_size qm31, the first one on the top)
+    ///
+    /// Output:
+    /// - folded values (2^(log_size - 1) qm31, the first one on the top)
+    fn fold_level(log_size: usize) -> Script {
+        let half = 1 << (log_size - 1);
+        script! {
+            OP_FROMALTSTACK
+
+            // fold the deepest pairs first, so that the first folded value ends up on the top
+            for i in (0..half).rev() {
+                // roll values[2i] and values[2i + 1] over the factor and the folded values
+                for _ in 0..4 {
+                    { 8 * i + 4 * (half - 1 - i) + 4 } OP_ROLL
+                }
+                for _ in 0..4 {
+                    { 8 * i + 4 * (half - 1 - i) + 8 } OP_ROLL
+                }
+                { 4 * (half - 1 - i) + 8 } OP_PICK
+                qm31_mul_m31
+                qm31_add
+            }
+
+            // drop the factor
+            { 4 * half } OP_ROLL OP_DROP
+        }
+    }
+
+    /// Compute the x coordinate of the double of a point, 2x^2 - 1.
+    fn double_x() -> Script {
+        script! {
+            OP_DUP m31_mul
+            OP_DUP m31_add
+            1 m31_sub
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::line::{LineDomainGadget, LinePolyGadget};
+    use crate::tests_utils::report::report_bitcoin_script_size;
+    use crate::treepp::*;
+    use crate::utils::{bit_reverse_index, get_rand_qm31};
+    use rand::{Rng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use rust_bitcoin_m31::qm31_equalverify;
+    use stwo_prover::core::circle::Coset;
+    use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::poly::line::{LineDomain, LinePoly};
+
+    #[test]
+    fn test_line_domain_at() {
+        for log_size in 1..=6 {
+            let domain = LineDomain::new(Coset::half_odds(log_size));
+
+            let at_script = LineDomainGadget::at_constant(&domain);
+            report_bitcoin_script_size(
+                "LineDomain",
+                format!("at(2^{})", log_size).as_str(),
+                at_script.len(),
+            );
+
+            for pos in 0..(1 << log_size) {
+                let expected = domain.at(bit_reverse_index(pos, log_size as usize));
+
+                let script = script! {
+                    { pos }
+                    { at_script.clone() }
+                    { expected }
+                    OP_EQUAL
+                };
+
+                let exec_result = execute_script(script);
+                assert!(exec_result.success);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_domain_position_out_of_range() {
+        let log_size = 4;
+        let domain = LineDomain::new(Coset::half_odds(log_size));
+
+        for pos in [1 << log_size, (1 << log_size) + 3, -1] {
+            for at_script in [
+                LineDomainGadget::at_constant(&domain),
+                LineDomainGadget::derive_at(&domain),
+            ] {
+                let script = script! {
+                    { pos }
+                    { at_script }
+                    OP_DROP
+                    OP_TRUE
+                };
+                let exec_result = execute_script(script);
+                assert!(!exec_result.success);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_domain_derive_at() {
+        for log_size in 1..=6 {
+            let domain = LineDomain::new(Coset::half_odds(log_size));
+
+            let at_script = LineDomainGadget::derive_at(&domain);
+            report_bitcoin_script_size(
+                "LineDomain",
+                format!("derive_at(2^{})", log_size).as_str(),
+                at_script.len(),
+            );
+
+            for pos in 0..(1 << log_size) {
+                let expected = domain.at(bit_reverse_index(pos, log_size as usize));
+
+                let script = script! {
+                    { pos }
+                    { at_script.clone() }
+                    { expected }
+                    OP_EQUAL
+                };
+
+                let exec_result = execute_script(script);
+                assert!(exec_result.success);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_poly_eval_at_point() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        for log_size in 0..=5 {
+            let eval_script = LinePolyGadget::eval_at_point(log_size);
+            report_bitcoin_script_size(
+                "LinePoly",
+                format!("eval_at_point(2^{})", log_size).as_str(),
+                eval_script.len(),
+            );
+
+            for _ in 0..4 {
+                let poly = LinePoly::new(
+                    (0..1 << log_size)
+                        .map(|_| get_rand_qm31(&mut prng))
+                        .collect(),
+                );
+                let x = M31::reduce(prng.next_u64());
+                let expected = poly.eval_at_point(x.into());
+
+                let script = script! {
+                    { &poly }
+                    { x }
+                    { eval_script.clone() }
+                    { expected }
+                    qm31_equalverify
+                    OP_TRUE
+                };
+                let exec_result = execute_script(script);
+                assert!(exec_result.success);
+            }
+        }
+
+        // evaluate at a point of the line domain derived from a position
+        let log_size = 3;
+        let domain = LineDomain::new(Coset::half_odds(6));
+        let poly = LinePoly::new(
+            (0..1 << log_size)
+                .map(|_| get_rand_qm31(&mut prng))
+                .collect(),
+        );
+        let pos = prng.gen_range(0..domain.size());
+        let expected = poly.eval_at_point(domain.at(bit_reverse_index(pos, 6)).into());
+
+        let script = script! {
+            { &poly }
+            { pos }
+            { LineDomainGadget::derive_at(&domain) }
+            { LinePolyGadget::eval_at_point(log_size) }
+            { expected }
+            qm31_equalverify
+            OP_TRUE
+        };
+        let exec_result = execute_script(script);
+        assert!(exec_result.success);
+    }
+}
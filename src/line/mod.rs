@@ -0,0 +1,50 @@
+use crate::treepp::pushable::{Builder, Pushable};
+use crate::utils::bit_reverse_index;
+use stwo_prover::core::poly::line::{LineDomain, LinePoly};
+
+mod bitcoin_script;
+pub use bitcoin_script::*;
+
+/// The x coordinates of all the points of a line domain, in the bit-reversed order that the
+/// evaluations over the domain (and therefore the query positions) follow.
+pub fn line_domain_bit_reversed_points(domain: &LineDomain) -> Vec<u32> {
+    let log_size = domain.log_size() as usize;
+    (0..domain.size())
+        .map(|i| domain.at(bit_reverse_index(i, log_size)).0)
+        .collect()
+}
+
+/// Push the x coordinates of all the points of the line domain, where the point at the
+/// (bit-reversed) position 0 ends up on the top, so that a position can be used directly
+/// with `OP_PICK`.
+impl Pushable for &LineDomain {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        for x in line_domain_bit_reversed_points(self).iter().rev() {
+            builder = x.bitcoin_script_push(builder);
+        }
+        builder
+    }
+}
+
+impl Pushable for LineDomain {
+    fn bitcoin_script_push(self, builder: Builder) -> Builder {
+        (&self).bitcoin_script_push(builder)
+    }
+}
+
+/// Push the coefficients of the line polynomial, where the first coefficient ends up on the
+/// top.
+impl Pushable for &LinePoly {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        for coeff in self.iter().rev() {
+            builder = coeff.bitcoin_script_push(builder);
+        }
+        builder
+    }
+}
+
+impl Pushable for LinePoly {
+    fn bitcoin_script_push(self, builder: Builder) -> Builder {
+        (&self).bitcoin_script_push(builder)
+    }
+}
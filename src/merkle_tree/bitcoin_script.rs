@@ -1,5 +1,6 @@
 use crate::treepp::*;
 use crate::utils::{hash_felt_gadget, limb_to_be_bits_toaltstack};
+use crate::OP_HINT;
 
 /// Gadget for verifying a regular binary Merkle tree.
 pub struct MerkleTreeGadget;
@@ -71,18 +72,84 @@ impl MerkleTreeGadget {
     }
 }
 
+/// Gadget for verifying the openings of a tree of the commitment scheme, in which all the
+/// columns have the same size.
+pub struct ColumnMerkleTreeGadget;
+
+impl ColumnMerkleTreeGadget {
+    /// Hash the values of all the columns at a leaf.
+    ///
+    /// Input:
+    /// - values (n_columns m31, the first column on the top)
+    ///
+    /// Output:
+    /// - leaf hash
+    pub fn hash_column_values(n_columns: usize) -> Script {
+        assert!(n_columns > 0);
+        script! {
+            OP_SHA256
+            for _ in 1..n_columns {
+                OP_CAT OP_SHA256
+            }
+        }
+    }
+
+    /// Query and verify using the Merkle path as a hint.
+    ///
+    /// Hint:
+    /// - Merkle path, see [`crate::merkle_tree::ColumnMerkleProof`]
+    ///
+    /// Input:
+    /// - root_hash
+    /// - pos
+    ///
+    /// Output:
+    /// - values (n_columns m31, the first column on the top)
+    pub fn query_and_verify(n_columns: usize, logn: usize) -> Script {
+        script! {
+            { limb_to_be_bits_toaltstack(logn as u32) }
+
+            for _ in 0..n_columns {
+                OP_HINT
+            }
+
+            // copy the values
+            for _ in 0..n_columns {
+                { n_columns - 1 } OP_PICK
+            }
+
+            { Self::hash_column_values(n_columns) }
+
+            for _ in 0..logn {
+                OP_HINT
+                OP_FROMALTSTACK OP_IF OP_SWAP OP_ENDIF
+                OP_CAT OP_SHA256
+            }
+
+            { n_columns + 1 } OP_ROLL
+            OP_EQUALVERIFY
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
 
     use crate::treepp::*;
     use crate::utils::get_rand_qm31;
     use crate::{
-        merkle_tree::{MerkleTree, MerkleTreeGadget},
+        merkle_tree::{split_decommitment, ColumnMerkleTreeGadget, MerkleTree, MerkleTreeGadget},
         tests_utils::report::report_bitcoin_script_size,
     };
-    use rand::{Rng, SeedableRng};
+    use rand::{Rng, RngCore, SeedableRng};
     use rand_chacha::ChaCha20Rng;
     use rust_bitcoin_m31::qm31_equalverify;
+    use std::collections::BTreeMap;
+    use stwo_prover::core::backend::CpuBackend;
+    use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+    use stwo_prover::core::vcs::bws_sha256_merkle::BWSSha256MerkleHasher;
+    use stwo_prover::core::vcs::prover::MerkleProver;
 
     #[test]
     fn test_merkle_tree_verify() {
@@ -157,4 +224,65 @@ mod test {
             assert!(exec_result.success);
         }
     }
+
+    #[test]
+    fn test_column_merkle_tree_verify() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let log_size = 10;
+
+        // one column for a trace tree and four columns for a composition tree
+        for n_columns in [1, 4] {
+            let columns = (0..n_columns)
+                .map(|_| {
+                    (0..(1 << log_size))
+                        .map(|_| M31::reduce(prng.next_u64()))
+                        .collect::<Vec<M31>>()
+                })
+                .collect::<Vec<Vec<M31>>>();
+
+            let mut queries = (0..5)
+                .map(|_| prng.gen_range(0..(1 << log_size)))
+                .collect::<Vec<usize>>();
+            queries.sort_unstable();
+            queries.dedup();
+
+            let merkle_prover =
+                MerkleProver::<CpuBackend, BWSSha256MerkleHasher>::commit(columns.iter().collect());
+            let root: BWSSha256Hash = merkle_prover.root();
+            let (queried_values, decommitment) = merkle_prover.decommit(
+                BTreeMap::from([(log_size, queries.clone())]),
+                columns.iter().collect(),
+            );
+
+            let proofs = split_decommitment(log_size, &queries, &queried_values, &decommitment);
+
+            let verify_script =
+                ColumnMerkleTreeGadget::query_and_verify(n_columns, log_size as usize);
+            report_bitcoin_script_size(
+                "ColumnMerkleTree",
+                format!("verify({} columns, 2^{})", n_columns, log_size).as_str(),
+                verify_script.len(),
+            );
+
+            for (query, proof) in queries.iter().zip(proofs.iter()) {
+                assert!(proof.verify(&root, *query));
+
+                let script = script! {
+                    { proof }
+                    { root }
+                    { *query }
+                    { verify_script.clone() }
+                    for column in columns.iter() {
+                        { column[*query] }
+                        OP_EQUALVERIFY
+                    }
+                    OP_TRUE
+                };
+
+                let exec_result = execute_script(script);
+                assert!(exec_result.success);
+            }
+        }
+    }
 }
@@ -0,0 +1,230 @@
+use crate::digest::CanonicalDigest;
+use crate::treepp::pushable::{Builder, Pushable};
+use crate::utils::num_to_bytes;
+use sha2::{Digest, Sha256};
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use stwo_prover::core::fields::m31::M31;
+use stwo_prover::core::vcs::bws_sha256_merkle::BWSSha256MerkleHasher;
+use stwo_prover::core::vcs::ops::MerkleHasher;
+use stwo_prover::core::vcs::prover::MerkleDecommitment;
+
+/// Hash the values of all the columns at a leaf.
+///
+/// The values are absorbed one by one, starting from the first column, in the same way as
+/// [`crate::utils::hash_qm31`], so that a leaf of the four columns of a secure field element
+/// hashes to the hash of that element.
+pub fn hash_column_values(values: &[M31]) -> [u8; 32] {
+    assert!(!values.is_empty());
+
+    let mut res = [0u8; 32];
+
+    let mut hasher = Sha256::new();
+    Digest::update(&mut hasher, num_to_bytes(values[0]));
+    res.copy_from_slice(hasher.finalize().as_slice());
+
+    for value in values.iter().skip(1) {
+        let mut hasher = Sha256::new();
+        Digest::update(&mut hasher, num_to_bytes(*value));
+        Digest::update(&mut hasher, res);
+        res.copy_from_slice(hasher.finalize().as_slice());
+    }
+
+    res
+}
+
+/// The Merkle path of an opening of a tree of the commitment scheme, in which all the columns
+/// have the same size.
+///
+/// The path is generic over the hasher of the tree. Only the trees committed with
+/// [`BWSSha256MerkleHasher`] can be verified in script, see [`ColumnMerkleTreeGadget`]: Blake2s
+/// has no opcode, and emulating its compression function is far beyond the script budget, so
+/// the paths of the other hashers can only be verified natively.
+#[derive(Clone, Debug)]
+pub struct ColumnMerkleProof<H: MerkleHasher = BWSSha256MerkleHasher> {
+    /// The values of all the columns at the queried position.
+    pub values: Vec<M31>,
+    /// All the intermediate sibling nodes, starting from the leaf layer.
+    pub siblings: Vec<H::Hash>,
+}
+
+impl<H: MerkleHasher> ColumnMerkleProof<H> {
+    /// Verify the Merkle path.
+    pub fn verify(&self, root_hash: &H::Hash, mut query: usize) -> bool {
+        let mut hash = H::hash_node(None, &self.values);
+
+        for sibling in self.siblings.iter() {
+            hash = if query & 1 == 0 {
+                H::hash_node(Some((hash, *sibling)), &[])
+            } else {
+                H::hash_node(Some((*sibling, hash)), &[])
+            };
+            query >>= 1;
+        }
+
+        hash == *root_hash
+    }
+}
+
+impl Pushable for ColumnMerkleProof {
+    fn bitcoin_script_push(self, builder: Builder) -> Builder {
+        (&self).bitcoin_script_push(builder)
+    }
+}
+
+impl Pushable for &ColumnMerkleProof {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        for value in self.values.iter().rev() {
+            builder = value.bitcoin_script_push(builder);
+        }
+        for elem in self.siblings.iter() {
+            builder = elem
+                .to_canonical_bytes()
+                .to_vec()
+                .bitcoin_script_push(builder);
+        }
+        builder
+    }
+}
+
+/// Split the decommitment of a tree of the commitment scheme into one Merkle path per query.
+///
+/// The decommitment covers all the queries at once, where a sibling is only included in the
+/// hash witness if it cannot be computed from the other queries. It is replayed in the same
+/// order as it is produced, which rebuilds every node needed by the individual paths.
+///
+/// All the columns of the tree must have `2^log_size` rows, and `queries` must be the sorted
+/// and deduplicated positions for which `queried_values` is given.
+pub fn split_decommitment<H: MerkleHasher>(
+    log_size: u32,
+    queries: &[usize],
+    queried_values: &[Vec<M31>],
+    decommitment: &MerkleDecommitment<H>,
+) -> Vec<ColumnMerkleProof<H>> {
+    assert!(queries.windows(2).all(|w| w[0] < w[1]));
+    try_split_decommitment(log_size, queries, queried_values, decommitment)
+        .expect("the decommitment should match the queries")
+}
+
+/// Split the decommitment of a tree of the commitment scheme into one Merkle path per query, as
+/// [`split_decommitment`] does, or return `None` if the decommitment or the queried values do
+/// not match the queries.
+pub fn try_split_decommitment<H: MerkleHasher>(
+    log_size: u32,
+    queries: &[usize],
+    queried_values: &[Vec<M31>],
+    decommitment: &MerkleDecommitment<H>,
+) -> Option<Vec<ColumnMerkleProof<H>>> {
+    if !queries.windows(2).all(|w| w[0] < w[1])
+        || !decommitment.column_witness.is_empty()
+        || queried_values
+            .iter()
+            .any(|column| column.len() != queries.len())
+    {
+        return None;
+    }
+
+    let values = queries
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            queried_values
+                .iter()
+                .map(|column| column[i])
+                .collect::<Vec<M31>>()
+        })
+        .collect::<Vec<Vec<M31>>>();
+
+    // the nodes of each layer, starting from the leaf layer
+    let mut layers = vec![queries
+        .iter()
+        .zip(values.iter())
+        .map(|(query, values)| (*query, H::hash_node(None, values)))
+        .collect::<BTreeMap<usize, H::Hash>>()];
+
+    let mut hash_witness = decommitment.hash_witness.iter();
+    for _ in 0..log_size {
+        let layer = layers.last_mut().unwrap();
+
+        let mut parents = layer.keys().map(|i| i >> 1).collect::<Vec<usize>>();
+        parents.dedup();
+
+        let mut next_layer = BTreeMap::new();
+        for parent in parents {
+            for child in [2 * parent, 2 * parent + 1] {
+                if let Entry::Vacant(entry) = layer.entry(child) {
+                    entry.insert(*hash_witness.next()?);
+                }
+            }
+            next_layer.insert(
+                parent,
+                H::hash_node(Some((layer[&(2 * parent)], layer[&(2 * parent + 1)])), &[]),
+            );
+        }
+        layers.push(next_layer);
+    }
+    if hash_witness.next().is_some() {
+        return None;
+    }
+
+    Some(
+        queries
+            .iter()
+            .zip(values)
+            .map(|(query, values)| {
+                let mut pos = *query;
+                let mut siblings = Vec::with_capacity(log_size as usize);
+                for layer in layers.iter().take(log_size as usize) {
+                    siblings.push(layer[&(pos ^ 1)]);
+                    pos >>= 1;
+                }
+                ColumnMerkleProof { values, siblings }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::merkle_tree::split_decommitment;
+    use rand::{Rng, RngCore, SeedableRng};
+    use rand_chacha::ChaCha20Rng;
+    use std::collections::BTreeMap;
+    use stwo_prover::core::backend::CpuBackend;
+    use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::vcs::blake2_merkle::Blake2sMerkleHasher;
+    use stwo_prover::core::vcs::prover::MerkleProver;
+
+    #[test]
+    fn test_split_blake2s_decommitment() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let log_size = 10;
+        let columns = (0..4)
+            .map(|_| {
+                (0..(1 << log_size))
+                    .map(|_| M31::reduce(prng.next_u64()))
+                    .collect::<Vec<M31>>()
+            })
+            .collect::<Vec<Vec<M31>>>();
+
+        let mut queries = (0..5)
+            .map(|_| prng.gen_range(0..(1 << log_size)))
+            .collect::<Vec<usize>>();
+        queries.sort_unstable();
+        queries.dedup();
+
+        let merkle_prover =
+            MerkleProver::<CpuBackend, Blake2sMerkleHasher>::commit(columns.iter().collect());
+        let (queried_values, decommitment) = merkle_prover.decommit(
+            BTreeMap::from([(log_size, queries.clone())]),
+            columns.iter().collect(),
+        );
+
+        let proofs = split_decommitment(log_size, &queries, &queried_values, &decommitment);
+        for (query, proof) in queries.iter().zip(proofs.iter()) {
+            assert!(proof.verify(&merkle_prover.root(), *query));
+            assert!(!proof.verify(&merkle_prover.root(), *query ^ 1));
+        }
+    }
+}
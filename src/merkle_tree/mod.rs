@@ -3,9 +3,12 @@ use stwo_prover::core::fields::qm31::QM31;
 use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
 
 mod bitcoin_script;
+mod column;
+use crate::digest::CanonicalDigest;
 use crate::treepp::pushable::{Builder, Pushable};
-use crate::utils::hash_qm31;
+use crate::utils::{hash_chunks, hash_qm31};
 pub use bitcoin_script::*;
+pub use column::*;
 
 /// A Merkle tree.
 pub struct MerkleTree {
@@ -20,38 +23,35 @@ pub struct MerkleTree {
 impl MerkleTree {
     /// Create a new Merkle tree.
     pub fn new(leaf_layer: Vec<QM31>) -> Self {
+        #[cfg(feature = "profiling")]
+        let _scope = crate::profiling::scope("MerkleTree::new");
+
         assert!(leaf_layer.len().is_power_of_two());
 
         let mut intermediate_layers = vec![];
-        let mut cur = leaf_layer
-            .chunks_exact(2)
-            .map(|v| {
-                let commit_1 = hash_qm31(&v[0]);
-                let commit_2 = hash_qm31(&v[1]);
+        let mut cur = hash_chunks(&leaf_layer, 2, |_, v| {
+            let commit_1 = hash_qm31(&v[0]);
+            let commit_2 = hash_qm31(&v[1]);
 
-                let mut hash_result = [0u8; 32];
+            let mut hash_result = [0u8; 32];
 
-                let mut hasher = Sha256::new();
-                Digest::update(&mut hasher, commit_1);
-                Digest::update(&mut hasher, commit_2);
-                hash_result.copy_from_slice(hasher.finalize().as_slice());
-                hash_result
-            })
-            .collect::<Vec<[u8; 32]>>();
+            let mut hasher = Sha256::new();
+            Digest::update(&mut hasher, commit_1);
+            Digest::update(&mut hasher, commit_2);
+            hash_result.copy_from_slice(hasher.finalize().as_slice());
+            hash_result
+        });
         intermediate_layers.push(cur.clone());
 
         while cur.len() > 1 {
-            cur = cur
-                .chunks_exact(2)
-                .map(|v| {
-                    let mut hash_result = [0u8; 32];
-                    let mut hasher = Sha256::new();
-                    Digest::update(&mut hasher, v[0]);
-                    Digest::update(&mut hasher, v[1]);
-                    hash_result.copy_from_slice(hasher.finalize().as_slice());
-                    hash_result
-                })
-                .collect::<Vec<[u8; 32]>>();
+            cur = hash_chunks(&cur, 2, |_, v| {
+                let mut hash_result = [0u8; 32];
+                let mut hasher = Sha256::new();
+                Digest::update(&mut hasher, v[0]);
+                Digest::update(&mut hasher, v[1]);
+                hash_result.copy_from_slice(hasher.finalize().as_slice());
+                hash_result
+            });
             intermediate_layers.push(cur.clone());
         }
 
@@ -134,7 +134,10 @@ impl Pushable for &MerkleTreeProof {
     fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
         builder = self.leaf.bitcoin_script_push(builder);
         for elem in self.siblings.iter() {
-            builder = elem.to_vec().bitcoin_script_push(builder);
+            builder = elem
+                .to_canonical_bytes()
+                .to_vec()
+                .bitcoin_script_push(builder);
         }
         builder
     }
@@ -65,10 +65,31 @@ impl OODSGadget {
             qm31_equalverify
         }
     }
+
+    /// Samples several random points, one after the other, see [`OODSGadget::get_random_point`].
+    ///
+    /// Hint:
+    /// - OODSHint (n_points times)
+    ///
+    /// Input:
+    /// - channel
+    ///
+    /// Output:
+    /// - points (n_points * 8 elements, the last point on the top)
+    /// - channel'
+    pub fn get_random_points(n_points: usize) -> Script {
+        script! {
+            for _ in 0..n_points {
+                { Self::get_random_point() }
+                8 OP_ROLL
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::circle::CirclePointGadget;
     use crate::oods::{OODSGadget, OODS};
     use crate::treepp::*;
     use crate::{channel::Sha256Channel, tests_utils::report::report_bitcoin_script_size};
@@ -113,4 +134,35 @@ mod test {
         let exec_result = execute_script(script);
         assert!(exec_result.success);
     }
+
+    #[test]
+    fn test_get_random_points() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let mut a = [0u8; 32];
+        a.iter_mut().for_each(|v| *v = prng.gen());
+        let a = BWSSha256Hash::from(a.to_vec());
+
+        let n_points = 3;
+        let mut channel = Sha256Channel::new(a);
+        let (points, hints) = CirclePoint::get_random_points_with_hints(&mut channel, n_points);
+        let c = channel.digest;
+
+        let script = script! {
+            for hint in hints {
+                { hint }
+            }
+            { a }
+            { OODSGadget::get_random_points(n_points) }
+            { c } // check channel'
+            OP_EQUALVERIFY
+            for point in points.iter().rev() {
+                { *point }
+                { CirclePointGadget::equalverify() }
+            }
+            OP_TRUE
+        };
+        let exec_result = execute_script(script);
+        assert!(exec_result.success);
+    }
 }
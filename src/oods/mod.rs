@@ -14,6 +14,16 @@ pub use bitcoin_script::*;
 pub trait OODS: Sized {
     /// Obtain a random point from the channel and its hint.
     fn get_random_point_with_hint(channel: &mut Sha256Channel) -> (Self, OODSHint);
+
+    /// Obtain several random points from the channel, one after the other, and their hints.
+    fn get_random_points_with_hints(
+        channel: &mut Sha256Channel,
+        n_points: usize,
+    ) -> (Vec<Self>, Vec<OODSHint>) {
+        (0..n_points)
+            .map(|_| Self::get_random_point_with_hint(channel))
+            .unzip()
+    }
 }
 
 impl OODS for CirclePoint<QM31> {
@@ -29,6 +39,37 @@ impl OODS for CirclePoint<QM31> {
     }
 }
 
+/// The points at which the columns are sampled for several OODS points: for each column, the
+/// points that `column_points` gives at each OODS point, in the order of the OODS points.
+///
+/// With a single OODS point, this is the usual construction of `sampled_points`, e.g., the mask
+/// points for the trace and the OODS point itself for the composition polynomial.
+pub fn sampled_points<F>(
+    oods_points: &[CirclePoint<QM31>],
+    column_points: F,
+) -> Vec<Vec<CirclePoint<QM31>>>
+where
+    F: Fn(CirclePoint<QM31>) -> Vec<Vec<CirclePoint<QM31>>>,
+{
+    assert!(!oods_points.is_empty());
+
+    let per_point = oods_points
+        .iter()
+        .map(|point| column_points(*point))
+        .collect::<Vec<_>>();
+    let n_columns = per_point[0].len();
+    assert!(per_point.iter().all(|columns| columns.len() == n_columns));
+
+    (0..n_columns)
+        .map(|i| {
+            per_point
+                .iter()
+                .flat_map(|columns| columns[i].iter().copied())
+                .collect()
+        })
+        .collect()
+}
+
 /// Hint for out-of-domain sampling.
 #[derive(Clone)]
 pub struct OODSHint {
@@ -47,3 +88,46 @@ impl Pushable for OODSHint {
         self.y.bitcoin_script_push(builder)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::oods::sampled_points;
+    use crate::utils::get_rand_qm31;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use stwo_prover::core::circle::{CirclePoint, CirclePointIndex};
+    use stwo_prover::core::fields::qm31::QM31;
+
+    #[test]
+    fn test_sampled_points() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let oods_points = (0..3)
+            .map(|_| CirclePoint {
+                x: get_rand_qm31(&mut prng),
+                y: get_rand_qm31(&mut prng),
+            })
+            .collect::<Vec<_>>();
+        let shift = CirclePointIndex::subgroup_gen(5)
+            .to_point()
+            .into_ef::<QM31>();
+
+        // a column sampled at the point and its shift, and a column sampled at the point
+        let res = sampled_points(&oods_points, |p| vec![vec![p, p + shift], vec![p]]);
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            res[0],
+            oods_points
+                .iter()
+                .flat_map(|p| [*p, *p + shift])
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(res[1], oods_points);
+
+        // a single point gives back the columns
+        assert_eq!(
+            sampled_points(&oods_points[..1], |p| vec![vec![p]; 4]),
+            vec![vec![oods_points[0]]; 4]
+        );
+    }
+}
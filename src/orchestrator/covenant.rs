@@ -0,0 +1,152 @@
+use crate::treepp::*;
+#[cfg(feature = "ctv")]
+use bitcoin::consensus::Encodable;
+use bitcoin::Transaction;
+#[cfg(any(feature = "ctv", feature = "txhash"))]
+use sha2::{Digest, Sha256};
+
+/// A covenant that forces the output of a verification step to be spent by the expected
+/// transaction of the chain, which carries the state to the next step.
+///
+/// The transaction that spends the output is known in full, except for its outpoint and its
+/// witness, when the leaf is assembled. The chain is assembled from the end, so that the
+/// covenant of a step can commit to the output of the next step.
+///
+/// Besides [`NoCovenant`], the backends are [`CtvCovenant`] (behind the `ctv` feature) and
+/// [`TxHashCovenant`] (behind the `txhash` feature).
+pub trait CovenantBackend {
+    /// Add the covenant to a leaf script, which leaves a single true element on success, for the
+    /// given spending transaction.
+    fn enforce(&self, leaf_script: Script, spending_tx: &Transaction) -> Script;
+}
+
+/// No covenant: the leaf script is left unchanged, and the chain only tells the parties which
+/// transactions to expect.
+pub struct NoCovenant;
+
+impl CovenantBackend for NoCovenant {
+    fn enforce(&self, leaf_script: Script, _spending_tx: &Transaction) -> Script {
+        leaf_script
+    }
+}
+
+/// A covenant with `OP_CHECKTEMPLATEVERIFY` (BIP-119), which is `OP_NOP4` until it activates.
+///
+/// This backend is experimental.
+#[cfg(feature = "ctv")]
+pub struct CtvCovenant;
+
+#[cfg(feature = "ctv")]
+impl CovenantBackend for CtvCovenant {
+    fn enforce(&self, leaf_script: Script, spending_tx: &Transaction) -> Script {
+        script! {
+            { leaf_script }
+            OP_VERIFY
+            { ctv_hash(spending_tx, 0).to_vec() }
+            OP_NOP4
+        }
+    }
+}
+
+/// The default template hash of BIP-119 for the input of the given index.
+#[cfg(feature = "ctv")]
+pub fn ctv_hash(tx: &Transaction, input_index: u32) -> [u8; 32] {
+    let mut buffer = vec![];
+    buffer.extend_from_slice(&tx.version.0.to_le_bytes());
+    buffer.extend_from_slice(&tx.lock_time.to_consensus_u32().to_le_bytes());
+
+    if tx.input.iter().any(|input| !input.script_sig.is_empty()) {
+        let mut script_sigs = vec![];
+        for input in tx.input.iter() {
+            input.script_sig.consensus_encode(&mut script_sigs).unwrap();
+        }
+        buffer.extend_from_slice(Sha256::digest(&script_sigs).as_slice());
+    }
+
+    buffer.extend_from_slice(&(tx.input.len() as u32).to_le_bytes());
+    let mut sequences = vec![];
+    for input in tx.input.iter() {
+        sequences.extend_from_slice(&input.sequence.to_consensus_u32().to_le_bytes());
+    }
+    buffer.extend_from_slice(Sha256::digest(&sequences).as_slice());
+
+    buffer.extend_from_slice(&(tx.output.len() as u32).to_le_bytes());
+    let mut outputs = vec![];
+    for output in tx.output.iter() {
+        output.consensus_encode(&mut outputs).unwrap();
+    }
+    buffer.extend_from_slice(Sha256::digest(&outputs).as_slice());
+
+    buffer.extend_from_slice(&input_index.to_le_bytes());
+
+    Sha256::digest(&buffer).into()
+}
+
+/// A covenant with `OP_CHECKTXHASHVERIFY` (BIP-346), which is `OP_NOP4` until it activates, with
+/// the field selector [`TXHASH_SELECTOR`].
+///
+/// This backend is experimental: it follows the draft of BIP-346, whose field selectors may still
+/// change.
+#[cfg(feature = "txhash")]
+pub struct TxHashCovenant;
+
+#[cfg(feature = "txhash")]
+impl CovenantBackend for TxHashCovenant {
+    fn enforce(&self, leaf_script: Script, spending_tx: &Transaction) -> Script {
+        // the opcode reads the hash followed by the selector from the top element
+        let mut hash_and_selector = txhash(spending_tx, 0).to_vec();
+        hash_and_selector.extend_from_slice(&TXHASH_SELECTOR);
+
+        script! {
+            { leaf_script }
+            OP_VERIFY
+            { hash_and_selector }
+            OP_NOP4
+        }
+    }
+}
+
+/// The field selector of [`TxHashCovenant`], which selects the version, the lock time, the index
+/// of the current input, the number of inputs and their sequences, and the number of outputs
+/// with their scriptPubKeys and values.
+#[cfg(feature = "txhash")]
+pub const TXHASH_SELECTOR: [u8; 4] = [
+    // TXFS_VERSION | TXFS_LOCKTIME | TXFS_CURRENT_INPUT_IDX
+    0b0000_0111,
+    // TXFS_INPUTS_SEQUENCES | TXFS_OUTPUTS_SCRIPTPUBKEYS | TXFS_OUTPUTS_VALUES
+    0b1100_0010,
+    // TXFS_INOUT_NUMBER | TXFS_INOUT_SELECTION_ALL, for the inputs and the outputs
+    0b1011_1111,
+    0b1011_1111,
+];
+
+/// The hash of the fields of the given transaction that [`TXHASH_SELECTOR`] selects, for the
+/// input of the given index.
+#[cfg(feature = "txhash")]
+pub fn txhash(tx: &Transaction, input_index: u32) -> [u8; 32] {
+    let mut buffer = vec![];
+    buffer.extend_from_slice(&tx.version.0.to_le_bytes());
+    buffer.extend_from_slice(&tx.lock_time.to_consensus_u32().to_le_bytes());
+    buffer.extend_from_slice(&input_index.to_le_bytes());
+
+    buffer.extend_from_slice(&(tx.input.len() as u32).to_le_bytes());
+    let mut sequences = vec![];
+    for input in tx.input.iter() {
+        sequences.extend_from_slice(&input.sequence.to_consensus_u32().to_le_bytes());
+    }
+    buffer.extend_from_slice(Sha256::digest(&sequences).as_slice());
+
+    // the scriptPubKeys are hashed one by one, and then together, and so are the values
+    buffer.extend_from_slice(&(tx.output.len() as u32).to_le_bytes());
+    let mut script_pubkeys = vec![];
+    let mut values = vec![];
+    for output in tx.output.iter() {
+        script_pubkeys
+            .extend_from_slice(Sha256::digest(output.script_pubkey.as_bytes()).as_slice());
+        values.extend_from_slice(&output.value.to_sat().to_le_bytes());
+    }
+    buffer.extend_from_slice(Sha256::digest(&script_pubkeys).as_slice());
+    buffer.extend_from_slice(Sha256::digest(&values).as_slice());
+
+    Sha256::digest(&buffer).into()
+}
@@ -0,0 +1,585 @@
+mod covenant;
+pub use covenant::*;
+
+use crate::fri::{FriIncrementalVerifier, FriProof, FriVerificationStep, FriVerifierState};
+use crate::taproot::{VerifierTaproot, VerifierTaprootBuilder};
+use crate::treepp::*;
+use bitcoin::absolute::LockTime;
+use bitcoin::hashes::Hash;
+use bitcoin::key::{Keypair, XOnlyPublicKey};
+use bitcoin::secp256k1::{Message, Secp256k1};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{LeafVersion, TapLeafHash};
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, FeeRate, OutPoint, Sequence, Transaction, TxIn, TxOut, Txid, Witness};
+use bitcoin_scriptexec::convert_to_witness;
+use std::collections::HashSet;
+use stwo_prover::core::prover::N_QUERIES;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// The verification step that a transaction of the chain performs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    /// Verify the i-th FRI layer.
+    FriLayer(usize),
+    /// Verify the last layer and draw the queries.
+    LastLayer,
+    /// Verify the j-th query.
+    Query(usize),
+}
+
+/// The role of a transaction in the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxRole {
+    /// Move the funds into the output of the first verification step.
+    Commit,
+    /// Spend the output of a verification step by running it.
+    ///
+    /// The transaction of the last step is the finalize transaction, which pays to the
+    /// destination.
+    Verify(StepKind),
+}
+
+/// A transaction of the chain.
+pub struct ChainTx {
+    /// The role of the transaction.
+    pub role: TxRole,
+    /// The transaction.
+    ///
+    /// The verification transactions carry their complete witness. The commit transaction is
+    /// left unsigned, as it spends the funding outpoint owned by the user.
+    pub tx: Transaction,
+}
+
+/// A verification step, locked in its own taproot output.
+pub struct ChainStep {
+    /// The verification step.
+    pub kind: StepKind,
+    /// The leaf script, which pins the commitments of the state before and after the step.
+    pub leaf_script: Script,
+    /// The taproot output holding the funds before the step.
+    pub taproot: VerifierTaproot,
+    /// The state of the Fiat-Shamir transcript before the step, from which the hints of the
+    /// step can be computed again, see [`FriIncrementalVerifier::resume`].
+    pub state: FriVerifierState,
+}
+
+/// The chain of transactions that verifies a FRI proof one step at a time: a commit
+/// transaction, and then one transaction for each verification step.
+///
+/// Each step output commits to the state commitment before and after the step, so the chain
+/// progresses only if every step is verified. Without a covenant, however, a step transaction
+/// is not forced to pay into the output of the next step, so the chain only tells the parties
+/// which transactions to expect, see [`CovenantBackend`].
+pub struct VerificationChain {
+    /// The verification steps, in order.
+    pub steps: Vec<ChainStep>,
+    /// The transactions, starting with the commit transaction.
+    pub txs: Vec<ChainTx>,
+    /// The key of the challenger and the delay, see [`ChainParameters::timeout`].
+    pub timeout: Option<(XOnlyPublicKey, u16)>,
+}
+
+/// The parameters of the chain.
+pub struct ChainParameters {
+    /// The outpoint funding the chain.
+    pub funding_outpoint: OutPoint,
+    /// The amount of the funding outpoint.
+    pub funding_amount: Amount,
+    /// The fee paid by each transaction.
+    pub fee_per_tx: Amount,
+    /// The scriptPubKey that the finalize transaction pays to.
+    pub destination: Script,
+    /// The key of the challenger, and the delay in blocks after which the challenger can claim
+    /// the output of a step that is not spent by the transaction of the step, see
+    /// [`VerificationChain::claim`]. Without it, the outputs can only be spent by the steps.
+    pub timeout: Option<(XOnlyPublicKey, u16)>,
+}
+
+fn step_leaf_script(step: &FriVerificationStep, old_state_commitment: BWSSha256Hash) -> Script {
+    script! {
+        { step.script.clone() }
+        { step.state_commitment }
+        OP_EQUALVERIFY
+        { old_state_commitment }
+        OP_EQUAL
+    }
+}
+
+// A transaction of the chain, without its outpoint and witness.
+fn chain_tx_template(amount: Amount, script_pubkey: Script) -> Transaction {
+    Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: Script::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: amount,
+            script_pubkey,
+        }],
+    }
+}
+
+fn query_leaf_script(step: &FriVerificationStep) -> Script {
+    script! {
+        { step.script.clone() }
+        { step.state_commitment }
+        OP_EQUAL
+    }
+}
+
+impl VerificationChain {
+    /// Build the chain for a FRI proof, without a covenant.
+    pub fn new(
+        channel_init_state: BWSSha256Hash,
+        logn: usize,
+        proof: &FriProof,
+        twiddle_merkle_tree_root: [u8; 32],
+        params: &ChainParameters,
+    ) -> Result<Self, String> {
+        Self::new_with_covenant(
+            channel_init_state,
+            logn,
+            proof,
+            twiddle_merkle_tree_root,
+            params,
+            &NoCovenant,
+        )
+    }
+
+    /// Build the chain for a FRI proof, where the output of each step is locked with the given
+    /// covenant to the transaction of the next step.
+    pub fn new_with_covenant(
+        channel_init_state: BWSSha256Hash,
+        logn: usize,
+        proof: &FriProof,
+        twiddle_merkle_tree_root: [u8; 32],
+        params: &ChainParameters,
+        covenant: &dyn CovenantBackend,
+    ) -> Result<Self, String> {
+        let mut verifier =
+            FriIncrementalVerifier::new(channel_init_state, logn, proof, twiddle_merkle_tree_root);
+
+        // the state is stored before the step updates it
+        let mut kinds_and_steps = vec![];
+        for i in 0..(logn - 1) {
+            let state = verifier.state().clone();
+            kinds_and_steps.push((StepKind::FriLayer(i), state, verifier.verify_fri_layer(i)));
+        }
+        let state = verifier.state().clone();
+        kinds_and_steps.push((StepKind::LastLayer, state, verifier.verify_last_layer()));
+        for j in 0..N_QUERIES {
+            let state = verifier.state().clone();
+            kinds_and_steps.push((StepKind::Query(j), state, verifier.verify_query(j)));
+        }
+
+        let n_steps = kinds_and_steps.len();
+        let fees = params.fee_per_tx * (n_steps as u64 + 1);
+        if params.funding_amount <= fees {
+            return Err("the funding amount does not cover the fees".to_string());
+        }
+
+        let mut kinds = Vec::with_capacity(n_steps);
+        let mut states = Vec::with_capacity(n_steps);
+        let mut base_leaf_scripts = Vec::with_capacity(n_steps);
+        let mut hints = Vec::with_capacity(n_steps);
+        let mut old_state_commitment = FriVerifierState::new(channel_init_state).commitment();
+        for (kind, state, step) in kinds_and_steps {
+            base_leaf_scripts.push(match kind {
+                StepKind::Query(_) => query_leaf_script(&step),
+                _ => step_leaf_script(&step, old_state_commitment),
+            });
+            old_state_commitment = step.state_commitment;
+
+            kinds.push(kind);
+            states.push(state);
+            hints.push(convert_to_witness(step.hints)?);
+        }
+
+        // the transaction i spends the output of the step i - 1, and the covenant of a step
+        // depends on the output of the next one, so the steps are assembled from the end
+        let amount_of = |i: usize| params.funding_amount - params.fee_per_tx * (i as u64 + 1);
+        let mut steps_rev: Vec<ChainStep> = Vec::with_capacity(n_steps);
+        for i in (0..n_steps).rev() {
+            let script_pubkey = match steps_rev.last() {
+                Some(next_step) => next_step.taproot.script_pubkey(),
+                None => params.destination.clone(),
+            };
+            let spending_tx = chain_tx_template(amount_of(i + 1), script_pubkey);
+
+            let leaf_script = covenant.enforce(base_leaf_scripts[i].clone(), &spending_tx);
+            let mut builder = VerifierTaprootBuilder::new().add_verifier_leaf(leaf_script.clone());
+            if let Some((challenger, delay)) = params.timeout {
+                builder = builder.refund(challenger, delay);
+            }
+            let taproot = builder.finalize();
+
+            steps_rev.push(ChainStep {
+                kind: kinds[i],
+                leaf_script,
+                taproot,
+                state: states[i].clone(),
+            });
+        }
+        let steps = steps_rev.into_iter().rev().collect::<Vec<_>>();
+
+        let mut txs = Vec::with_capacity(n_steps + 1);
+        let mut previous_output = params.funding_outpoint;
+        for i in 0..=n_steps {
+            let script_pubkey = match steps.get(i) {
+                Some(step) => step.taproot.script_pubkey(),
+                None => params.destination.clone(),
+            };
+            let mut tx = chain_tx_template(amount_of(i), script_pubkey);
+            tx.input[0].previous_output = previous_output;
+
+            let role = if i == 0 {
+                TxRole::Commit
+            } else {
+                let step = &steps[i - 1];
+                tx.input[0].witness = Witness::from_slice(
+                    &step
+                        .taproot
+                        .script_path_witness(hints[i - 1].clone(), &step.leaf_script),
+                );
+                TxRole::Verify(step.kind)
+            };
+
+            previous_output = OutPoint::new(tx.compute_txid(), 0);
+            txs.push(ChainTx { role, tx });
+        }
+
+        Ok(Self {
+            steps,
+            txs,
+            timeout: params.timeout,
+        })
+    }
+
+    /// The index of the transaction that spends the given outpoint, which tells which step a
+    /// UTXO of the chain is at.
+    pub fn spender_of(&self, outpoint: &OutPoint) -> Option<usize> {
+        self.txs
+            .iter()
+            .position(|chain_tx| chain_tx.tx.input[0].previous_output == *outpoint)
+    }
+
+    /// Build the transaction of the challenger that claims the output spent by the transaction
+    /// at `index`, through the refund leaf of the step, once the step has timed out, and sign it
+    /// with the key of the challenger.
+    ///
+    /// The claim is only valid once the output has `delay` confirmations.
+    pub fn claim(
+        &self,
+        index: usize,
+        destination: Script,
+        fee_rate: FeeRate,
+        challenger: &Keypair,
+    ) -> Result<Transaction, String> {
+        if index == 0 || index >= self.txs.len() {
+            return Err("only the output of a step can be claimed".to_string());
+        }
+        let (challenger_key, delay) = self
+            .timeout
+            .ok_or_else(|| "the chain has no timeout".to_string())?;
+        if challenger.x_only_public_key().0 != challenger_key {
+            return Err("the key is not the one of the challenger".to_string());
+        }
+
+        let step = &self.steps[index - 1];
+        let refund_leaf = step
+            .taproot
+            .refund_leaf
+            .as_ref()
+            .expect("the steps have a refund leaf when the chain has a timeout");
+        let prevout = self.txs[index - 1].tx.output[0].clone();
+
+        // a placeholder of the size of the signature, so that the fee covers it
+        let mut tx = step
+            .taproot
+            .script_path_spend(
+                self.txs[index].tx.input[0].previous_output,
+                prevout.value,
+                refund_leaf,
+                &Witness::from_slice(&[[0u8; 64]]),
+                destination,
+                fee_rate,
+            )
+            .ok_or_else(|| "the amount does not cover the fee".to_string())?;
+        tx.input[0].sequence = Sequence::from_height(delay);
+
+        let sighash = SighashCache::new(&tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&[prevout]),
+                TapLeafHash::from_script(refund_leaf, LeafVersion::TapScript),
+                TapSighashType::Default,
+            )
+            .map_err(|err| err.to_string())?;
+        let signature = Secp256k1::signing_only()
+            .sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), challenger);
+
+        // the witness is the signature, the leaf, and the control block
+        let mut witness = tx.input[0].witness.to_vec();
+        witness[0] = signature.serialize().to_vec();
+        tx.input[0].witness = witness.into();
+        Ok(tx)
+    }
+
+    /// The index of the first transaction that is not confirmed, or `None` if the whole chain
+    /// is confirmed.
+    ///
+    /// Since each transaction spends the previous one, a reorg that drops a transaction also
+    /// drops all the later ones, and the chain resumes from the first dropped one.
+    pub fn resume_from(&self, confirmed: &HashSet<Txid>) -> Option<usize> {
+        self.txs
+            .iter()
+            .position(|chain_tx| !confirmed.contains(&chain_tx.tx.compute_txid()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fri;
+    use crate::fri::FriProof;
+    #[cfg(feature = "ctv")]
+    use crate::orchestrator::{ctv_hash, CtvCovenant};
+    #[cfg(feature = "txhash")]
+    use crate::orchestrator::{txhash, TxHashCovenant, TXHASH_SELECTOR};
+    use crate::orchestrator::{ChainParameters, StepKind, TxRole, VerificationChain};
+    use crate::treepp::*;
+    use crate::twiddle_merkle_tree::TWIDDLE_MERKLE_TREE_ROOT_4;
+    use crate::utils::permute_eval;
+    use bitcoin::hashes::Hash;
+    use bitcoin::key::Keypair;
+    use bitcoin::secp256k1::{schnorr, Message, Secp256k1, SecretKey};
+    use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+    use bitcoin::taproot::{LeafVersion, TapLeafHash};
+    use bitcoin::{Amount, FeeRate, OutPoint, Sequence, Txid};
+    use bitcoin_scriptexec::execute_script_with_witness_unlimited_stack;
+    use num_traits::One;
+    use std::collections::HashSet;
+    use stwo_prover::core::circle::CirclePointIndex;
+    use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::fields::FieldExpOps;
+    use stwo_prover::core::prover::N_QUERIES;
+    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+    fn test_proof(logn: usize) -> (BWSSha256Hash, FriProof) {
+        let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
+
+        let evaluation = (0..(1 << logn))
+            .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
+            .collect();
+        let evaluation = permute_eval(evaluation);
+
+        fri::fri_prove_with_seed(0, evaluation)
+    }
+
+    fn test_params() -> ChainParameters {
+        ChainParameters {
+            funding_outpoint: OutPoint::new(Txid::all_zeros(), 0),
+            funding_amount: Amount::from_sat(100_000),
+            fee_per_tx: Amount::from_sat(1_000),
+            destination: script! { OP_TRUE },
+            timeout: None,
+        }
+    }
+
+    #[test]
+    fn test_verification_chain() {
+        let logn = 5;
+        let (channel_init_state, proof) = test_proof(logn);
+        let params = test_params();
+
+        let chain = VerificationChain::new(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+            &params,
+        )
+        .unwrap();
+
+        assert_eq!(chain.steps.len(), logn - 1 + 1 + N_QUERIES);
+        assert_eq!(chain.txs.len(), chain.steps.len() + 1);
+        assert_eq!(chain.txs[0].role, TxRole::Commit);
+        assert_eq!(chain.txs[1].role, TxRole::Verify(StepKind::FriLayer(0)));
+        assert_eq!(
+            chain.txs.last().unwrap().tx.output[0].script_pubkey,
+            params.destination
+        );
+
+        // each verification transaction spends the output of the previous one
+        for i in 1..chain.txs.len() {
+            let outpoint = OutPoint::new(chain.txs[i - 1].tx.compute_txid(), 0);
+            assert_eq!(chain.spender_of(&outpoint), Some(i));
+            assert_eq!(
+                chain.txs[i - 1].tx.output[0].script_pubkey,
+                chain.steps[i - 1].taproot.script_pubkey()
+            );
+
+            // the witness satisfies the leaf script
+            let mut witness = chain.txs[i].tx.input[0].witness.to_vec();
+            witness.truncate(witness.len() - 2);
+            let exec_result = execute_script_with_witness_unlimited_stack(
+                chain.steps[i - 1].leaf_script.clone(),
+                witness,
+            );
+            assert!(exec_result.success);
+        }
+
+        // after a reorg that drops the third transaction, the chain resumes from it
+        let confirmed = chain.txs[0..2]
+            .iter()
+            .map(|chain_tx| chain_tx.tx.compute_txid())
+            .collect::<HashSet<Txid>>();
+        assert_eq!(chain.resume_from(&confirmed), Some(2));
+    }
+
+    #[test]
+    fn test_verification_chain_claim() {
+        let logn = 5;
+        let (channel_init_state, proof) = test_proof(logn);
+
+        let secp = Secp256k1::new();
+        let challenger = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[1; 32]).unwrap());
+        let params = ChainParameters {
+            timeout: Some((challenger.x_only_public_key().0, 144)),
+            ..test_params()
+        };
+
+        let chain = VerificationChain::new(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+            &params,
+        )
+        .unwrap();
+
+        let index = 3;
+        let fee_rate = FeeRate::from_sat_per_vb(1).unwrap();
+        let claim = chain
+            .claim(index, script! { OP_TRUE }, fee_rate, &challenger)
+            .unwrap();
+        assert_eq!(
+            claim.input[0].previous_output,
+            chain.txs[index].tx.input[0].previous_output
+        );
+        assert_eq!(claim.input[0].sequence, Sequence::from_height(144));
+
+        // the claim goes through the refund leaf, with a signature of the challenger
+        let refund_leaf = chain.steps[index - 1].taproot.refund_leaf.clone().unwrap();
+        let witness = claim.input[0].witness.to_vec();
+        assert_eq!(witness.len(), 3);
+        assert_eq!(witness[1], refund_leaf.to_bytes());
+
+        let prevouts = [chain.txs[index - 1].tx.output[0].clone()];
+        let sighash = SighashCache::new(&claim)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&prevouts),
+                TapLeafHash::from_script(&refund_leaf, LeafVersion::TapScript),
+                TapSighashType::Default,
+            )
+            .unwrap();
+        let signature = schnorr::Signature::from_slice(&witness[0]).unwrap();
+        assert!(secp
+            .verify_schnorr(
+                &signature,
+                &Message::from_digest(sighash.to_byte_array()),
+                &challenger.x_only_public_key().0
+            )
+            .is_ok());
+
+        // the commit output is not a step, and a chain without a timeout cannot be claimed
+        assert!(chain
+            .claim(0, script! { OP_TRUE }, fee_rate, &challenger)
+            .is_err());
+        let chain = VerificationChain::new(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+            &test_params(),
+        )
+        .unwrap();
+        assert!(chain
+            .claim(index, script! { OP_TRUE }, fee_rate, &challenger)
+            .is_err());
+    }
+
+    #[cfg(feature = "ctv")]
+    #[test]
+    fn test_verification_chain_with_ctv() {
+        let logn = 5;
+        let (channel_init_state, proof) = test_proof(logn);
+
+        let chain = VerificationChain::new_with_covenant(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+            &test_params(),
+            &CtvCovenant,
+        )
+        .unwrap();
+
+        for (i, step) in chain.steps.iter().enumerate() {
+            // the output of the step is funded by the previous transaction
+            assert_eq!(
+                chain.txs[i].tx.output[0].script_pubkey,
+                step.taproot.script_pubkey()
+            );
+
+            // and it can only be spent by the next one
+            let covenant = script! {
+                OP_VERIFY
+                { ctv_hash(&chain.txs[i + 1].tx, 0).to_vec() }
+                OP_NOP4
+            };
+            assert!(step.leaf_script.as_bytes().ends_with(covenant.as_bytes()));
+        }
+    }
+
+    #[cfg(feature = "txhash")]
+    #[test]
+    fn test_verification_chain_with_txhash() {
+        let logn = 5;
+        let (channel_init_state, proof) = test_proof(logn);
+
+        let chain = VerificationChain::new_with_covenant(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+            &test_params(),
+            &TxHashCovenant,
+        )
+        .unwrap();
+
+        for (i, step) in chain.steps.iter().enumerate() {
+            let spending_tx = &chain.txs[i + 1].tx;
+            let mut hash_and_selector = txhash(spending_tx, 0).to_vec();
+            hash_and_selector.extend_from_slice(&TXHASH_SELECTOR);
+            let covenant = script! {
+                OP_VERIFY
+                { hash_and_selector }
+                OP_NOP4
+            };
+            assert!(step.leaf_script.as_bytes().ends_with(covenant.as_bytes()));
+
+            // the hash does not depend on the witness, but it does on the outputs
+            let mut redirected = spending_tx.clone();
+            redirected.input[0].witness.clear();
+            assert_eq!(txhash(&redirected, 0), txhash(spending_tx, 0));
+            redirected.output[0].script_pubkey = script! { OP_FALSE };
+            assert_ne!(txhash(&redirected, 0), txhash(spending_tx, 0));
+        }
+    }
+}
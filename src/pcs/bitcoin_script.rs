@@ -0,0 +1,89 @@
+use crate::treepp::*;
+use rust_bitcoin_m31::{
+    qm31_add, qm31_copy, qm31_fromaltstack, qm31_mul, qm31_roll, qm31_toaltstack,
+};
+
+/// Gadget for accumulating the quotients of several columns at a query.
+pub struct QuotientAccumulationGadget;
+
+impl QuotientAccumulationGadget {
+    /// Accumulate the quotients with successive powers of `random_coeff` in the Horner form, see
+    /// [`crate::pcs::accumulate_quotients`].
+    ///
+    /// When a column is sampled at several points, see [`crate::oods::sampled_points`], there is
+    /// one quotient per column and sample point, in the same order.
+    ///
+    /// Input:
+    /// - random_coeff (qm31)
+    /// - quotients (k qm31, the last one on the top)
+    ///
+    /// Output:
+    /// - accumulated quotient (qm31)
+    pub fn accumulate(k: usize) -> Script {
+        assert!(k > 0);
+        script! {
+            { qm31_roll(k) }
+            qm31_toaltstack
+
+            if k > 1 {
+                { qm31_roll(k - 1) }
+            }
+            for i in 1..k {
+                qm31_fromaltstack
+                { qm31_copy(0) }
+                qm31_toaltstack
+                qm31_mul
+
+                { qm31_roll(k - i) }
+                qm31_add
+            }
+
+            qm31_fromaltstack
+            OP_2DROP OP_2DROP
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::pcs::{accumulate_quotients, QuotientAccumulationGadget};
+    use crate::tests_utils::report::report_bitcoin_script_size;
+    use crate::treepp::*;
+    use crate::utils::get_rand_qm31;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use rust_bitcoin_m31::qm31_equalverify;
+
+    #[test]
+    fn test_accumulate_quotients() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        for k in 1..=8 {
+            let accumulate_script = QuotientAccumulationGadget::accumulate(k);
+            report_bitcoin_script_size(
+                "QuotientAccumulation",
+                format!("accumulate({})", k).as_str(),
+                accumulate_script.len(),
+            );
+
+            let random_coeff = get_rand_qm31(&mut prng);
+            let quotients = (0..k).map(|_| get_rand_qm31(&mut prng)).collect::<Vec<_>>();
+
+            let expected = accumulate_quotients(random_coeff, &quotients);
+
+            let script = script! {
+                { random_coeff }
+                for quotient in quotients.iter() {
+                    { *quotient }
+                }
+                { accumulate_script.clone() }
+                { expected }
+                qm31_equalverify
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
+}
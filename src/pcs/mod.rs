@@ -0,0 +1,19 @@
+use stwo_prover::core::fields::qm31::QM31;
+
+mod bitcoin_script;
+pub use bitcoin_script::*;
+
+/// Accumulate the quotients of several columns at a query into a single value, with successive
+/// powers of `random_coeff` in the Horner form.
+///
+/// This is how the quotients are combined before being fed into FRI, where the first quotient
+/// ends up with the highest power of `random_coeff`.
+pub fn accumulate_quotients(random_coeff: QM31, quotients: &[QM31]) -> QM31 {
+    assert!(!quotients.is_empty());
+
+    let mut res = quotients[0];
+    for quotient in quotients.iter().skip(1) {
+        res = res * random_coeff + *quotient;
+    }
+    res
+}
@@ -0,0 +1,402 @@
+use crate::digest::digest_to_hex;
+use crate::fibonacci::{verify_with_hints, FibonacciConfig, FibonacciVerifierGadget};
+use crate::proof::{load_proof, ProofFormat, PROOF_FORMAT_VERSION};
+use crate::taproot::{VerifierTaproot, VerifierTaprootBuilder};
+use crate::treepp::*;
+use crate::utils::optimize_pick_roll;
+use bitcoin::hex::DisplayHex;
+use bitcoin_scriptexec::convert_to_witness;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::Path;
+use stwo_prover::core::channel::BWSSha256Channel;
+
+/// The version of the artifact layout.
+///
+/// It must be bumped whenever the files or the manifest change.
+pub const ARTIFACTS_VERSION: u32 = 3;
+
+/// Whether the verifier leaf opens the commitments and checks the FRI queries.
+///
+/// It does not yet: [`FibonacciVerifierGadget::run_verifier`] replays the Fiat-Shamir
+/// transcript, the composition check, the PoW, and the query sampling, and then drops its
+/// state, so the hinted commitments are never opened. Hints that pass these checks can be made
+/// without a valid proof, so an output locked to the verifier leaf alone is not protected by the
+/// proof, and it should only hold test funds, e.g., on regtest.
+pub const VERIFIER_CHECKS_OPENINGS: bool = false;
+
+/// File name of the verifier script (hex).
+pub const VERIFIER_SCRIPT_FILE: &str = "verifier_script.hex";
+/// File name of the hint witness (a JSON array of hex-encoded witness items).
+pub const HINT_WITNESS_FILE: &str = "hint_witness.json";
+/// File name of the taproot data (JSON).
+pub const TAPROOT_FILE: &str = "taproot.json";
+/// File name of the manifest (JSON).
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// A file in the artifact set.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactFile {
+    /// The file name, relative to the artifact directory.
+    pub file: String,
+    /// The size of the file in bytes.
+    pub size: usize,
+    /// The SHA256 of the file content (hex).
+    pub sha256: String,
+}
+
+/// A leaf of the taptree.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaprootLeafData {
+    /// The leaf script (hex).
+    pub script: String,
+    /// The leaf version.
+    pub leaf_version: u8,
+    /// The control block for spending through this leaf (hex).
+    pub control_block: String,
+}
+
+/// The taproot data of the locked output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaprootData {
+    /// The internal key (hex).
+    pub internal_key: String,
+    /// The tweaked output key (hex).
+    pub output_key: String,
+    /// The scriptPubKey of the output (hex).
+    pub script_pubkey: String,
+    /// The Merkle root of the taptree (hex).
+    pub merkle_root: Option<String>,
+    /// The verifier leaves of the taptree.
+    pub leaves: Vec<TaprootLeafData>,
+    /// The refund leaf of the taptree, if any.
+    pub refund_leaf: Option<TaprootLeafData>,
+    /// The committee leaf of the taptree, if any.
+    pub committee_leaf: Option<TaprootLeafData>,
+}
+
+impl TaprootLeafData {
+    fn new(taproot: &VerifierTaproot, leaf: &Script) -> Self {
+        Self {
+            script: leaf.as_bytes().to_lower_hex_string(),
+            leaf_version: taproot
+                .leaf_version(leaf)
+                .expect("the script should be a leaf of the taptree")
+                .to_consensus(),
+            control_block: taproot
+                .control_block(leaf)
+                .serialize()
+                .to_lower_hex_string(),
+        }
+    }
+}
+
+impl From<&VerifierTaproot> for TaprootData {
+    fn from(taproot: &VerifierTaproot) -> Self {
+        Self {
+            internal_key: taproot.internal_key.to_string(),
+            output_key: taproot.spend_info.output_key().to_string(),
+            script_pubkey: taproot.script_pubkey().as_bytes().to_lower_hex_string(),
+            merkle_root: taproot
+                .spend_info
+                .merkle_root()
+                .map(|root| root.to_string()),
+            leaves: taproot
+                .verifier_leaves
+                .iter()
+                .map(|(leaf, _)| TaprootLeafData::new(taproot, leaf))
+                .collect(),
+            refund_leaf: taproot
+                .refund_leaf
+                .as_ref()
+                .map(|leaf| TaprootLeafData::new(taproot, leaf)),
+            committee_leaf: taproot
+                .committee_leaf
+                .as_ref()
+                .map(|leaf| TaprootLeafData::new(taproot, leaf)),
+        }
+    }
+}
+
+/// The manifest describing the artifact set of a locked output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactManifest {
+    /// The version of the artifact layout, see [`ARTIFACTS_VERSION`].
+    pub version: u32,
+    /// The version of the proof format that the hints are generated from.
+    pub proof_format_version: u32,
+    /// The statement being verified.
+    pub config: FibonacciConfig,
+    /// The initial channel digest (hex).
+    pub channel_init_state: String,
+    /// The verifier script, which is the only leaf of the taptree.
+    pub verifier_script: ArtifactFile,
+    /// The hint witness, which precedes the leaf script and the control block in the witness.
+    pub hint_witness: ArtifactFile,
+    /// The number of items in the hint witness.
+    pub hint_witness_items: usize,
+    /// The taproot data.
+    pub taproot: ArtifactFile,
+    /// Whether the verifier script opens the commitments and checks the FRI queries, see
+    /// [`VERIFIER_CHECKS_OPENINGS`].
+    pub verifier_checks_openings: bool,
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+fn write_artifact(dir: &Path, file: &str, content: &[u8]) -> io::Result<ArtifactFile> {
+    fs::write(dir.join(file), content)?;
+
+    let mut hasher = Sha256::new();
+    Digest::update(&mut hasher, content);
+
+    Ok(ArtifactFile {
+        file: file.to_string(),
+        size: content.len(),
+        sha256: hasher.finalize().as_slice().to_lower_hex_string(),
+    })
+}
+
+/// Read the configuration of the statement from a JSON file.
+pub fn load_config<P: AsRef<Path>>(path: P) -> io::Result<FibonacciConfig> {
+    serde_json::from_slice(&fs::read(path)?).map_err(invalid_data)
+}
+
+/// Assemble the verifier leaf script, which leaves exactly one true element when it succeeds.
+///
+/// The script does not open the commitments nor check the FRI queries, see
+/// [`VERIFIER_CHECKS_OPENINGS`].
+///
+/// The shallow `OP_PICK` and `OP_ROLL` of the gadgets are replaced by their one-byte
+/// equivalents, see [`optimize_pick_roll`].
+pub fn verifier_leaf_script(channel: &BWSSha256Channel, config: &FibonacciConfig) -> Script {
+    optimize_pick_roll(&unoptimized_verifier_leaf_script(channel, config))
+}
+
+fn unoptimized_verifier_leaf_script(
+    channel: &BWSSha256Channel,
+    config: &FibonacciConfig,
+) -> Script {
+    script! {
+        { FibonacciVerifierGadget::run_verifier(channel, config.log_size, config.claim_m31()) }
+        OP_TRUE
+    }
+}
+
+/// Read a serialized proof and the configuration from disk, and write the verifier script, the
+/// hint witness, the taproot data, and the manifest into `output_dir`.
+///
+/// The output described by the taproot data is locked to the verifier leaf alone, which does
+/// not check the openings, see [`VERIFIER_CHECKS_OPENINGS`]. The manifest records it, so that
+/// the archived artifacts of an output say so.
+pub fn generate_artifacts<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    proof_path: P,
+    proof_format: ProofFormat,
+    config_path: Q,
+    output_dir: R,
+) -> io::Result<ArtifactManifest> {
+    let config = load_config(config_path)?;
+    let (proof, channel) = load_proof(proof_path, proof_format)?;
+
+    let hints = verify_with_hints(proof, &config.air(), &mut channel.clone())
+        .map_err(|err| invalid_data(format!("{:?}", err)))?;
+    let hint_witness = convert_to_witness(script! { { hints } }).map_err(invalid_data)?;
+
+    let verifier_script = verifier_leaf_script(&channel, &config);
+    let taproot = VerifierTaprootBuilder::new()
+        .add_verifier_leaf(verifier_script.clone())
+        .finalize();
+
+    let output_dir = output_dir.as_ref();
+    fs::create_dir_all(output_dir)?;
+
+    let verifier_script_file = write_artifact(
+        output_dir,
+        VERIFIER_SCRIPT_FILE,
+        verifier_script.as_bytes().to_lower_hex_string().as_bytes(),
+    )?;
+
+    let hint_witness_hex = hint_witness
+        .iter()
+        .map(|item| item.to_lower_hex_string())
+        .collect::<Vec<String>>();
+    let hint_witness_file = write_artifact(
+        output_dir,
+        HINT_WITNESS_FILE,
+        &serde_json::to_vec_pretty(&hint_witness_hex).map_err(invalid_data)?,
+    )?;
+
+    let taproot_file = write_artifact(
+        output_dir,
+        TAPROOT_FILE,
+        &serde_json::to_vec_pretty(&TaprootData::from(&taproot)).map_err(invalid_data)?,
+    )?;
+
+    let manifest = ArtifactManifest {
+        version: ARTIFACTS_VERSION,
+        proof_format_version: PROOF_FORMAT_VERSION,
+        config,
+        channel_init_state: digest_to_hex(&channel.digest),
+        verifier_script: verifier_script_file,
+        hint_witness: hint_witness_file,
+        hint_witness_items: hint_witness.len(),
+        taproot: taproot_file,
+        verifier_checks_openings: VERIFIER_CHECKS_OPENINGS,
+    };
+    fs::write(
+        output_dir.join(MANIFEST_FILE),
+        serde_json::to_vec_pretty(&manifest).map_err(invalid_data)?,
+    )?;
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::FibonacciConfig;
+    use crate::pipeline::{
+        generate_artifacts, unoptimized_verifier_leaf_script, verifier_leaf_script,
+        ArtifactManifest, TaprootData, HINT_WITNESS_FILE, MANIFEST_FILE, TAPROOT_FILE,
+    };
+    use crate::proof::{save_proof, ProofFormat};
+    use crate::taproot::VerifierTaprootBuilder;
+    use crate::tests_utils::report::report_bitcoin_script_size;
+    use crate::tests_utils::temp_dir::TempDir;
+    use crate::treepp::*;
+    use bitcoin::hex::{DisplayHex, FromHex};
+    use bitcoin::key::Keypair;
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use bitcoin::taproot::ControlBlock;
+    use bitcoin_scriptexec::execute_script_with_witness_unlimited_stack;
+    use std::fs;
+    use stwo_prover::core::channel::{BWSSha256Channel, Channel};
+    use stwo_prover::core::fields::m31::BaseField;
+    use stwo_prover::core::fields::IntoSlice;
+    use stwo_prover::core::prover::prove;
+    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hasher;
+    use stwo_prover::core::vcs::hasher::Hasher;
+    use stwo_prover::examples::fibonacci::Fibonacci;
+
+    #[test]
+    fn test_generate_artifacts() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let fib = Fibonacci::new(config.log_size, config.claim_m31());
+
+        let trace = fib.get_trace();
+        let channel =
+            &mut BWSSha256Channel::new(BWSSha256Hasher::hash(BaseField::into_slice(&[fib
+                .air
+                .component
+                .claim])));
+        let channel_clone = channel.clone();
+        let proof = prove(&fib.air, channel, vec![trace]).unwrap();
+
+        let temp_dir = TempDir::new("test-generate-artifacts");
+        let dir = temp_dir.path();
+
+        let proof_path = dir.join("proof.bin");
+        save_proof(&proof_path, &proof, &channel_clone, ProofFormat::Bincode).unwrap();
+
+        let config_path = dir.join("config.json");
+        fs::write(&config_path, serde_json::to_vec(&config).unwrap()).unwrap();
+
+        let output_dir = dir.join("artifacts");
+        let manifest =
+            generate_artifacts(&proof_path, ProofFormat::Bincode, &config_path, &output_dir)
+                .unwrap();
+
+        let stored_manifest: ArtifactManifest =
+            serde_json::from_slice(&fs::read(output_dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+        assert_eq!(stored_manifest, manifest);
+        assert!(!manifest.verifier_checks_openings);
+
+        let taproot: TaprootData =
+            serde_json::from_slice(&fs::read(output_dir.join(TAPROOT_FILE)).unwrap()).unwrap();
+        let verifier_script = verifier_leaf_script(&channel_clone, &config);
+
+        // the peephole pass only makes the verifier smaller
+        let unoptimized_script = unoptimized_verifier_leaf_script(&channel_clone, &config);
+        report_bitcoin_script_size("Pipeline", "Verifier", unoptimized_script.len());
+        report_bitcoin_script_size("Pipeline", "Verifier-Optimized", verifier_script.len());
+        assert!(verifier_script.len() < unoptimized_script.len());
+
+        assert_eq!(taproot.leaves.len(), 1);
+        assert!(taproot.refund_leaf.is_none() && taproot.committee_leaf.is_none());
+        assert_eq!(
+            taproot.leaves[0].script,
+            verifier_script.as_bytes().to_lower_hex_string()
+        );
+
+        let hint_witness: Vec<String> =
+            serde_json::from_slice(&fs::read(output_dir.join(HINT_WITNESS_FILE)).unwrap()).unwrap();
+        assert_eq!(hint_witness.len(), manifest.hint_witness_items);
+        let hint_witness = hint_witness
+            .iter()
+            .map(|item| Vec::<u8>::from_hex(item).unwrap())
+            .collect::<Vec<Vec<u8>>>();
+
+        let exec_result =
+            execute_script_with_witness_unlimited_stack(verifier_script, hint_witness);
+        assert!(exec_result.success);
+    }
+
+    #[test]
+    fn test_taproot_data_leaves() {
+        let secp = Secp256k1::new();
+        let keys = (1..=3u8)
+            .map(|i| {
+                Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[i; 32]).unwrap())
+                    .x_only_public_key()
+                    .0
+            })
+            .collect::<Vec<_>>();
+
+        let taproot = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(script! { OP_1 })
+            .refund(keys[0], 144)
+            .committee(keys.clone(), 2)
+            .finalize();
+        let data = TaprootData::from(&taproot);
+
+        let refund_leaf = data.refund_leaf.as_ref().unwrap();
+        assert_eq!(
+            refund_leaf.script,
+            taproot
+                .refund_leaf
+                .as_ref()
+                .unwrap()
+                .as_bytes()
+                .to_lower_hex_string()
+        );
+        let committee_leaf = data.committee_leaf.as_ref().unwrap();
+        assert_eq!(
+            committee_leaf.script,
+            taproot
+                .committee_leaf
+                .as_ref()
+                .unwrap()
+                .as_bytes()
+                .to_lower_hex_string()
+        );
+
+        // every leaf is spendable with its own control block
+        let output_key = taproot.spend_info.output_key().to_inner();
+        for leaf in data.leaves.iter().chain([refund_leaf, committee_leaf]) {
+            let script = Script::from_bytes(Vec::<u8>::from_hex(&leaf.script).unwrap());
+            let control_block =
+                ControlBlock::decode(&Vec::<u8>::from_hex(&leaf.control_block).unwrap()).unwrap();
+            assert!(control_block.verify_taproot_commitment(&secp, output_key, &script));
+        }
+
+        // the data survives a roundtrip through JSON
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<TaprootData>(&json).unwrap(), data);
+    }
+}
@@ -87,35 +87,7 @@ mod test {
     use stwo_prover::core::channel::Channel;
     use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
 
-    use crate::pow::{bitcoin_script::PowGadget, hash_with_nonce, PoWHint};
-
-    // Check that the prefix leading zeros is greater than `bound_bits`.
-    fn check_leading_zeros(bytes: &[u8], bound_bits: u32) -> bool {
-        let mut n_bits = 0;
-        // bytes are in little endian order.
-        for byte in bytes.iter().rev() {
-            if *byte == 0 {
-                n_bits += 8;
-            } else {
-                n_bits += byte.leading_zeros();
-                break;
-            }
-        }
-        n_bits >= bound_bits
-    }
-
-    // A handy function for grinding, which finds a nonce that makes the resulting hash with enough zeroes.
-    fn grind_find_nonce(channel_digest: Vec<u8>, n_bits: u32) -> u64 {
-        let mut nonce = 0u64;
-
-        loop {
-            let hash = hash_with_nonce(&channel_digest, nonce);
-            if check_leading_zeros(hash.as_ref(), n_bits) {
-                return nonce;
-            }
-            nonce += 1;
-        }
-    }
+    use crate::pow::{bitcoin_script::PowGadget, grind_find_nonce, hash_with_nonce, PoWHint};
 
     #[test]
     fn test_push_pow_hint() {
@@ -124,7 +96,7 @@ mod test {
         let mut channel_digest = vec![0u8; 32];
         prng.fill_bytes(&mut channel_digest);
 
-        let nonce = grind_find_nonce(channel_digest.clone(), 1);
+        let nonce = grind_find_nonce(&channel_digest, 1, 0);
         let new_channel = hash_with_nonce(&channel_digest, nonce);
 
         let pow_hint = PoWHint::new(BWSSha256Hash::from(channel_digest), nonce, 1);
@@ -233,7 +205,7 @@ mod test {
                 let mut channel_digest = [0u8; 32].to_vec();
                 prng.fill_bytes(&mut channel_digest);
 
-                let nonce = grind_find_nonce(channel_digest.clone(), n_bits);
+                let nonce = grind_find_nonce(&channel_digest, n_bits, prng_seed);
 
                 let verify_pow_script = PowGadget::verify_pow(n_bits);
                 if prng_seed == 0 {
@@ -2,6 +2,9 @@ mod bitcoin_script;
 pub use bitcoin_script::*;
 
 use crate::treepp::pushable::{Builder, Pushable};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
 
@@ -16,7 +19,40 @@ pub fn hash_with_nonce(seed: &[u8], nonce: u64) -> Vec<u8> {
     hasher.finalize().as_slice().to_vec()
 }
 
+// Check that the number of leading zero bits of sha256(channel||nonce), which is read as a
+// little-endian number (so that its last byte is the most significant one), is at least
+// `n_bits`.
+fn check_leading_zeros(bytes: &[u8], n_bits: u32) -> bool {
+    let mut count = 0;
+    for byte in bytes.iter().rev() {
+        if *byte == 0 {
+            count += 8;
+        } else {
+            count += byte.leading_zeros();
+            break;
+        }
+    }
+    count >= n_bits
+}
+
+/// Find a PoW nonce for the channel digest with at least `n_bits` of zeroes.
+///
+/// The search starts from a nonce derived from `seed` and proceeds sequentially, so the result
+/// only depends on the channel digest, `n_bits`, and `seed`.
+pub fn grind_find_nonce(channel_digest: &[u8], n_bits: u32, seed: u64) -> u64 {
+    let mut nonce = ChaCha20Rng::seed_from_u64(seed).next_u64();
+
+    loop {
+        let hash = hash_with_nonce(channel_digest, nonce);
+        if check_leading_zeros(&hash, n_bits) {
+            return nonce;
+        }
+        nonce = nonce.wrapping_add(1);
+    }
+}
+
 /// A hint for PoW.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PoWHint {
     /// The PoW nonce.
     /// Note: with a nonce of only 64 bits, it is not possible to get 78 bit security here :)
@@ -0,0 +1,188 @@
+use crate::treepp::*;
+use rust_bitcoin_m31::{qm31_copy, qm31_equalverify, qm31_from_bottom, qm31_mul};
+
+/// Gadget for the table of powers of a challenge, which is built once and then served to all
+/// the use sites by copying.
+///
+/// The table is either computed from alpha, see [`Self::compute`], or hinted and checked, see
+/// [`Self::from_hint`]. Both take k - 1 qm31 multiplications, and the hinted table also pays an
+/// equality check for each power, so computing is the default, and the hinted table is meant for
+/// the verifiers that receive the powers from the prover anyway.
+pub struct PowersGadget;
+
+impl PowersGadget {
+    /// Compute the table of powers, see [`crate::powers::powers`].
+    ///
+    /// Input:
+    /// - alpha (qm31)
+    ///
+    /// Output:
+    /// - alpha, alpha^2, ..., alpha^k (k qm31, alpha^k on the top)
+    pub fn compute(k: usize) -> Script {
+        assert!(k > 0);
+        script! {
+            for i in 1..k {
+                { qm31_copy(0) }
+                { qm31_copy(i) }
+                qm31_mul
+            }
+        }
+    }
+
+    /// Pull the table of powers from the hint and check each power against the previous one.
+    ///
+    /// Hint:
+    /// - alpha^2, ..., alpha^k (see [`crate::powers::PowersHint`])
+    ///
+    /// Input:
+    /// - alpha (qm31)
+    ///
+    /// Output:
+    /// - alpha, alpha^2, ..., alpha^k (k qm31, alpha^k on the top)
+    pub fn from_hint(k: usize) -> Script {
+        assert!(k > 0);
+        script! {
+            for i in 1..k {
+                // alpha^(i + 1) == alpha^i * alpha
+                qm31_from_bottom
+                { qm31_copy(0) }
+                { qm31_copy(2) }
+                { qm31_copy(i + 2) }
+                qm31_mul
+                qm31_equalverify
+            }
+        }
+    }
+
+    /// Copy alpha^i from the table to the top of the stack, when there are `depth` qm31
+    /// elements above the table.
+    pub fn pick(k: usize, i: usize, depth: usize) -> Script {
+        assert!(i >= 1 && i <= k);
+        script! {
+            { qm31_copy(depth + k - i) }
+        }
+    }
+
+    /// Drop the table, which is on the top of the stack.
+    pub fn drop_table(k: usize) -> Script {
+        script! {
+            for _ in 0..k {
+                OP_2DROP OP_2DROP
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::powers::{powers, PowersGadget, PowersHint};
+    use crate::tests_utils::report::report_bitcoin_script_size;
+    use crate::treepp::*;
+    use crate::utils::get_rand_qm31;
+    use num_traits::One;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use rust_bitcoin_m31::qm31_equalverify;
+    use stwo_prover::core::fields::qm31::QM31;
+
+    #[test]
+    fn test_powers() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        for k in 1..=8 {
+            let compute_script = PowersGadget::compute(k);
+            report_bitcoin_script_size(
+                "Powers",
+                format!("compute({})", k).as_str(),
+                compute_script.len(),
+            );
+
+            let alpha = get_rand_qm31(&mut prng);
+            let expected = powers(alpha, k);
+
+            let script = script! {
+                { alpha }
+                { compute_script.clone() }
+                // serve every power, with the ones already served on top of the table
+                for i in 1..=k {
+                    { PowersGadget::pick(k, i, i - 1) }
+                }
+                for i in (1..=k).rev() {
+                    { expected[i - 1] }
+                    qm31_equalverify
+                }
+                for i in (1..=k).rev() {
+                    { expected[i - 1] }
+                    qm31_equalverify
+                }
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            let script = script! {
+                { alpha }
+                { compute_script.clone() }
+                { PowersGadget::drop_table(k) }
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
+
+    #[test]
+    fn test_powers_from_hint() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        for k in 1..=8 {
+            let hint_script = PowersGadget::from_hint(k);
+            report_bitcoin_script_size(
+                "Powers",
+                format!("from_hint({})", k).as_str(),
+                hint_script.len(),
+            );
+
+            let alpha = get_rand_qm31(&mut prng);
+            let hint = PowersHint::new(alpha, k);
+
+            let script = script! {
+                { hint.clone() }
+                { alpha }
+                { hint_script.clone() }
+                // serve every power, as the computed table does
+                for i in 1..=k {
+                    { PowersGadget::pick(k, i, i - 1) }
+                }
+                for i in (1..=k).rev() {
+                    { hint.powers[i - 1] }
+                    qm31_equalverify
+                }
+                { PowersGadget::drop_table(k) }
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+
+            // a wrong power is rejected
+            if k > 1 {
+                let mut wrong_hint = hint.clone();
+                wrong_hint.powers[k - 1] += QM31::one();
+
+                let script = script! {
+                    { wrong_hint }
+                    { alpha }
+                    { hint_script.clone() }
+                    { PowersGadget::drop_table(k) }
+                    OP_TRUE
+                };
+
+                let exec_result = execute_script(script);
+                assert!(!exec_result.success);
+            }
+        }
+    }
+}
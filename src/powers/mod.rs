@@ -0,0 +1,49 @@
+use crate::treepp::pushable::{Builder, Pushable};
+use stwo_prover::core::fields::qm31::QM31;
+
+mod bitcoin_script;
+pub use bitcoin_script::*;
+
+/// The powers alpha, alpha^2, ..., alpha^k of a challenge.
+pub fn powers(alpha: QM31, k: usize) -> Vec<QM31> {
+    assert!(k > 0);
+
+    let mut res = Vec::with_capacity(k);
+    res.push(alpha);
+    for i in 1..k {
+        res.push(res[i - 1] * alpha);
+    }
+    res
+}
+
+/// The hint for the table of powers, see [`PowersGadget::from_hint`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PowersHint {
+    /// The powers alpha, alpha^2, ..., alpha^k.
+    pub powers: Vec<QM31>,
+}
+
+impl PowersHint {
+    /// Compute the hint for the first k powers of the challenge.
+    pub fn new(alpha: QM31, k: usize) -> Self {
+        Self {
+            powers: powers(alpha, k),
+        }
+    }
+}
+
+/// Push alpha^2, ..., alpha^k, as alpha is already in the input of the gadget.
+impl Pushable for &PowersHint {
+    fn bitcoin_script_push(self, mut builder: Builder) -> Builder {
+        for power in self.powers.iter().skip(1) {
+            builder = power.bitcoin_script_push(builder);
+        }
+        builder
+    }
+}
+
+impl Pushable for PowersHint {
+    fn bitcoin_script_push(self, builder: Builder) -> Builder {
+        (&self).bitcoin_script_push(builder)
+    }
+}
@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+lazy_static::lazy_static! {
+    static ref SCOPES: Mutex<BTreeMap<String, ScopeStats>> = Mutex::new(BTreeMap::new());
+}
+
+thread_local! {
+    static OPEN_SCOPES: RefCell<Vec<&'static str>> = const { RefCell::new(vec![]) };
+}
+
+/// The time spent in a timing scope, over all its calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScopeStats {
+    /// The number of times the scope was entered.
+    pub calls: usize,
+    /// The total time spent in the scope, including its nested scopes.
+    pub total: Duration,
+}
+
+/// A timing scope, which records the time until it is dropped, see [`scope`].
+pub struct ProfileScope {
+    path: String,
+    start: Instant,
+}
+
+/// Open a timing scope, which is closed when the returned guard is dropped.
+///
+/// The scope is recorded under its path, which joins the names of the scopes open on the same
+/// thread with `/`, e.g., `prove_and_hint/verify_with_hints`. A scope opened on a thread of the
+/// `parallel` feature does not see the scopes of the thread that spawned the work.
+pub fn scope(name: &'static str) -> ProfileScope {
+    let path = OPEN_SCOPES.with(|open| {
+        let mut open = open.borrow_mut();
+        open.push(name);
+        open.join("/")
+    });
+    ProfileScope {
+        path,
+        start: Instant::now(),
+    }
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        OPEN_SCOPES.with(|open| open.borrow_mut().pop());
+
+        let mut scopes = SCOPES.lock().unwrap();
+        let stats = scopes.entry(std::mem::take(&mut self.path)).or_default();
+        stats.calls += 1;
+        stats.total += elapsed;
+    }
+}
+
+/// The time spent in each scope since the start or the last [`reset_report`], ordered by path.
+pub fn report() -> Vec<(String, ScopeStats)> {
+    SCOPES
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(path, stats)| (path.clone(), *stats))
+        .collect()
+}
+
+/// Forget the time spent in all the scopes so far.
+pub fn reset_report() {
+    SCOPES.lock().unwrap().clear();
+}
+
+/// Render a report as a table, with one line per scope, indented by nesting depth.
+pub fn format_report(report: &[(String, ScopeStats)]) -> String {
+    let mut out = String::new();
+    for (path, stats) in report.iter() {
+        let depth = path.matches('/').count();
+        let name = path.rsplit('/').next().unwrap();
+        out.push_str(&format!(
+            "{:indent$}{:<width$} {:>6} calls {:>12.3?}\n",
+            "",
+            name,
+            stats.calls,
+            stats.total,
+            indent = 2 * depth,
+            width = 40 - 2 * depth.min(20),
+        ));
+    }
+    out
+}
+
+/// Run `f` under a sampling profiler, and write the samples as a flamegraph in SVG to `path`.
+pub fn flamegraph<T, F: FnOnce() -> T>(path: &Path, f: F) -> io::Result<T> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(1000)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(io::Error::other)?;
+
+    let res = f();
+
+    let report = guard.report().build().map_err(io::Error::other)?;
+    report
+        .flamegraph(File::create(path)?)
+        .map_err(io::Error::other)?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::{prove_and_hint, FibonacciConfig};
+    use crate::profiling::{format_report, report, scope};
+
+    #[test]
+    fn test_scopes() {
+        {
+            let _outer = scope("test_scopes");
+            for _ in 0..3 {
+                let _inner = scope("inner");
+            }
+        }
+
+        let report = report();
+        let outer = report
+            .iter()
+            .find(|(path, _)| path == "test_scopes")
+            .unwrap();
+        let inner = report
+            .iter()
+            .find(|(path, _)| path == "test_scopes/inner")
+            .unwrap();
+        assert_eq!(outer.1.calls, 1);
+        assert_eq!(inner.1.calls, 3);
+        assert!(inner.1.total <= outer.1.total);
+        assert!(format_report(&report).contains("  inner"));
+    }
+
+    #[test]
+    fn test_profile_prove_and_hint() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        prove_and_hint(&config).unwrap();
+
+        // other tests record scopes at the same time, so only the presence is checked
+        let report = report();
+        for path in [
+            "prove_and_hint",
+            "prove_and_hint/prove",
+            "prove_and_hint/verify_with_hints",
+        ] {
+            assert!(report.iter().any(|(p, _)| p == path), "missing {}", path);
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::fibonacci::FibonacciConfig;
+use crate::pipeline::verifier_leaf_script;
+use crate::treepp::*;
+use bitcoin::hex::DisplayHex;
+use sha2::{Digest, Sha256};
+use stwo_prover::core::prover::{LOG_BLOWUP_FACTOR, N_QUERIES, PROOF_OF_WORK_BITS};
+
+/// The version of the verifier protocol.
+///
+/// It is part of the program ID, and it must be bumped whenever the verifier changes in a way
+/// that the leaves do not capture, e.g., the layout of the hints.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The domain separator of the program ID.
+const PROGRAM_ID_TAG: &[u8] = b"bitcoin-circle-stark/program-id";
+
+/// A verifier program: the assembled leaves that verify a statement, along with the statement
+/// and the security parameters they are built for.
+pub struct VerifierProgram {
+    /// The statement.
+    pub config: FibonacciConfig,
+    /// The verifier leaves.
+    pub leaves: Vec<Script>,
+}
+
+impl VerifierProgram {
+    /// The program that verifies a Fibonacci statement in a single leaf.
+    pub fn fibonacci(config: FibonacciConfig) -> Self {
+        let leaves = vec![verifier_leaf_script(&config.initial_channel(), &config)];
+        Self { config, leaves }
+    }
+
+    /// A stable commitment to the program, which hashes the protocol version, the security
+    /// parameters, the statement, and the leaves.
+    ///
+    /// Off-chain systems can refer to a verifier by its ID, and a change in any of the gadgets
+    /// across releases shows up as a different ID.
+    pub fn program_id(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        Digest::update(&mut hasher, PROGRAM_ID_TAG);
+        Digest::update(&mut hasher, PROTOCOL_VERSION.to_le_bytes());
+
+        Digest::update(&mut hasher, LOG_BLOWUP_FACTOR.to_le_bytes());
+        Digest::update(&mut hasher, (N_QUERIES as u64).to_le_bytes());
+        Digest::update(&mut hasher, PROOF_OF_WORK_BITS.to_le_bytes());
+
+        Digest::update(&mut hasher, self.config.log_size.to_le_bytes());
+        Digest::update(&mut hasher, self.config.claim.to_le_bytes());
+
+        Digest::update(&mut hasher, (self.leaves.len() as u64).to_le_bytes());
+        for leaf in self.leaves.iter() {
+            Digest::update(&mut hasher, (leaf.len() as u64).to_le_bytes());
+            Digest::update(&mut hasher, leaf.as_bytes());
+        }
+
+        hasher.finalize().into()
+    }
+
+    /// The program ID in hex.
+    pub fn program_id_hex(&self) -> String {
+        self.program_id().to_lower_hex_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::FibonacciConfig;
+    use crate::program::{VerifierProgram, PROTOCOL_VERSION};
+    use crate::tests_utils::snapshot::assert_snapshot;
+    use crate::treepp::*;
+
+    #[test]
+    fn test_program_id() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+
+        let program = VerifierProgram::fibonacci(config);
+        assert_eq!(
+            program.program_id(),
+            VerifierProgram::fibonacci(config).program_id()
+        );
+        assert_eq!(program.program_id_hex().len(), 64);
+
+        // a different statement is a different program
+        let other = VerifierProgram::fibonacci(FibonacciConfig {
+            log_size: 5,
+            claim: 1,
+        });
+        assert_ne!(program.program_id(), other.program_id());
+
+        // so is a change in the leaves
+        let mut drifted = VerifierProgram::fibonacci(config);
+        drifted.leaves.push(script! { OP_TRUE });
+        assert_ne!(program.program_id(), drifted.program_id());
+    }
+
+    /// The program ID of a fixed statement is pinned, so that a change in the leaves is noticed
+    /// in review: when this test fails, bump [`PROTOCOL_VERSION`] if the change also affects
+    /// what the leaves do not capture (e.g. the layout of the hints), then record the snapshot
+    /// again with `UPDATE_SNAPSHOTS=1`.
+    #[test]
+    fn test_program_id_golden() {
+        let program = VerifierProgram::fibonacci(FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        });
+
+        assert_snapshot(
+            "program_id",
+            &[
+                ("protocol_version".to_string(), PROTOCOL_VERSION.to_string()),
+                ("fibonacci_5".to_string(), program.program_id_hex()),
+            ],
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use crate::digest::{digest_from_canonical_bytes, CanonicalDigest};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+use stwo_prover::core::channel::{BWSSha256Channel, Channel};
+use stwo_prover::core::fields::m31::{M31, P};
+use stwo_prover::core::fields::qm31::QM31;
+use stwo_prover::core::fri::{FriLayerProof, FriProof};
+use stwo_prover::core::pcs::{CommitmentSchemeProof, TreeVec};
+use stwo_prover::core::poly::line::LinePoly;
+use stwo_prover::core::proof_of_work::ProofOfWorkProof;
+use stwo_prover::core::prover::StarkProof;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+use stwo_prover::core::vcs::bws_sha256_merkle::BWSSha256MerkleHasher;
+use stwo_prover::core::vcs::prover::MerkleDecommitment;
+
+/// The version of the serialized proof format.
+///
+/// It must be bumped whenever the layout of [`SerializedProof`] changes.
+pub const PROOF_FORMAT_VERSION: u32 = 1;
+
+/// The encoding of a serialized proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofFormat {
+    /// Human-readable JSON.
+    Json,
+    /// Compact bincode.
+    Bincode,
+}
+
+/// A Merkle decommitment in the serialized proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializedMerkleDecommitment {
+    /// Hashes of the sibling nodes.
+    pub hash_witness: Vec<Vec<u8>>,
+    /// Column values that are not queried but needed for the decommitment.
+    pub column_witness: Vec<u32>,
+}
+
+/// A FRI inner layer in the serialized proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializedFriLayerProof {
+    /// The subset of the evaluations needed to fold the queries.
+    pub evals_subset: Vec<[u32; 4]>,
+    /// The Merkle decommitment of the layer.
+    pub decommitment: SerializedMerkleDecommitment,
+    /// The commitment of the layer.
+    pub commitment: Vec<u8>,
+}
+
+/// A stwo proof in a stable, serializable layout, where every qm31 element is written as its four
+/// m31 limbs and every hash as its raw bytes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializedStarkProof {
+    /// Commitments of the trace and the composition polynomial.
+    pub commitments: Vec<Vec<u8>>,
+    /// Sampled values, per tree, per column, per sample point.
+    pub sampled_values: Vec<Vec<Vec<[u32; 4]>>>,
+    /// Merkle decommitments, per tree.
+    pub decommitments: Vec<SerializedMerkleDecommitment>,
+    /// Queried values, per tree, per column.
+    pub queried_values: Vec<Vec<Vec<u32>>>,
+    /// The PoW nonce.
+    pub proof_of_work_nonce: u64,
+    /// The FRI inner layers.
+    pub fri_inner_layers: Vec<SerializedFriLayerProof>,
+    /// The coefficients of the FRI last layer polynomial.
+    pub fri_last_layer_poly: Vec<[u32; 4]>,
+}
+
+/// A proof together with the initial state of the channel that the verifier starts from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializedProof {
+    /// The version of the format, see [`PROOF_FORMAT_VERSION`].
+    pub version: u32,
+    /// The initial channel digest.
+    pub channel_init_state: Vec<u8>,
+    /// The proof.
+    pub proof: SerializedStarkProof,
+}
+
+pub(crate) fn qm31_to_limbs(v: &QM31) -> [u32; 4] {
+    [v.0 .0 .0, v.0 .1 .0, v.1 .0 .0, v.1 .1 .0]
+}
+
+pub(crate) fn qm31_from_limbs(v: &[u32; 4]) -> io::Result<QM31> {
+    Ok(QM31::from_m31(
+        m31_from_u32(v[0])?,
+        m31_from_u32(v[1])?,
+        m31_from_u32(v[2])?,
+        m31_from_u32(v[3])?,
+    ))
+}
+
+// An m31 element, which is rejected rather than reduced if it is not canonical.
+pub(crate) fn m31_from_u32(v: u32) -> io::Result<M31> {
+    if v >= P {
+        return Err(invalid_data(format!(
+            "{} is not a canonical m31 element",
+            v
+        )));
+    }
+    Ok(M31::from_u32_unchecked(v))
+}
+
+pub(crate) fn digest_from_bytes(bytes: &[u8]) -> io::Result<BWSSha256Hash> {
+    digest_from_canonical_bytes(bytes).map_err(invalid_data)
+}
+
+pub(crate) fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+impl From<&StarkProof> for SerializedStarkProof {
+    fn from(proof: &StarkProof) -> Self {
+        let pcs_proof = &proof.commitment_scheme_proof;
+
+        Self {
+            commitments: proof
+                .commitments
+                .0
+                .iter()
+                .map(|c| c.to_canonical_bytes().to_vec())
+                .collect(),
+            sampled_values: pcs_proof
+                .sampled_values
+                .0
+                .iter()
+                .map(|tree| {
+                    tree.iter()
+                        .map(|column| column.iter().map(qm31_to_limbs).collect())
+                        .collect()
+                })
+                .collect(),
+            decommitments: pcs_proof
+                .decommitments
+                .0
+                .iter()
+                .map(|d| SerializedMerkleDecommitment {
+                    hash_witness: d
+                        .hash_witness
+                        .iter()
+                        .map(|h| h.to_canonical_bytes().to_vec())
+                        .collect(),
+                    column_witness: d.column_witness.iter().map(|v| v.0).collect(),
+                })
+                .collect(),
+            queried_values: pcs_proof
+                .queried_values
+                .0
+                .iter()
+                .map(|tree| {
+                    tree.iter()
+                        .map(|column| column.iter().map(|v| v.0).collect())
+                        .collect()
+                })
+                .collect(),
+            proof_of_work_nonce: pcs_proof.proof_of_work.nonce,
+            fri_inner_layers: pcs_proof
+                .fri_proof
+                .inner_layers
+                .iter()
+                .map(|layer| SerializedFriLayerProof {
+                    evals_subset: layer.evals_subset.iter().map(qm31_to_limbs).collect(),
+                    decommitment: SerializedMerkleDecommitment {
+                        hash_witness: layer
+                            .decommitment
+                            .hash_witness
+                            .iter()
+                            .map(|h| h.to_canonical_bytes().to_vec())
+                            .collect(),
+                        column_witness: layer
+                            .decommitment
+                            .column_witness
+                            .iter()
+                            .map(|v| v.0)
+                            .collect(),
+                    },
+                    commitment: layer.commitment.to_canonical_bytes().to_vec(),
+                })
+                .collect(),
+            fri_last_layer_poly: pcs_proof
+                .fri_proof
+                .last_layer_poly
+                .iter()
+                .map(qm31_to_limbs)
+                .collect(),
+        }
+    }
+}
+
+fn decommitment_from(
+    d: &SerializedMerkleDecommitment,
+) -> io::Result<MerkleDecommitment<BWSSha256MerkleHasher>> {
+    Ok(MerkleDecommitment {
+        hash_witness: d
+            .hash_witness
+            .iter()
+            .map(|h| digest_from_bytes(h))
+            .collect::<io::Result<_>>()?,
+        column_witness: d
+            .column_witness
+            .iter()
+            .map(|v| m31_from_u32(*v))
+            .collect::<io::Result<_>>()?,
+    })
+}
+
+impl TryFrom<&SerializedStarkProof> for StarkProof {
+    type Error = io::Error;
+
+    fn try_from(proof: &SerializedStarkProof) -> io::Result<Self> {
+        Ok(StarkProof {
+            commitments: TreeVec::new(
+                proof
+                    .commitments
+                    .iter()
+                    .map(|c| digest_from_bytes(c))
+                    .collect::<io::Result<_>>()?,
+            ),
+            commitment_scheme_proof: CommitmentSchemeProof {
+                sampled_values: TreeVec::new(
+                    proof
+                        .sampled_values
+                        .iter()
+                        .map(|tree| {
+                            tree.iter()
+                                .map(|column| {
+                                    column
+                                        .iter()
+                                        .map(qm31_from_limbs)
+                                        .collect::<io::Result<Vec<_>>>()
+                                })
+                                .collect::<io::Result<Vec<_>>>()
+                        })
+                        .collect::<io::Result<_>>()?,
+                ),
+                decommitments: TreeVec::new(
+                    proof
+                        .decommitments
+                        .iter()
+                        .map(decommitment_from)
+                        .collect::<io::Result<_>>()?,
+                ),
+                queried_values: TreeVec::new(
+                    proof
+                        .queried_values
+                        .iter()
+                        .map(|tree| {
+                            tree.iter()
+                                .map(|column| {
+                                    column
+                                        .iter()
+                                        .map(|v| m31_from_u32(*v))
+                                        .collect::<io::Result<Vec<_>>>()
+                                })
+                                .collect::<io::Result<Vec<_>>>()
+                        })
+                        .collect::<io::Result<_>>()?,
+                ),
+                proof_of_work: ProofOfWorkProof {
+                    nonce: proof.proof_of_work_nonce,
+                },
+                fri_proof: FriProof {
+                    inner_layers: proof
+                        .fri_inner_layers
+                        .iter()
+                        .map(|layer| {
+                            Ok(FriLayerProof {
+                                evals_subset: layer
+                                    .evals_subset
+                                    .iter()
+                                    .map(qm31_from_limbs)
+                                    .collect::<io::Result<_>>()?,
+                                decommitment: decommitment_from(&layer.decommitment)?,
+                                commitment: digest_from_bytes(&layer.commitment)?,
+                            })
+                        })
+                        .collect::<io::Result<_>>()?,
+                    last_layer_poly: LinePoly::new(
+                        proof
+                            .fri_last_layer_poly
+                            .iter()
+                            .map(qm31_from_limbs)
+                            .collect::<io::Result<_>>()?,
+                    ),
+                },
+            },
+        })
+    }
+}
+
+impl SerializedProof {
+    /// Wrap a proof and the channel that the verifier will start from.
+    pub fn new(proof: &StarkProof, channel: &BWSSha256Channel) -> Self {
+        Self {
+            version: PROOF_FORMAT_VERSION,
+            channel_init_state: channel.digest.to_canonical_bytes().to_vec(),
+            proof: SerializedStarkProof::from(proof),
+        }
+    }
+
+    /// Recover the proof and a fresh channel at the initial state.
+    pub fn to_proof_and_channel(&self) -> io::Result<(StarkProof, BWSSha256Channel)> {
+        if self.version != PROOF_FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "unsupported proof format version {}",
+                self.version
+            )));
+        }
+        let channel = BWSSha256Channel::new(digest_from_bytes(&self.channel_init_state)?);
+        Ok((StarkProof::try_from(&self.proof)?, channel))
+    }
+
+    /// Encode the proof in the given format.
+    pub fn to_bytes(&self, format: ProofFormat) -> io::Result<Vec<u8>> {
+        match format {
+            ProofFormat::Json => serde_json::to_vec_pretty(self).map_err(invalid_data),
+            ProofFormat::Bincode => bincode::serialize(self).map_err(invalid_data),
+        }
+    }
+
+    /// Decode the proof from the given format.
+    pub fn from_bytes(bytes: &[u8], format: ProofFormat) -> io::Result<Self> {
+        match format {
+            ProofFormat::Json => serde_json::from_slice(bytes).map_err(invalid_data),
+            ProofFormat::Bincode => bincode::deserialize(bytes).map_err(invalid_data),
+        }
+    }
+}
+
+/// Export a proof, together with the channel that the verifier will start from.
+pub fn export_proof(
+    proof: &StarkProof,
+    channel: &BWSSha256Channel,
+    format: ProofFormat,
+) -> io::Result<Vec<u8>> {
+    SerializedProof::new(proof, channel).to_bytes(format)
+}
+
+/// Import a proof and the channel that the verifier will start from.
+pub fn import_proof(
+    bytes: &[u8],
+    format: ProofFormat,
+) -> io::Result<(StarkProof, BWSSha256Channel)> {
+    SerializedProof::from_bytes(bytes, format)?.to_proof_and_channel()
+}
+
+/// Write a proof and the channel that the verifier will start from into a file.
+pub fn save_proof<P: AsRef<Path>>(
+    path: P,
+    proof: &StarkProof,
+    channel: &BWSSha256Channel,
+    format: ProofFormat,
+) -> io::Result<()> {
+    fs::write(path, export_proof(proof, channel, format)?)
+}
+
+/// Load a proof produced elsewhere, which can then be passed to
+/// [`verify_with_hints`](crate::fibonacci::verify_with_hints) with the returned channel.
+pub fn load_proof<P: AsRef<Path>>(
+    path: P,
+    format: ProofFormat,
+) -> io::Result<(StarkProof, BWSSha256Channel)> {
+    import_proof(&fs::read(path)?, format)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::verify_with_hints;
+    use crate::proof::{export_proof, import_proof, ProofFormat, SerializedProof};
+    use stwo_prover::core::channel::{BWSSha256Channel, Channel};
+    use stwo_prover::core::fields::m31::{BaseField, M31, P};
+    use stwo_prover::core::fields::IntoSlice;
+    use stwo_prover::core::prover::{prove, verify};
+    use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hasher;
+    use stwo_prover::core::vcs::hasher::Hasher;
+    use stwo_prover::examples::fibonacci::Fibonacci;
+
+    #[test]
+    fn test_proof_roundtrip() {
+        let fib = Fibonacci::new(5, M31::reduce(443693538));
+
+        let trace = fib.get_trace();
+        let channel =
+            &mut BWSSha256Channel::new(BWSSha256Hasher::hash(BaseField::into_slice(&[fib
+                .air
+                .component
+                .claim])));
+        let channel_clone = channel.clone();
+        let proof = prove(&fib.air, channel, vec![trace]).unwrap();
+
+        for format in [ProofFormat::Json, ProofFormat::Bincode] {
+            let bytes = export_proof(&proof, &channel_clone, format).unwrap();
+            let (imported_proof, mut imported_channel) = import_proof(&bytes, format).unwrap();
+            assert_eq!(imported_channel.digest, channel_clone.digest);
+
+            // the imported proof must be re-serialized into exactly the same bytes
+            assert_eq!(
+                export_proof(&imported_proof, &imported_channel, format).unwrap(),
+                bytes
+            );
+
+            let mut hint_channel = imported_channel.clone();
+            verify(imported_proof, &fib.air, &mut imported_channel).unwrap();
+
+            let (imported_proof, _) = import_proof(&bytes, format).unwrap();
+            verify_with_hints(imported_proof, &fib.air, &mut hint_channel).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_reject_unknown_version() {
+        let fib = Fibonacci::new(5, M31::reduce(443693538));
+
+        let trace = fib.get_trace();
+        let channel =
+            &mut BWSSha256Channel::new(BWSSha256Hasher::hash(BaseField::into_slice(&[fib
+                .air
+                .component
+                .claim])));
+        let channel_clone = channel.clone();
+        let proof = prove(&fib.air, channel, vec![trace]).unwrap();
+
+        let mut serialized = SerializedProof::new(&proof, &channel_clone);
+        serialized.version += 1;
+
+        let bytes = serialized.to_bytes(ProofFormat::Json).unwrap();
+        assert!(import_proof(&bytes, ProofFormat::Json).is_err());
+    }
+
+    #[test]
+    fn test_reject_malformed_proof() {
+        let fib = Fibonacci::new(5, M31::reduce(443693538));
+
+        let trace = fib.get_trace();
+        let channel =
+            &mut BWSSha256Channel::new(BWSSha256Hasher::hash(BaseField::into_slice(&[fib
+                .air
+                .component
+                .claim])));
+        let channel_clone = channel.clone();
+        let proof = prove(&fib.air, channel, vec![trace]).unwrap();
+        let serialized = SerializedProof::new(&proof, &channel_clone);
+
+        // a truncated hash
+        let mut truncated = serialized.clone();
+        truncated.proof.commitments[0].pop();
+        assert!(truncated.to_proof_and_channel().is_err());
+
+        // an m31 limb that is not reduced
+        let mut unreduced = serialized.clone();
+        unreduced.proof.fri_last_layer_poly[0][0] = P;
+        assert!(unreduced.to_proof_and_channel().is_err());
+
+        assert!(serialized.to_proof_and_channel().is_ok());
+    }
+}
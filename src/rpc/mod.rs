@@ -0,0 +1,285 @@
+use crate::pipeline::VERIFIER_CHECKS_OPENINGS;
+use crate::taproot::VerifierTaproot;
+use crate::treepp::*;
+use bitcoin::{Address, Amount, FeeRate, Network, OutPoint, Transaction, Txid, Witness};
+use bitcoincore_rpc::{Auth, Client, RpcApi};
+use std::time::{Duration, Instant};
+use std::{env, thread};
+
+/// The environment variable holding the URL of the RPC endpoint.
+pub const RPC_URL_ENV: &str = "BITCOIN_RPC_URL";
+/// The environment variable holding the path of the cookie file.
+pub const RPC_COOKIE_ENV: &str = "BITCOIN_RPC_COOKIE";
+/// The environment variable holding the RPC user, used if there is no cookie file.
+pub const RPC_USER_ENV: &str = "BITCOIN_RPC_USER";
+/// The environment variable holding the RPC password, used if there is no cookie file.
+pub const RPC_PASS_ENV: &str = "BITCOIN_RPC_PASS";
+
+const DEFAULT_REGTEST_RPC_URL: &str = "http://127.0.0.1:18443";
+
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The error of the client.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The node returns an error.
+    Rpc(bitcoincore_rpc::Error),
+    /// The funding transaction does not pay to the verifier output.
+    MissingOutput(Txid),
+    /// The amount of the output does not cover the fee of the reveal transaction.
+    InsufficientAmount,
+    /// The transaction is not confirmed before the timeout.
+    Timeout(Txid),
+    /// The verifier output is funded on a network other than regtest, while the verifier leaf
+    /// does not check the openings, see [`VERIFIER_CHECKS_OPENINGS`].
+    IncompleteVerifier(Network),
+}
+
+impl From<bitcoincore_rpc::Error> for ClientError {
+    fn from(err: bitcoincore_rpc::Error) -> Self {
+        Self::Rpc(err)
+    }
+}
+
+/// Assemble the reveal transaction, which spends the verifier output through `leaf_script` with
+/// the given hints, and pays the amount of the output, minus the fee, to `destination`.
+pub fn build_reveal(
+    outpoint: OutPoint,
+    amount: Amount,
+    taproot: &VerifierTaproot,
+    leaf_script: &Script,
+    hints: &Witness,
+    destination: Script,
+    fee_rate: FeeRate,
+) -> Result<Transaction, ClientError> {
+    taproot
+        .script_path_spend(outpoint, amount, leaf_script, hints, destination, fee_rate)
+        .ok_or(ClientError::InsufficientAmount)
+}
+
+/// A connection to a bitcoind node, with a loaded wallet.
+///
+/// The node must accept `OP_CAT` in tapscript (e.g., Bitcoin Inquisition) for the verifier to be
+/// spendable, and, since the verifier transactions are above the standard weight, it must also
+/// accept non-standard transactions. The transactions are looked up with `getrawtransaction`,
+/// which needs `-txindex` once they are confirmed.
+pub struct BitcoindClient {
+    /// The RPC client.
+    pub client: Client,
+    /// The network of the node.
+    pub network: Network,
+}
+
+impl BitcoindClient {
+    /// Connect to a node.
+    pub fn connect(url: &str, auth: Auth, network: Network) -> bitcoincore_rpc::Result<Self> {
+        Ok(Self {
+            client: Client::new(url, auth)?,
+            network,
+        })
+    }
+
+    /// Connect to a regtest node described by the environment: the URL is read from
+    /// [`RPC_URL_ENV`] (by default, the regtest port on localhost), and the credentials from
+    /// [`RPC_COOKIE_ENV`], or else from [`RPC_USER_ENV`] and [`RPC_PASS_ENV`].
+    pub fn regtest_from_env() -> bitcoincore_rpc::Result<Self> {
+        let url = env::var(RPC_URL_ENV).unwrap_or_else(|_| DEFAULT_REGTEST_RPC_URL.to_string());
+        let auth = match (
+            env::var(RPC_COOKIE_ENV),
+            env::var(RPC_USER_ENV),
+            env::var(RPC_PASS_ENV),
+        ) {
+            (Ok(cookie), _, _) => Auth::CookieFile(cookie.into()),
+            (_, Ok(user), Ok(pass)) => Auth::UserPass(user, pass),
+            _ => Auth::None,
+        };
+        Self::connect(&url, auth, Network::Regtest)
+    }
+
+    /// A new address of the wallet.
+    pub fn new_address(&self) -> bitcoincore_rpc::Result<Address> {
+        Ok(self.client.get_new_address(None, None)?.assume_checked())
+    }
+
+    /// Mine blocks paying to the wallet, which also confirms the transactions in the mempool.
+    pub fn mine_blocks(&self, n: u64) -> bitcoincore_rpc::Result<()> {
+        let address = self.new_address()?;
+        self.client.generate_to_address(n, &address)?;
+        Ok(())
+    }
+
+    /// Send `amount` from the wallet to the verifier output, and return the outpoint of the
+    /// output, which is unconfirmed.
+    ///
+    /// Only a regtest node is accepted as long as the verifier leaf does not check the openings,
+    /// see [`VERIFIER_CHECKS_OPENINGS`], since the output is then not protected by the proof.
+    pub fn fund_verifier_output(
+        &self,
+        taproot: &VerifierTaproot,
+        amount: Amount,
+    ) -> Result<OutPoint, ClientError> {
+        if !VERIFIER_CHECKS_OPENINGS && self.network != Network::Regtest {
+            return Err(ClientError::IncompleteVerifier(self.network));
+        }
+
+        let txid = self.client.send_to_address(
+            &taproot.address(self.network),
+            amount,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )?;
+
+        let tx = self.client.get_raw_transaction(&txid, None)?;
+        let script_pubkey = taproot.script_pubkey();
+        let vout = tx
+            .output
+            .iter()
+            .position(|output| output.script_pubkey == script_pubkey && output.value == amount)
+            .ok_or(ClientError::MissingOutput(txid))?;
+
+        Ok(OutPoint::new(txid, vout as u32))
+    }
+
+    /// Assemble the reveal transaction with [`build_reveal`], paying to a new address of the
+    /// wallet, and broadcast it.
+    pub fn build_and_send_reveal(
+        &self,
+        outpoint: OutPoint,
+        amount: Amount,
+        taproot: &VerifierTaproot,
+        leaf_script: &Script,
+        hints: &Witness,
+        fee_rate: FeeRate,
+    ) -> Result<Txid, ClientError> {
+        let reveal = build_reveal(
+            outpoint,
+            amount,
+            taproot,
+            leaf_script,
+            hints,
+            self.new_address()?.script_pubkey(),
+            fee_rate,
+        )?;
+        Ok(self.client.send_raw_transaction(&reveal)?)
+    }
+
+    /// Poll the node until the transaction has the given number of confirmations, and return
+    /// the number of confirmations.
+    pub fn wait_for_confirmation(
+        &self,
+        txid: &Txid,
+        confirmations: u32,
+        timeout: Duration,
+    ) -> Result<u32, ClientError> {
+        let start = Instant::now();
+        loop {
+            let info = self.client.get_raw_transaction_info(txid, None)?;
+            let current = info.confirmations.unwrap_or(0);
+            if current >= confirmations {
+                return Ok(current);
+            }
+            if start.elapsed() >= timeout {
+                return Err(ClientError::Timeout(*txid));
+            }
+            thread::sleep(CONFIRMATION_POLL_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::{prove_and_hint, FibonacciConfig};
+    use crate::pipeline::verifier_leaf_script;
+    use crate::rpc::{build_reveal, BitcoindClient, ClientError};
+    use crate::taproot::VerifierTaprootBuilder;
+    use crate::treepp::*;
+    use crate::witness::hints_to_witness;
+    use bitcoin::hashes::Hash;
+    use bitcoin::{Amount, FeeRate, OutPoint, Txid};
+    use std::time::Duration;
+
+    #[test]
+    fn test_build_reveal() {
+        let leaf_script = script! { OP_DROP OP_TRUE };
+        let taproot = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(leaf_script.clone())
+            .finalize();
+        let hints = hints_to_witness(script! { 1 }).unwrap();
+        let outpoint = OutPoint::new(Txid::all_zeros(), 0);
+
+        let reveal = build_reveal(
+            outpoint,
+            Amount::from_sat(10_000),
+            &taproot,
+            &leaf_script,
+            &hints,
+            script! { OP_TRUE },
+            FeeRate::from_sat_per_vb(2).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(reveal.input[0].previous_output, outpoint);
+        assert_eq!(
+            reveal.output[0].value,
+            Amount::from_sat(10_000 - 2 * reveal.vsize() as u64)
+        );
+
+        // the fee cannot exceed the amount
+        assert!(matches!(
+            build_reveal(
+                outpoint,
+                Amount::from_sat(100),
+                &taproot,
+                &leaf_script,
+                &hints,
+                script! { OP_TRUE },
+                FeeRate::from_sat_per_vb(2).unwrap(),
+            ),
+            Err(ClientError::InsufficientAmount)
+        ));
+    }
+
+    #[test]
+    #[ignore = "requires a regtest node, see BitcoindClient::regtest_from_env"]
+    fn test_regtest_verifier() {
+        let node = BitcoindClient::regtest_from_env().unwrap();
+        // make the coinbase outputs spendable
+        node.mine_blocks(101).unwrap();
+
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let (_, hints) = prove_and_hint(&config).unwrap();
+
+        let leaf_script = verifier_leaf_script(&config.initial_channel(), &config);
+        let taproot = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(leaf_script.clone())
+            .finalize();
+
+        let amount = Amount::from_sat(1_000_000);
+        let outpoint = node.fund_verifier_output(&taproot, amount).unwrap();
+        node.mine_blocks(1).unwrap();
+
+        let reveal_txid = node
+            .build_and_send_reveal(
+                outpoint,
+                amount,
+                &taproot,
+                &leaf_script,
+                &hints_to_witness(script! { { hints } }).unwrap(),
+                FeeRate::BROADCAST_MIN,
+            )
+            .unwrap();
+        node.mine_blocks(1).unwrap();
+
+        assert!(
+            node.wait_for_confirmation(&reveal_txid, 1, Duration::from_secs(10))
+                .unwrap()
+                >= 1
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use crate::simulator::{concat_gadgets, simulate};
+use crate::treepp::*;
+use bitcoin::opcodes::all::{OP_IF, OP_NOTIF};
+use bitcoin::script::Instruction;
+use std::collections::BTreeMap;
+
+/// The coverage of the branches of a gadget.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GadgetCoverage {
+    /// The name of the gadget.
+    pub name: String,
+    /// The number of branches, which is twice the number of `OP_IF` and `OP_NOTIF`.
+    pub n_branches: usize,
+    /// The number of branches that are taken at least once.
+    pub n_covered: usize,
+    /// The branches that are never taken, as the index of the conditional within the gadget,
+    /// and whether it is the branch that runs when the conditional succeeds (the `OP_IF` part
+    /// of an `OP_IF`, or the `OP_ELSE` part of an `OP_NOTIF`, is run when the element is true).
+    pub uncovered: Vec<(usize, bool)>,
+}
+
+/// The branches taken by the conditionals of gadgets, accumulated over several executions.
+///
+/// A conditional is identified by the name of its gadget and its index within the gadget, so
+/// that the executions can push different inputs before the gadget.
+#[derive(Clone, Debug, Default)]
+pub struct BranchCoverage {
+    // the number of times the element is true and false, for each conditional
+    branches: BTreeMap<(String, usize), [usize; 2]>,
+}
+
+impl BranchCoverage {
+    /// Start with no execution.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Execute the concatenation of the gadgets with the witness, record the branches taken,
+    /// and return whether the execution succeeds.
+    ///
+    /// A conditional inside a branch that is not run is not evaluated, and is not recorded.
+    pub fn run<T: ToString>(&mut self, gadgets: Vec<(T, Script)>, witness: Vec<Vec<u8>>) -> bool {
+        let (script, ranges) = concat_gadgets(gadgets);
+
+        // the gadget of each instruction, and its index within the gadget
+        let mut locations = vec![];
+        for range in ranges.iter() {
+            for i in range.start..range.end {
+                locations.push((range.name.clone(), i - range.start));
+            }
+        }
+
+        // record all the conditionals, including those that are never reached
+        for (instruction, location) in script.instructions().zip(locations.iter()) {
+            if matches!(instruction, Ok(Instruction::Op(OP_IF | OP_NOTIF))) {
+                self.branches.entry(location.clone()).or_insert([0, 0]);
+            }
+        }
+
+        let mut prev_len = witness.len();
+        let mut prev_top = witness.last().cloned();
+        simulate(script, witness, |step| {
+            let len = step.exec.stack().len();
+
+            // an evaluated conditional pops the element it checks
+            if matches!(step.opcode, Some(OP_IF | OP_NOTIF)) && len + 1 == prev_len {
+                let value = prev_top.as_ref().is_some_and(|v| !v.is_empty());
+                let counts = self
+                    .branches
+                    .get_mut(&locations[step.index])
+                    .expect("all the conditionals are recorded");
+                counts[if value { 0 } else { 1 }] += 1;
+            }
+
+            prev_len = len;
+            prev_top = step.top.clone();
+        })
+    }
+
+    /// The coverage of each gadget with conditionals, in the order of the names.
+    pub fn report(&self) -> Vec<GadgetCoverage> {
+        let mut report: Vec<GadgetCoverage> = vec![];
+        for ((name, index), counts) in self.branches.iter() {
+            if report.last().map(|gadget| &gadget.name) != Some(name) {
+                report.push(GadgetCoverage {
+                    name: name.clone(),
+                    n_branches: 0,
+                    n_covered: 0,
+                    uncovered: vec![],
+                });
+            }
+            let gadget = report.last_mut().unwrap();
+            for (count, value) in counts.iter().zip([true, false]) {
+                gadget.n_branches += 1;
+                if *count > 0 {
+                    gadget.n_covered += 1;
+                } else {
+                    gadget.uncovered.push((*index, value));
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simulator::{BranchCoverage, GadgetCoverage};
+    use crate::treepp::*;
+
+    #[test]
+    fn test_branch_coverage() {
+        let gadget = script! {
+            OP_IF
+                OP_IF 1 OP_ELSE 2 OP_ENDIF
+            OP_ELSE
+                OP_DROP 3
+            OP_ENDIF
+        };
+        let check = script! { OP_DROP OP_TRUE };
+
+        let mut coverage = BranchCoverage::new();
+        assert!(coverage.run(
+            vec![
+                ("input", script! { 1 1 }),
+                ("gadget", gadget.clone()),
+                ("check", check.clone())
+            ],
+            vec![]
+        ));
+        assert_eq!(
+            coverage.report(),
+            vec![GadgetCoverage {
+                name: "gadget".to_string(),
+                n_branches: 4,
+                n_covered: 2,
+                uncovered: vec![(0, false), (1, false)],
+            }]
+        );
+
+        // the inner conditional is skipped, so it is not evaluated
+        assert!(coverage.run(
+            vec![
+                ("input", script! { 5 0 }),
+                ("gadget", gadget.clone()),
+                ("check", check.clone())
+            ],
+            vec![]
+        ));
+        assert_eq!(coverage.report()[0].uncovered, vec![(1, false)]);
+
+        // the inputs can also come from the witness
+        assert!(coverage.run(
+            vec![("gadget", gadget), ("check", check)],
+            vec![vec![], vec![1]]
+        ));
+        let report = coverage.report();
+        assert_eq!(report[0].n_covered, 4);
+        assert!(report[0].uncovered.is_empty());
+    }
+}
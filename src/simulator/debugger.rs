@@ -0,0 +1,228 @@
+use crate::simulator::new_exec;
+use crate::treepp::*;
+use bitcoin::opcodes::all::OP_TOALTSTACK;
+use bitcoin_scriptexec::Exec;
+use std::collections::BTreeSet;
+
+/// A gadget of the script under debugging.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GadgetRange {
+    /// The name of the gadget.
+    pub name: String,
+    /// The index of the first instruction of the gadget.
+    pub start: usize,
+    /// The index right after the last instruction of the gadget.
+    pub end: usize,
+}
+
+/// Concatenate the gadgets into a script, and return the instruction range of each gadget.
+pub fn concat_gadgets<T: ToString>(gadgets: Vec<(T, Script)>) -> (Script, Vec<GadgetRange>) {
+    let mut ranges = Vec::with_capacity(gadgets.len());
+    let mut bytes = vec![];
+    let mut start = 0;
+    for (name, gadget) in gadgets.into_iter() {
+        let end = start + gadget.instructions().count();
+        ranges.push(GadgetRange {
+            name: name.to_string(),
+            start,
+            end,
+        });
+        bytes.extend_from_slice(gadget.as_bytes());
+        start = end;
+    }
+    (Script::from_bytes(bytes), ranges)
+}
+
+/// A step debugger over the executor, with breakpoints at instruction indices or at the start
+/// of gadgets.
+///
+/// The debugger stops before executing the instruction at a breakpoint, and the stacks can
+/// then be inspected and modified before resuming.
+pub struct Debugger {
+    exec: Exec,
+    index: usize,
+    started: bool,
+    gadgets: Vec<GadgetRange>,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    /// Debug a script with the witness.
+    pub fn new(script: Script, witness: Vec<Vec<u8>>) -> Self {
+        Self::with_gadgets(vec![("script", script)], witness)
+    }
+
+    /// Debug the concatenation of the gadgets with the witness, so that breakpoints can refer
+    /// to the gadgets by name.
+    pub fn with_gadgets<T: ToString>(gadgets: Vec<(T, Script)>, witness: Vec<Vec<u8>>) -> Self {
+        let (script, gadgets) = concat_gadgets(gadgets);
+
+        Self {
+            exec: new_exec(script, witness),
+            index: 0,
+            started: false,
+            gadgets,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    /// Stop before executing the instruction at the given index.
+    pub fn break_at(&mut self, index: usize) {
+        self.breakpoints.insert(index);
+    }
+
+    /// Stop before executing the first instruction of the gadget, and return whether there is
+    /// such a gadget.
+    pub fn break_at_gadget(&mut self, name: &str) -> bool {
+        match self.gadgets.iter().find(|gadget| gadget.name == name) {
+            Some(gadget) => {
+                self.breakpoints.insert(gadget.start);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all the breakpoints.
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// The index of the next instruction.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The gadget of the next instruction, if any.
+    pub fn current_gadget(&self) -> Option<&GadgetRange> {
+        self.gadgets
+            .iter()
+            .find(|gadget| gadget.start <= self.index && self.index < gadget.end)
+    }
+
+    /// Execute the next instruction, and return whether the execution can continue.
+    pub fn step(&mut self) -> bool {
+        self.started = true;
+        if self.exec.exec_next().is_err() {
+            return false;
+        }
+        self.index += 1;
+        true
+    }
+
+    /// Execute until the next breakpoint, and return its index, or `None` if the execution
+    /// ends first.
+    pub fn resume(&mut self) -> Option<usize> {
+        // before the execution starts, a breakpoint at the first instruction stops it right away
+        if !self.started && self.breakpoints.contains(&self.index) {
+            self.started = true;
+            return Some(self.index);
+        }
+
+        // otherwise, a breakpoint at the current instruction is the one that stopped the execution
+        if !self.step() {
+            return None;
+        }
+        while !self.breakpoints.contains(&self.index) {
+            if !self.step() {
+                return None;
+            }
+        }
+        Some(self.index)
+    }
+
+    /// Whether the execution succeeds, or `None` if it has not ended.
+    pub fn result(&self) -> Option<bool> {
+        self.exec.result().map(|res| res.success)
+    }
+
+    /// The main stack, from the bottom to the top.
+    pub fn stack(&self) -> Vec<Vec<u8>> {
+        self.exec.stack().as_v8_vec()
+    }
+
+    /// The alt stack, from the bottom to the top.
+    pub fn altstack(&self) -> Vec<Vec<u8>> {
+        self.exec.altstack().as_v8_vec()
+    }
+
+    /// Replace the stacks, given from the bottom to the top, and keep executing the rest of the
+    /// script from the current instruction.
+    ///
+    /// The executor is restarted on the rest of the script, so the stacks must not be replaced
+    /// inside a conditional branch.
+    pub fn set_stacks(&mut self, stack: Vec<Vec<u8>>, altstack: Vec<Vec<u8>>) {
+        // the alt stack is passed on the top of the main stack, and moved back in place
+        let n_alt = altstack.len();
+        let mut witness = stack;
+        witness.extend(altstack.into_iter().rev());
+
+        let mut bytes = vec![OP_TOALTSTACK.to_u8(); n_alt];
+        bytes.extend_from_slice(self.exec.remaining_script().as_bytes());
+
+        self.exec = new_exec(Script::from_bytes(bytes), witness);
+        for _ in 0..n_alt {
+            self.exec
+                .exec_next()
+                .expect("moving the alt stack back should succeed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simulator::Debugger;
+    use crate::treepp::*;
+
+    #[test]
+    fn test_debugger() {
+        let mut debugger = Debugger::with_gadgets(
+            vec![
+                ("push", script! { 2 3 OP_TOALTSTACK }),
+                ("add", script! { OP_FROMALTSTACK OP_ADD }),
+                ("check", script! { 5 OP_EQUAL }),
+            ],
+            vec![],
+        );
+
+        assert!(debugger.break_at_gadget("add"));
+        assert!(!debugger.break_at_gadget("mul"));
+        debugger.break_at(5);
+
+        assert_eq!(debugger.resume(), Some(3));
+        assert_eq!(debugger.current_gadget().unwrap().name, "add");
+        assert_eq!(debugger.stack(), vec![vec![2]]);
+        assert_eq!(debugger.altstack(), vec![vec![3]]);
+
+        // change the operands from (2, 3) to (1, 4), which keeps the sum
+        debugger.set_stacks(vec![vec![1]], vec![vec![4]]);
+        assert_eq!(debugger.index(), 3);
+        assert_eq!(debugger.altstack(), vec![vec![4]]);
+
+        assert_eq!(debugger.resume(), Some(5));
+        assert_eq!(debugger.current_gadget().unwrap().name, "check");
+        assert_eq!(debugger.stack(), vec![vec![5]]);
+
+        // a wrong sum fails the check
+        debugger.set_stacks(vec![vec![6]], vec![]);
+        assert_eq!(debugger.resume(), None);
+        assert_eq!(debugger.result(), Some(false));
+
+        // a breakpoint at the first gadget stops before any instruction is executed
+        let mut debugger = Debugger::with_gadgets(
+            vec![
+                ("push", script! { 2 3 OP_TOALTSTACK }),
+                ("add", script! { OP_FROMALTSTACK OP_ADD }),
+            ],
+            vec![],
+        );
+        assert!(debugger.break_at_gadget("push"));
+        debugger.break_at(1);
+
+        assert_eq!(debugger.resume(), Some(0));
+        assert!(debugger.stack().is_empty());
+        assert_eq!(debugger.resume(), Some(1));
+        assert_eq!(debugger.stack(), vec![vec![2]]);
+        assert_eq!(debugger.resume(), None);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::treepp::*;
+use bitcoin::hashes::Hash;
+use bitcoin::opcodes::Opcode;
+use bitcoin::script::Instruction;
+use bitcoin::taproot::TapLeafHash;
+use bitcoin::transaction::Version;
+use bitcoin::{absolute::LockTime, Transaction};
+use bitcoin_scriptexec::{Exec, ExecCtx, Options, TxTemplate};
+
+mod coverage;
+pub use coverage::*;
+
+mod debugger;
+pub use debugger::*;
+
+/// The limit on the number of elements in the main and alt stacks combined.
+pub const MAX_STACK_SIZE: usize = 1000;
+
+/// The state of the execution right after an instruction.
+pub struct SimulationStep<'a> {
+    /// The index of the instruction in the script.
+    pub index: usize,
+    /// The opcode, or `None` for a push.
+    pub opcode: Option<Opcode>,
+    /// The element on the top of the main stack, if any.
+    pub top: Option<Vec<u8>>,
+    /// The executor, which gives access to the rest of the state.
+    pub exec: &'a Exec,
+}
+
+// An executor of a tapscript with the witness, which does not enforce the stack limit.
+fn new_exec(script: Script, witness: Vec<Vec<u8>>) -> Exec {
+    Exec::new(
+        ExecCtx::Tapscript,
+        Options {
+            enforce_stack_limit: false,
+            ..Default::default()
+        },
+        TxTemplate {
+            tx: Transaction {
+                version: Version::TWO,
+                lock_time: LockTime::ZERO,
+                input: vec![],
+                output: vec![],
+            },
+            prevouts: vec![],
+            input_idx: 0,
+            taproot_annex_scriptleaf: Some((TapLeafHash::all_zeros(), None)),
+        },
+        script,
+        witness,
+    )
+    .expect("the executor should be created")
+}
+
+/// Execute a tapscript with the witness one instruction at a time, calling `on_step` after
+/// each instruction, and return whether the execution succeeds.
+///
+/// The stack limit is not enforced, so that an execution that exceeds it can still be observed.
+pub fn simulate<F: FnMut(&SimulationStep)>(
+    script: Script,
+    witness: Vec<Vec<u8>>,
+    mut on_step: F,
+) -> bool {
+    let mut exec = new_exec(script, witness);
+
+    let mut index = 0;
+    loop {
+        let opcode = match exec.remaining_script().instructions().next() {
+            Some(Ok(Instruction::Op(opcode))) => Some(opcode),
+            _ => None,
+        };
+        if exec.exec_next().is_err() {
+            break;
+        }
+
+        let top = exec.stack().last().ok();
+        on_step(&SimulationStep {
+            index,
+            opcode,
+            top,
+            exec: &exec,
+        });
+        index += 1;
+    }
+
+    exec.result().map(|res| res.success).unwrap_or(false)
+}
+
+/// The profile of the stack size over a whole execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StackProfile {
+    /// Whether the execution succeeds, with the stack limit not enforced.
+    pub success: bool,
+    /// The number of executed instructions.
+    pub n_steps: usize,
+    /// The maximal number of elements in the main and alt stacks combined.
+    pub max_stack_size: usize,
+    /// The index of the first instruction after which the maximum is reached.
+    pub max_stack_size_index: usize,
+    /// The range of instructions, from the first one to the last one, after which the stacks
+    /// hold more than [`MAX_STACK_SIZE`] elements, if any.
+    pub over_limit: Option<(usize, usize)>,
+}
+
+/// Track the number of elements in the main and alt stacks combined after every instruction.
+///
+/// The depths on entry and exit do not show a script that transiently exceeds the limit, which
+/// this profile does.
+pub fn profile_stack(script: Script, witness: Vec<Vec<u8>>) -> StackProfile {
+    let mut n_steps = 0;
+    let mut max_stack_size = 0;
+    let mut max_stack_size_index = 0;
+    let mut over_limit: Option<(usize, usize)> = None;
+
+    let success = simulate(script, witness, |step| {
+        n_steps += 1;
+
+        let stack_size = step.exec.stack().len() + step.exec.altstack().len();
+        if stack_size > max_stack_size {
+            max_stack_size = stack_size;
+            max_stack_size_index = step.index;
+        }
+        if stack_size > MAX_STACK_SIZE {
+            over_limit = Some(match over_limit {
+                Some((first, _)) => (first, step.index),
+                None => (step.index, step.index),
+            });
+        }
+    });
+
+    StackProfile {
+        success,
+        n_steps,
+        max_stack_size,
+        max_stack_size_index,
+        over_limit,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::{prove_and_hint, FibonacciConfig};
+    use crate::pipeline::verifier_leaf_script;
+    use crate::simulator::{profile_stack, simulate, MAX_STACK_SIZE};
+    use crate::treepp::*;
+    use crate::witness::hints_to_witness;
+    use bitcoin::opcodes::all::OP_ADD;
+
+    #[test]
+    fn test_simulate() {
+        let mut steps = vec![];
+        let success = simulate(script! { 2 OP_ADD 5 OP_EQUAL }, vec![vec![3]], |step| {
+            steps.push((step.index, step.opcode, step.top.clone()));
+        });
+        assert!(success);
+
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[1], (1, Some(OP_ADD), Some(vec![5])));
+        assert_eq!(steps[3].2, Some(vec![1]));
+    }
+
+    #[test]
+    fn test_profile_stack() {
+        // the stacks only exceed the limit in the middle of the execution
+        let script = script! {
+            for _ in 0..600 {
+                OP_1
+            }
+            for _ in 0..500 {
+                OP_1 OP_TOALTSTACK
+            }
+            for _ in 0..500 {
+                OP_FROMALTSTACK OP_DROP
+            }
+            for _ in 0..599 {
+                OP_DROP
+            }
+        };
+
+        let profile = profile_stack(script, vec![]);
+        assert!(profile.success);
+        assert_eq!(profile.max_stack_size, 1100);
+        assert_eq!(profile.max_stack_size_index, 600 + 2 * 499);
+        // from the push of the 1001st element until the 100th element is popped from the alt
+        // stack, right before it is dropped
+        assert_eq!(profile.over_limit, Some((600 + 2 * 400, 1600 + 2 * 99)));
+    }
+
+    #[test]
+    fn test_profile_verifier() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let (_, hints) = prove_and_hint(&config).unwrap();
+
+        let profile = profile_stack(
+            verifier_leaf_script(&config.initial_channel(), &config),
+            hints_to_witness(script! { { hints } }).unwrap().to_vec(),
+        );
+        assert!(profile.success);
+        assert!(profile.max_stack_size <= MAX_STACK_SIZE);
+        assert!(profile.over_limit.is_none());
+    }
+}
@@ -0,0 +1,426 @@
+use crate::treepp::*;
+use bitcoin::absolute::LockTime;
+use bitcoin::key::XOnlyPublicKey;
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::taproot::{ControlBlock, LeafVersion, NodeInfo, TaprootSpendInfo};
+use bitcoin::transaction::Version;
+use bitcoin::{
+    Address, Amount, FeeRate, Network, OutPoint, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// The x-only "nothing up my sleeve" point H from BIP-341, which has no known discrete logarithm.
+///
+/// Using it as the internal key disables the key path, so the output can only be spent through
+/// the script paths.
+pub const NUMS_INTERNAL_KEY: [u8; 32] = [
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+];
+
+/// The leaf script of the refund path: after `delay` blocks since the output is confirmed, the
+/// funds can be spent with a signature of `refund_key`.
+pub fn refund_leaf_script(refund_key: &XOnlyPublicKey, delay: u16) -> Script {
+    script! {
+        { Sequence::from_height(delay).to_consensus_u32() }
+        OP_CSV
+        OP_DROP
+        { refund_key.serialize().to_vec() }
+        OP_CHECKSIG
+    }
+}
+
+/// The leaf script of the committee path: the funds can be spent with signatures of at least
+/// `threshold` of the `keys`, checked with `OP_CHECKSIGADD`.
+///
+/// The witness provides one item per key, in the reverse order of the keys, which is either a
+/// signature or an empty item for a key that does not sign.
+pub fn committee_leaf_script(keys: &[XOnlyPublicKey], threshold: usize) -> Script {
+    assert!(threshold >= 1 && threshold <= keys.len());
+
+    script! {
+        { keys[0].serialize().to_vec() }
+        OP_CHECKSIG
+        for key in keys.iter().skip(1) {
+            { key.serialize().to_vec() }
+            OP_CHECKSIGADD
+        }
+        { threshold }
+        OP_NUMEQUAL
+    }
+}
+
+/// Builder for the taproot output that locks funds to the verifier.
+pub struct VerifierTaprootBuilder {
+    internal_key: XOnlyPublicKey,
+    verifier_leaves: Vec<(Script, LeafVersion)>,
+    refund: Option<(XOnlyPublicKey, u16)>,
+    committee: Option<(Vec<XOnlyPublicKey>, usize)>,
+}
+
+impl Default for VerifierTaprootBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VerifierTaprootBuilder {
+    /// Start a taproot output with the NUMS internal key and no leaves.
+    pub fn new() -> Self {
+        Self {
+            internal_key: XOnlyPublicKey::from_slice(&NUMS_INTERNAL_KEY).unwrap(),
+            verifier_leaves: vec![],
+            refund: None,
+            committee: None,
+        }
+    }
+
+    /// Use a different internal key, which enables the key path.
+    pub fn internal_key(mut self, internal_key: XOnlyPublicKey) -> Self {
+        self.internal_key = internal_key;
+        self
+    }
+
+    /// Add a verifier leaf, which is a tapscript leaf.
+    pub fn add_verifier_leaf(self, script: Script) -> Self {
+        self.add_verifier_leaf_with_version(script, LeafVersion::TapScript)
+    }
+
+    /// Add a verifier leaf with the given leaf version.
+    pub fn add_verifier_leaf_with_version(
+        mut self,
+        script: Script,
+        leaf_version: LeafVersion,
+    ) -> Self {
+        self.verifier_leaves.push((script, leaf_version));
+        self
+    }
+
+    /// Add a refund leaf next to the verifier leaves, so that the funds are not locked forever if
+    /// no valid proof is ever provided, see [`refund_leaf_script`].
+    pub fn refund(mut self, refund_key: XOnlyPublicKey, delay: u16) -> Self {
+        self.refund = Some((refund_key, delay));
+        self
+    }
+
+    /// Add a committee leaf next to the verifier leaves, which lets `threshold` of the `keys`
+    /// override the verifier, see [`committee_leaf_script`].
+    pub fn committee(mut self, keys: Vec<XOnlyPublicKey>, threshold: usize) -> Self {
+        assert!(threshold >= 1 && threshold <= keys.len());
+        self.committee = Some((keys, threshold));
+        self
+    }
+
+    /// Assemble the taptree, in which all the leaves are placed at (almost) the same depth.
+    ///
+    /// The tree is the one of [`bitcoin::taproot::TaprootBuilder::with_huffman_tree`] with equal
+    /// weights, which only supports tapscript leaves, so it is built here with the leaf version
+    /// of each leaf.
+    pub fn finalize(self) -> VerifierTaproot {
+        assert!(!self.verifier_leaves.is_empty());
+
+        let secp = Secp256k1::verification_only();
+
+        let refund_leaf = self
+            .refund
+            .map(|(refund_key, delay)| refund_leaf_script(&refund_key, delay));
+        let committee_leaf = self
+            .committee
+            .map(|(keys, threshold)| committee_leaf_script(&keys, threshold));
+
+        let mut nodes = self
+            .verifier_leaves
+            .iter()
+            .cloned()
+            .chain(
+                refund_leaf
+                    .iter()
+                    .chain(committee_leaf.iter())
+                    .map(|script| (script.clone(), LeafVersion::TapScript)),
+            )
+            .map(|(script, leaf_version)| {
+                (
+                    Reverse(1u32),
+                    NodeInfo::new_leaf_with_ver(script, leaf_version),
+                )
+            })
+            .collect::<BinaryHeap<_>>();
+        while nodes.len() > 1 {
+            let (weight1, node1) = nodes.pop().unwrap();
+            let (weight2, node2) = nodes.pop().unwrap();
+            nodes.push((
+                Reverse(weight1.0 + weight2.0),
+                NodeInfo::combine(node1, node2)
+                    .unwrap_or_else(|_| panic!("the taptree should not be too deep")),
+            ));
+        }
+        let (_, root) = nodes.pop().unwrap();
+        let spend_info = TaprootSpendInfo::from_node_info(&secp, self.internal_key, root);
+
+        VerifierTaproot {
+            internal_key: self.internal_key,
+            verifier_leaves: self.verifier_leaves,
+            refund_leaf,
+            committee_leaf,
+            spend_info,
+        }
+    }
+}
+
+/// A taproot output locking funds to the verifier.
+pub struct VerifierTaproot {
+    /// The internal key.
+    pub internal_key: XOnlyPublicKey,
+    /// The verifier leaves, with their leaf versions.
+    pub verifier_leaves: Vec<(Script, LeafVersion)>,
+    /// The refund leaf, if any.
+    ///
+    /// It is spent with [`VerifierTaproot::script_path_witness`], where the only hint is the
+    /// signature, and the spending input must have a relative timelock of at least the delay.
+    pub refund_leaf: Option<Script>,
+    /// The committee leaf, if any.
+    ///
+    /// It is a tapscript leaf, as it needs `OP_CHECKSIGADD`, and so is the refund leaf.
+    pub committee_leaf: Option<Script>,
+    /// The taproot spending information.
+    pub spend_info: TaprootSpendInfo,
+}
+
+impl VerifierTaproot {
+    /// The scriptPubKey of the output.
+    pub fn script_pubkey(&self) -> Script {
+        Script::new_p2tr_tweaked(self.spend_info.output_key())
+    }
+
+    /// The address of the output on the given network.
+    pub fn address(&self, network: Network) -> Address {
+        Address::p2tr_tweaked(self.spend_info.output_key(), network)
+    }
+
+    /// All the leaves with their leaf version: the verifier leaves, followed by the refund leaf
+    /// and the committee leaf if any.
+    pub fn leaves(&self) -> Vec<(Script, LeafVersion)> {
+        self.verifier_leaves
+            .iter()
+            .cloned()
+            .chain(
+                self.refund_leaf
+                    .iter()
+                    .chain(self.committee_leaf.iter())
+                    .map(|script| (script.clone(), LeafVersion::TapScript)),
+            )
+            .collect()
+    }
+
+    /// The leaf version of the given leaf, or `None` if the script is not a leaf of the taptree.
+    pub fn leaf_version(&self, script: &Script) -> Option<LeafVersion> {
+        self.leaves()
+            .into_iter()
+            .find(|(leaf, _)| leaf == script)
+            .map(|(_, leaf_version)| leaf_version)
+    }
+
+    /// The control block for spending through the given leaf.
+    pub fn control_block(&self, script: &Script) -> ControlBlock {
+        self.leaf_version(script)
+            .and_then(|leaf_version| {
+                self.spend_info
+                    .control_block(&(script.clone(), leaf_version))
+            })
+            .expect("the script should be a leaf of the taptree")
+    }
+
+    /// Assemble the witness of a script-path spend: the hints, followed by the leaf script and
+    /// its control block.
+    pub fn script_path_witness(&self, hints: Vec<Vec<u8>>, script: &Script) -> Vec<Vec<u8>> {
+        let mut witness = hints;
+        witness.push(script.to_bytes());
+        witness.push(self.control_block(script).serialize());
+        witness
+    }
+
+    /// Assemble a transaction that spends the output at `outpoint` through `leaf_script` with the
+    /// given hints, and pays its amount, minus the fee, to `destination`.
+    ///
+    /// Return `None` if the amount does not cover the fee.
+    pub fn script_path_spend(
+        &self,
+        outpoint: OutPoint,
+        amount: Amount,
+        leaf_script: &Script,
+        hints: &Witness,
+        destination: Script,
+        fee_rate: FeeRate,
+    ) -> Option<Transaction> {
+        let mut tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: outpoint,
+                script_sig: Script::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::from_slice(
+                    &self.script_path_witness(hints.to_vec(), leaf_script),
+                ),
+            }],
+            output: vec![TxOut {
+                value: amount,
+                script_pubkey: destination,
+            }],
+        };
+
+        // the value of the output does not change the size of the transaction
+        tx.output[0].value = fee_rate
+            .fee_vb(tx.vsize() as u64)
+            .and_then(|fee| amount.checked_sub(fee))?;
+        Some(tx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::taproot::VerifierTaprootBuilder;
+    use crate::treepp::*;
+    use bitcoin::key::Keypair;
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use bitcoin::taproot::{LeafVersion, TaprootBuilder};
+
+    #[test]
+    fn test_control_blocks() {
+        let leaves = vec![
+            script! { OP_1 },
+            script! { OP_2 OP_DROP OP_TRUE },
+            script! { OP_3 OP_DROP OP_TRUE },
+        ];
+
+        let taproot = leaves
+            .iter()
+            .fold(VerifierTaprootBuilder::new(), |builder, leaf| {
+                builder.add_verifier_leaf(leaf.clone())
+            })
+            .finalize();
+
+        let secp = Secp256k1::verification_only();
+        let output_key = taproot.spend_info.output_key().to_inner();
+
+        for leaf in leaves.iter() {
+            let control_block = taproot.control_block(leaf);
+            assert!(control_block.verify_taproot_commitment(&secp, output_key, leaf));
+            assert_eq!(control_block.leaf_version, LeafVersion::TapScript);
+        }
+    }
+
+    #[test]
+    fn test_refund_leaf() {
+        let secp = Secp256k1::new();
+        let refund_key = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[1; 32]).unwrap())
+            .x_only_public_key()
+            .0;
+
+        let verifier_leaf = script! { OP_1 };
+        let taproot = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(verifier_leaf.clone())
+            .refund(refund_key, 144)
+            .finalize();
+
+        let refund_leaf = taproot.refund_leaf.clone().unwrap();
+        let expected = script! {
+            144 OP_CSV OP_DROP { refund_key.serialize().to_vec() } OP_CHECKSIG
+        };
+        assert_eq!(refund_leaf, expected);
+
+        // both paths are committed in the output key
+        let output_key = taproot.spend_info.output_key().to_inner();
+        for leaf in [verifier_leaf, refund_leaf].iter() {
+            assert!(taproot
+                .control_block(leaf)
+                .verify_taproot_commitment(&secp, output_key, leaf));
+        }
+
+        // without a refund, the output is different
+        let no_refund = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(script! { OP_1 })
+            .finalize();
+        assert!(no_refund.refund_leaf.is_none());
+        assert_ne!(no_refund.script_pubkey(), taproot.script_pubkey());
+    }
+
+    #[test]
+    fn test_committee_leaf() {
+        let secp = Secp256k1::new();
+        let keys = (1..=3u8)
+            .map(|i| {
+                Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[i; 32]).unwrap())
+                    .x_only_public_key()
+                    .0
+            })
+            .collect::<Vec<_>>();
+
+        let taproot = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(script! { OP_1 })
+            .committee(keys.clone(), 2)
+            .finalize();
+
+        let committee_leaf = taproot.committee_leaf.clone().unwrap();
+        let expected = script! {
+            { keys[0].serialize().to_vec() } OP_CHECKSIG
+            { keys[1].serialize().to_vec() } OP_CHECKSIGADD
+            { keys[2].serialize().to_vec() } OP_CHECKSIGADD
+            2 OP_NUMEQUAL
+        };
+        assert_eq!(committee_leaf, expected);
+
+        let output_key = taproot.spend_info.output_key().to_inner();
+        let leaves = taproot.leaves();
+        assert_eq!(leaves.len(), 2);
+        for (leaf, leaf_version) in leaves.iter() {
+            let control_block = taproot.control_block(leaf);
+            assert!(control_block.verify_taproot_commitment(&secp, output_key, leaf));
+            assert_eq!(control_block.leaf_version, *leaf_version);
+        }
+    }
+
+    #[test]
+    fn test_leaf_versions() {
+        let secp = Secp256k1::verification_only();
+
+        let future_version = LeafVersion::from_consensus(0xc2).unwrap();
+        let taproot = VerifierTaprootBuilder::new()
+            .add_verifier_leaf(script! { OP_1 })
+            .add_verifier_leaf_with_version(script! { OP_2 }, future_version)
+            .finalize();
+
+        assert_eq!(
+            taproot.leaf_version(&script! { OP_1 }),
+            Some(LeafVersion::TapScript)
+        );
+        assert_eq!(
+            taproot.leaf_version(&script! { OP_2 }),
+            Some(future_version)
+        );
+        assert_eq!(taproot.leaf_version(&script! { OP_3 }), None);
+
+        let output_key = taproot.spend_info.output_key().to_inner();
+        for (leaf, leaf_version) in taproot.leaves().iter() {
+            let control_block = taproot.control_block(leaf);
+            assert!(control_block.verify_taproot_commitment(&secp, output_key, leaf));
+            assert_eq!(control_block.leaf_version, *leaf_version);
+        }
+
+        // with tapscript leaves only, the taptree is the one of the Huffman builder
+        let leaves = vec![script! { OP_1 }, script! { OP_2 }, script! { OP_3 }];
+        let taproot = leaves
+            .iter()
+            .fold(VerifierTaprootBuilder::new(), |builder, leaf| {
+                builder.add_verifier_leaf(leaf.clone())
+            })
+            .finalize();
+        let expected =
+            TaprootBuilder::with_huffman_tree(leaves.iter().map(|leaf| (1, leaf.clone())))
+                .unwrap()
+                .finalize(&secp, taproot.internal_key)
+                .unwrap();
+        assert_eq!(taproot.spend_info.output_key(), expected.output_key());
+    }
+}
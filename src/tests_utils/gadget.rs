@@ -0,0 +1,89 @@
+//! This module contains a harness for testing gadgets.
+use crate::circle::CirclePointGadget;
+use crate::treepp::*;
+use rust_bitcoin_m31::qm31_equalverify;
+use stwo_prover::core::circle::CirclePoint;
+use stwo_prover::core::fields::m31::M31;
+use stwo_prover::core::fields::qm31::QM31;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// A value that a gadget takes from or leaves on the stack.
+pub(crate) trait StackValue {
+    /// Push the value.
+    fn push(&self) -> Script;
+
+    /// Fail the execution if the value on the top of the stack is not this one, and drop it.
+    fn equalverify(&self) -> Script;
+}
+
+impl StackValue for M31 {
+    fn push(&self) -> Script {
+        script! { { *self } }
+    }
+
+    fn equalverify(&self) -> Script {
+        script! { { *self } OP_EQUALVERIFY }
+    }
+}
+
+impl StackValue for QM31 {
+    fn push(&self) -> Script {
+        script! { { *self } }
+    }
+
+    fn equalverify(&self) -> Script {
+        script! { { *self } qm31_equalverify }
+    }
+}
+
+impl StackValue for CirclePoint<QM31> {
+    fn push(&self) -> Script {
+        script! { { *self } }
+    }
+
+    fn equalverify(&self) -> Script {
+        script! { { *self } { CirclePointGadget::equalverify() } }
+    }
+}
+
+impl StackValue for BWSSha256Hash {
+    fn push(&self) -> Script {
+        script! { { *self } }
+    }
+
+    fn equalverify(&self) -> Script {
+        script! { { *self } OP_EQUALVERIFY }
+    }
+}
+
+/// Run a gadget on the inputs, and assert that it leaves exactly the expected values on the
+/// stack.
+///
+/// Both the inputs and the expected values are listed from the bottom of the stack to the top,
+/// and they implement [`StackValue`].
+///
+/// ```ignore
+/// run_gadget!(CirclePointGadget::double_x(), [a], [double_a]);
+/// ```
+macro_rules! run_gadget {
+    ($gadget:expr, [$($input:expr),* $(,)?], [$($expected:expr),* $(,)?]) => {{
+        use $crate::tests_utils::gadget::StackValue;
+        let inputs: Vec<$crate::treepp::Script> = vec![$(StackValue::push(&$input)),*];
+        let checks: Vec<$crate::treepp::Script> = vec![$(StackValue::equalverify(&$expected)),*];
+
+        let script = $crate::treepp::script! {
+            for input in inputs {
+                { input }
+            }
+            { $gadget }
+            for check in checks.into_iter().rev() {
+                { check }
+            }
+            OP_TRUE
+        };
+        let exec_result = $crate::treepp::execute_script(script);
+        assert!(exec_result.success);
+    }};
+}
+
+pub(crate) use run_gadget;
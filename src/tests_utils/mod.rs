@@ -1,3 +1,19 @@
 #[cfg(not(tarpaulin_include))]
 /// This module contains functions for reporting test results to a CSV file.
 pub mod report;
+
+#[cfg(feature = "test-utils")]
+/// This module contains proptest strategies for the types of the crate.
+pub mod strategies;
+
+#[cfg(test)]
+/// This module contains a harness for testing gadgets.
+pub(crate) mod gadget;
+
+#[cfg(test)]
+/// This module contains a helper for comparing values against snapshots on disk.
+pub(crate) mod snapshot;
+
+#[cfg(test)]
+/// This module contains a helper for giving each test its own temporary directory.
+pub(crate) mod temp_dir;
@@ -0,0 +1,70 @@
+//! This module contains a helper for comparing named values against snapshots on disk.
+//!
+//! The snapshots are stored in the `snapshots` directory of the crate, one `name value` pair per
+//! line. The snapshots are committed with the tests: a missing snapshot fails the test, and a
+//! snapshot is only recorded, or overwritten, when the `UPDATE_SNAPSHOTS` environment variable is
+//! set.
+use std::fs;
+use std::path::PathBuf;
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("snapshots")
+        .join(format!("{}.snap", name))
+}
+
+/// Compare the entries with the snapshot of the given name, and panic at the first entry that
+/// diverges.
+pub(crate) fn assert_snapshot(name: &str, entries: &[(String, String)]) {
+    let path = snapshot_path(name);
+    let rendered = entries
+        .iter()
+        .map(|(key, value)| format!("{} {}\n", key, value))
+        .collect::<String>();
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, rendered).unwrap();
+        return;
+    }
+    assert!(
+        path.exists(),
+        "snapshot {} is missing, run the test with UPDATE_SNAPSHOTS=1 to record it",
+        name
+    );
+
+    let stored = fs::read_to_string(&path).unwrap();
+    let stored = stored
+        .lines()
+        .map(|line| {
+            line.split_once(' ')
+                .expect("a snapshot line is `name value`")
+        })
+        .collect::<Vec<_>>();
+
+    for (i, (key, value)) in entries.iter().enumerate() {
+        match stored.get(i) {
+            Some((stored_key, stored_value)) => assert!(
+                stored_key == key && stored_value == value,
+                "snapshot {} diverges at entry {}: expected `{} {}`, got `{} {}`",
+                name,
+                i,
+                stored_key,
+                stored_value,
+                key,
+                value
+            ),
+            None => panic!(
+                "snapshot {} diverges at entry {}: expected the end, got `{} {}`",
+                name, i, key, value
+            ),
+        }
+    }
+    assert_eq!(
+        stored.len(),
+        entries.len(),
+        "snapshot {} diverges at entry {}: expected more entries",
+        name,
+        entries.len()
+    );
+}
@@ -0,0 +1,74 @@
+//! This module contains proptest strategies for the types of the crate.
+//!
+//! The values are generated the way the crate produces them, e.g., m31 elements are reduced and
+//! points and drawing hints come out of a channel, so that gadgets built downstream can be tested
+//! against the same encodings. Implementing `Arbitrary` directly is not possible, as these types
+//! are defined in stwo.
+use crate::channel::{ChannelWithHint, DrawHints, Sha256Channel};
+use crate::oods::OODS;
+use proptest::prelude::*;
+use stwo_prover::core::circle::CirclePoint;
+use stwo_prover::core::fields::cm31::CM31;
+use stwo_prover::core::fields::m31::M31;
+use stwo_prover::core::fields::qm31::QM31;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// A strategy for m31 elements.
+pub fn m31() -> impl Strategy<Value = M31> {
+    any::<u64>().prop_map(M31::reduce)
+}
+
+/// A strategy for cm31 elements.
+pub fn cm31() -> impl Strategy<Value = CM31> {
+    (m31(), m31()).prop_map(|(a, b)| CM31::from_m31(a, b))
+}
+
+/// A strategy for qm31 elements.
+pub fn qm31() -> impl Strategy<Value = QM31> {
+    (m31(), m31(), m31(), m31()).prop_map(|(a, b, c, d)| QM31::from_m31(a, b, c, d))
+}
+
+/// A strategy for BWS-SHA256 hashes.
+pub fn bws_sha256_hash() -> impl Strategy<Value = BWSSha256Hash> {
+    any::<[u8; 32]>().prop_map(|bytes| BWSSha256Hash::from(bytes.to_vec()))
+}
+
+/// A strategy for points on the circle over qm31, drawn from a channel.
+pub fn circle_point() -> impl Strategy<Value = CirclePoint<QM31>> {
+    bws_sha256_hash().prop_map(|digest| {
+        CirclePoint::<QM31>::get_random_point_with_hint(&mut Sha256Channel::new(digest)).0
+    })
+}
+
+/// A strategy for the hints of drawing a qm31 element from a channel.
+pub fn draw_hints() -> impl Strategy<Value = DrawHints> {
+    bws_sha256_hash().prop_map(|digest| Sha256Channel::new(digest).draw_felt_and_hints().1)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tests_utils::strategies::{circle_point, m31, qm31};
+    use num_traits::{One, Zero};
+    use proptest::prelude::*;
+    use stwo_prover::core::fields::m31::P;
+    use stwo_prover::core::fields::qm31::QM31;
+    use stwo_prover::core::fields::FieldExpOps;
+
+    proptest! {
+        #[test]
+        fn test_m31_is_reduced(a in m31()) {
+            prop_assert!(a.0 < P);
+        }
+
+        #[test]
+        fn test_qm31_inverse(a in qm31()) {
+            prop_assume!(!a.is_zero());
+            prop_assert_eq!(a * a.inverse(), QM31::one());
+        }
+
+        #[test]
+        fn test_circle_point_on_circle(p in circle_point()) {
+            prop_assert_eq!(p.x.square() + p.y.square(), QM31::one());
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use crate::fri::{FRIGadget, FriProof};
+use crate::simulator::{profile_stack, MAX_STACK_SIZE};
+use crate::treepp::*;
+use crate::twiddle_merkle_tree::TwiddleMerkleTree;
+use crate::witness::hints_to_witness;
+use stwo_prover::core::prover::N_QUERIES;
+
+/// Where a value comes from in the verifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// The value is baked into the script.
+    Inline,
+    /// The value is supplied in the witness and verified by the script.
+    Hinted,
+}
+
+/// The cost of a placement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlacementCost {
+    /// The size of the script, in bytes.
+    pub script_size: usize,
+    /// The serialized size of the hints in the witness, in bytes.
+    pub witness_size: usize,
+    /// The maximal number of elements in the main and alt stacks combined.
+    pub max_stack_size: usize,
+}
+
+impl PlacementCost {
+    /// The total size, which is what the fee is paid for, as the script is also in the witness
+    /// of a script-path spend.
+    pub fn total_size(&self) -> usize {
+        self.script_size + self.witness_size
+    }
+
+    /// Whether the stacks stay within [`MAX_STACK_SIZE`].
+    pub fn fits(&self) -> bool {
+        self.max_stack_size <= MAX_STACK_SIZE
+    }
+}
+
+/// The costs of both placements for a class of values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueClassReport {
+    /// The name of the class.
+    pub name: &'static str,
+    /// The cost when the values are baked into the script.
+    pub inline: PlacementCost,
+    /// The cost when the values are hinted.
+    pub hinted: PlacementCost,
+}
+
+impl ValueClassReport {
+    /// The placement with the smaller total size among those that fit in the stack limit, and
+    /// hinted if none does.
+    pub fn cheaper(&self) -> Placement {
+        if self.inline.fits()
+            && (!self.hinted.fits() || self.inline.total_size() < self.hinted.total_size())
+        {
+            Placement::Inline
+        } else {
+            Placement::Hinted
+        }
+    }
+}
+
+/// The script that outputs the twiddle factors of the queries of a FRI proof over a domain of
+/// size 2^logn, given the positions, see [`FRIGadget::check_twiddle_merkle_tree_proof`].
+pub fn twiddle_factors_gadget(logn: usize, placement: Placement) -> Script {
+    match placement {
+        Placement::Inline => FRIGadget::check_twiddle_table(logn),
+        Placement::Hinted => FRIGadget::check_twiddle_merkle_tree_proof(
+            logn,
+            TwiddleMerkleTree::new(logn - 1).root_hash,
+        ),
+    }
+}
+
+/// The hints of [`twiddle_factors_gadget`].
+pub fn twiddle_factors_hints(fri_proof: &FriProof, placement: Placement) -> Script {
+    match placement {
+        Placement::Inline => script! {},
+        Placement::Hinted => FRIGadget::push_twiddle_merkle_tree_proof(fri_proof),
+    }
+}
+
+fn placement_cost(logn: usize, placement: Placement) -> PlacementCost {
+    // the costs do not depend on the positions, except for the minimal encoding of the numbers
+    let positions = (0..N_QUERIES).collect::<Vec<_>>();
+    let twiddle_merkle_tree = TwiddleMerkleTree::new(logn - 1);
+    let hints = match placement {
+        Placement::Inline => script! {},
+        Placement::Hinted => script! {
+            for pos in positions.iter() {
+                { twiddle_merkle_tree.query(*pos) }
+            }
+        },
+    };
+    let witness = hints_to_witness(hints).expect("the hints should only be pushes");
+
+    let script = twiddle_factors_gadget(logn, placement);
+    let profile = profile_stack(
+        script! {
+            for pos in positions.iter() {
+                { *pos }
+            }
+            { script.clone() }
+        },
+        witness.to_vec(),
+    );
+
+    PlacementCost {
+        script_size: script.len(),
+        witness_size: witness.size(),
+        max_stack_size: profile.max_stack_size,
+    }
+}
+
+/// Compare both placements for each class of values of a FRI verifier over a domain of size
+/// 2^logn.
+///
+/// The only class with a choice is the twiddle factors: the folding factors, the queries, and
+/// the evaluations are only known with the proof and must be hinted, while the channel initial
+/// state and the Merkle roots of the twiddles are already baked into the script.
+pub fn analyze(logn: usize) -> Vec<ValueClassReport> {
+    vec![ValueClassReport {
+        name: "twiddle_factors",
+        inline: placement_cost(logn, Placement::Inline),
+        hinted: placement_cost(logn, Placement::Hinted),
+    }]
+}
+
+/// The cheaper placement of the twiddle factors for a FRI verifier over a domain of size
+/// 2^logn, which [`crate::fri::FriIncrementalVerifier::with_twiddle_placement`] applies.
+pub fn cheaper_twiddle_placement(logn: usize) -> Placement {
+    analyze(logn)
+        .into_iter()
+        .find(|report| report.name == "twiddle_factors")
+        .expect("the twiddle factors should be analyzed")
+        .cheaper()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::simulator::MAX_STACK_SIZE;
+    use crate::tradeoff::{analyze, cheaper_twiddle_placement, twiddle_factors_gadget, Placement};
+    use crate::treepp::*;
+    use crate::twiddle_merkle_tree::TwiddleMerkleTree;
+    use stwo_prover::core::prover::N_QUERIES;
+
+    #[test]
+    fn test_analyze() {
+        // small tables are cheaper than the Merkle paths
+        let report = analyze(4);
+        assert_eq!(report[0].name, "twiddle_factors");
+        assert_eq!(report[0].inline.witness_size, 1);
+        assert_eq!(report[0].cheaper(), Placement::Inline);
+        assert_eq!(cheaper_twiddle_placement(4), Placement::Inline);
+
+        // large tables do not fit in the stack
+        let report = analyze(12);
+        assert!(report[0].inline.max_stack_size > MAX_STACK_SIZE);
+        assert!(report[0].hinted.fits());
+        assert_eq!(report[0].cheaper(), Placement::Hinted);
+        assert_eq!(cheaper_twiddle_placement(12), Placement::Hinted);
+    }
+
+    #[test]
+    fn test_twiddle_factors_gadget() {
+        let logn = 6;
+        let twiddle_merkle_tree = TwiddleMerkleTree::new(logn - 1);
+        let positions = (0..N_QUERIES).map(|i| 7 * i + 3).collect::<Vec<_>>();
+        let proofs = positions
+            .iter()
+            .map(|pos| twiddle_merkle_tree.query(*pos))
+            .collect::<Vec<_>>();
+
+        // both placements output the same twiddle factors
+        for placement in [Placement::Inline, Placement::Hinted] {
+            let script = script! {
+                if placement == Placement::Hinted {
+                    for proof in proofs.iter() {
+                        { proof }
+                    }
+                }
+                for pos in positions.iter() {
+                    { *pos }
+                }
+                { twiddle_factors_gadget(logn, placement) }
+                for proof in proofs.iter().rev() {
+                    for i in 0..logn - 1 {
+                        { proof.elements[logn - 2 - i] }
+                        OP_EQUALVERIFY
+                    }
+                }
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::treepp::*;
-use crate::utils::limb_to_le_bits;
+use crate::utils::{get_twiddles, limb_to_le_bits};
+use stwo_prover::core::fields::FieldExpOps;
 
 /// Gadget for verifying a Merkle tree path in a twiddle tree.
 pub struct TwiddleMerkleTreeGadget;
@@ -78,10 +79,67 @@ impl TwiddleMerkleTreeGadget {
     }
 }
 
+/// Gadget for looking up the twiddle factors in tables baked into the script, as an alternative
+/// to [`TwiddleMerkleTreeGadget`] that needs no hint.
+///
+/// The tables of a domain of size 2^logn hold 2^(logn - 1) elements in total, which are all on
+/// the stack while looking up the first layer.
+pub struct TwiddleTableGadget;
+
+impl TwiddleTableGadget {
+    /// Look up the inverse twiddle factors of a point.
+    ///
+    /// input:
+    ///   pos
+    ///
+    /// output:
+    ///   v (m31 -- [num_layer] elements), as in [`TwiddleMerkleTreeGadget::query_and_verify`]
+    pub fn query(logn: usize) -> Script {
+        let num_layer = logn - 1;
+        let twiddles_inverse = get_twiddles(logn)
+            .into_iter()
+            .map(|row| row.into_iter().map(|v| v.inverse()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        script! {
+            // convert pos into bits, drop the LSB, and move the bits to the altstack, with the
+            // MSB on the top
+            { limb_to_le_bits(logn as u32) }
+            OP_DROP
+            for _ in 0..num_layer {
+                OP_TOALTSTACK
+            }
+
+            // the index in the current layer, which is pos >> (i + 1) for the layer i
+            0
+
+            for i in (0..num_layer).rev() {
+                OP_DUP OP_ADD OP_FROMALTSTACK OP_ADD
+                OP_DUP OP_TOALTSTACK
+
+                for v in twiddles_inverse[i].iter().rev() {
+                    { *v }
+                }
+                OP_FROMALTSTACK OP_PICK OP_TOALTSTACK
+
+                for _ in 0..twiddles_inverse[i].len() / 2 {
+                    OP_2DROP
+                }
+                OP_FROMALTSTACK OP_SWAP
+            }
+
+            OP_DROP
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use crate::digest::push_digest;
     use crate::treepp::*;
-    use crate::twiddle_merkle_tree::{TwiddleMerkleTree, TwiddleMerkleTreeGadget};
+    use crate::twiddle_merkle_tree::{
+        TwiddleMerkleTree, TwiddleMerkleTreeGadget, TwiddleTableGadget,
+    };
     use rand::{Rng, SeedableRng};
     use rand_chacha::ChaCha20Rng;
 
@@ -104,7 +162,7 @@ mod test {
 
             let script = script! {
                 { twiddle_proof.clone() }
-                { twiddle_merkle_tree.root_hash.to_vec() }
+                { push_digest(&twiddle_merkle_tree.root_hash) }
                 { pos }
                 { verify_script.clone() }
                 for i in 0..n_layers {
@@ -118,4 +176,39 @@ mod test {
             assert!(exec_result.success);
         }
     }
+
+    #[test]
+    fn test_twiddle_table() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        for logn in 3..=10 {
+            let query_script = TwiddleTableGadget::query(logn);
+            println!(
+                "TwiddleTable.query(2^{}) = {} bytes",
+                logn,
+                query_script.len()
+            );
+
+            let n_layers = logn - 1;
+            let twiddle_merkle_tree = TwiddleMerkleTree::new(n_layers);
+
+            let mut pos: u32 = prng.gen();
+            pos &= (1 << logn) - 1;
+
+            let twiddle_proof = twiddle_merkle_tree.query(pos as usize);
+
+            let script = script! {
+                { pos }
+                { query_script.clone() }
+                for i in 0..n_layers {
+                    { twiddle_proof.elements[n_layers - 1 - i] }
+                    OP_EQUALVERIFY
+                }
+                OP_TRUE
+            };
+
+            let exec_result = execute_script(script);
+            assert!(exec_result.success);
+        }
+    }
 }
@@ -1,5 +1,5 @@
 use crate::utils::get_twiddles;
-use crate::utils::num_to_bytes;
+use crate::utils::{hash_chunks, num_to_bytes};
 use sha2::{Digest, Sha256};
 use stwo_prover::core::fields::m31::M31;
 use stwo_prover::core::fields::FieldExpOps;
@@ -8,6 +8,7 @@ mod bitcoin_script;
 pub use bitcoin_script::*;
 
 mod constants;
+use crate::digest::CanonicalDigest;
 use crate::treepp::pushable::{Builder, Pushable};
 pub use constants::*;
 
@@ -32,58 +33,46 @@ impl TwiddleMerkleTree {
 
         let mut layers = vec![];
 
-        let mut leaf_hashes: Vec<[u8; 32]> = Vec::with_capacity(1 << logn);
-        for i in 0..(1 << logn) {
+        let leaf_hashes = hash_chunks(&twiddles[0], 1, |_, v| {
             let mut bytes = [0u8; 32];
             let hash = {
                 let mut sha256 = Sha256::new();
-                Digest::update(&mut sha256, num_to_bytes(twiddles[0][i]));
+                Digest::update(&mut sha256, num_to_bytes(v[0]));
                 sha256.finalize()
             };
             bytes.copy_from_slice(&hash);
-            leaf_hashes.push(bytes);
-        }
+            bytes
+        });
         layers.push(leaf_hashes.clone());
 
         let mut cur_parent_layer_idx = 1;
 
-        let mut cur = leaf_hashes
-            .chunks_exact(2)
-            .enumerate()
-            .map(|(i, v)| {
-                let mut hash_result = [0u8; 32];
+        let mut cur = hash_chunks(&leaf_hashes, 2, |i, v| {
+            let mut hash_result = [0u8; 32];
 
-                let mut hasher = Sha256::new();
-                Digest::update(&mut hasher, v[0]);
-                Digest::update(&mut hasher, num_to_bytes(twiddles[cur_parent_layer_idx][i]));
-                Digest::update(&mut hasher, v[1]);
-                hash_result.copy_from_slice(hasher.finalize().as_slice());
-                hash_result
-            })
-            .collect::<Vec<[u8; 32]>>();
+            let mut hasher = Sha256::new();
+            Digest::update(&mut hasher, v[0]);
+            Digest::update(&mut hasher, num_to_bytes(twiddles[cur_parent_layer_idx][i]));
+            Digest::update(&mut hasher, v[1]);
+            hash_result.copy_from_slice(hasher.finalize().as_slice());
+            hash_result
+        });
         layers.push(cur.clone());
 
         while cur.len() > 1 {
             cur_parent_layer_idx += 1;
 
-            cur = cur
-                .chunks_exact(2)
-                .enumerate()
-                .map(|(i, v)| {
-                    let mut hash_result = [0u8; 32];
-                    let mut hasher = Sha256::new();
-                    Digest::update(&mut hasher, v[0]);
-                    if cur_parent_layer_idx != logn {
-                        Digest::update(
-                            &mut hasher,
-                            num_to_bytes(twiddles[cur_parent_layer_idx][i]),
-                        );
-                    }
-                    Digest::update(&mut hasher, v[1]);
-                    hash_result.copy_from_slice(hasher.finalize().as_slice());
-                    hash_result
-                })
-                .collect::<Vec<[u8; 32]>>();
+            cur = hash_chunks(&cur, 2, |i, v| {
+                let mut hash_result = [0u8; 32];
+                let mut hasher = Sha256::new();
+                Digest::update(&mut hasher, v[0]);
+                if cur_parent_layer_idx != logn {
+                    Digest::update(&mut hasher, num_to_bytes(twiddles[cur_parent_layer_idx][i]));
+                }
+                Digest::update(&mut hasher, v[1]);
+                hash_result.copy_from_slice(hasher.finalize().as_slice());
+                hash_result
+            });
             layers.push(cur.clone());
         }
 
@@ -176,11 +165,15 @@ impl Pushable for &TwiddleMerkleTreeProof {
         builder = self.elements.last().unwrap().bitcoin_script_push(builder);
         for (element, sibling) in self.elements.iter().rev().skip(1).zip(self.siblings.iter()) {
             builder = element.bitcoin_script_push(builder);
-            builder = sibling.to_vec().bitcoin_script_push(builder);
+            builder = sibling
+                .to_canonical_bytes()
+                .to_vec()
+                .bitcoin_script_push(builder);
         }
         self.siblings
             .last()
             .unwrap()
+            .to_canonical_bytes()
             .to_vec()
             .bitcoin_script_push(builder)
     }
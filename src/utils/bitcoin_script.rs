@@ -1,4 +1,8 @@
 use crate::treepp::*;
+use bitcoin::opcodes::all::{
+    OP_DUP, OP_OVER, OP_PICK, OP_PUSHNUM_1, OP_PUSHNUM_2, OP_ROLL, OP_ROT, OP_SWAP,
+};
+use bitcoin::script::Instruction;
 
 /// Gadget for trimming away a m31 element to keep only logn bits.
 pub fn trim_m31_gadget(logn: usize) -> Script {
@@ -43,12 +47,63 @@ pub fn hash_felt_gadget() -> Script {
     }
 }
 
+/// Replace the shallow `OP_PICK` and `OP_ROLL` by their one-byte equivalents: `0 OP_PICK` by
+/// `OP_DUP`, `1 OP_PICK` by `OP_OVER`, `1 OP_ROLL` by `OP_SWAP`, `2 OP_ROLL` by `OP_ROT`, and drop
+/// `0 OP_ROLL`.
+///
+/// This is a peephole pass: the scripts carry no dataflow information, so independent
+/// computations cannot be reordered, and deep accesses are left to the gadgets themselves, e.g.,
+/// by keeping values on the alt stack. A scheduling pass is out of scope, see the chapter on
+/// limitations in the book.
+pub fn optimize_pick_roll(script: &Script) -> Script {
+    let instructions = script
+        .instructions()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("the script should be well-formed");
+
+    let mut res = Script::new();
+    let mut i = 0;
+    while i < instructions.len() {
+        let depth = match instructions[i] {
+            Instruction::PushBytes(bytes) if bytes.is_empty() => Some(0),
+            Instruction::Op(OP_PUSHNUM_1) => Some(1),
+            Instruction::Op(OP_PUSHNUM_2) => Some(2),
+            _ => None,
+        };
+        let next = instructions.get(i + 1).copied();
+
+        let replacement = match (depth, next) {
+            (Some(0), Some(Instruction::Op(OP_PICK))) => Some(Some(OP_DUP)),
+            (Some(1), Some(Instruction::Op(OP_PICK))) => Some(Some(OP_OVER)),
+            (Some(0), Some(Instruction::Op(OP_ROLL))) => Some(None),
+            (Some(1), Some(Instruction::Op(OP_ROLL))) => Some(Some(OP_SWAP)),
+            (Some(2), Some(Instruction::Op(OP_ROLL))) => Some(Some(OP_ROT)),
+            _ => None,
+        };
+
+        match replacement {
+            Some(opcode) => {
+                if let Some(opcode) = opcode {
+                    res.push_opcode(opcode);
+                }
+                i += 2;
+            }
+            None => {
+                res.push_instruction_no_opt(instructions[i]);
+                i += 1;
+            }
+        }
+    }
+    res
+}
+
 #[cfg(test)]
 mod test {
     use crate::treepp::*;
-    use crate::utils::{trim_m31, trim_m31_gadget};
+    use crate::utils::{optimize_pick_roll, trim_m31, trim_m31_gadget};
     use rand::{RngCore, SeedableRng};
     use rand_chacha::ChaCha20Rng;
+    use rust_bitcoin_m31::qm31_roll;
     use stwo_prover::core::fields::m31::M31;
 
     #[test]
@@ -72,4 +127,45 @@ mod test {
             assert!(exec_result.success);
         }
     }
+
+    #[test]
+    fn test_optimize_pick_roll() {
+        let script = script! {
+            1 2 3
+            0 OP_PICK
+            1 OP_PICK
+            0 OP_ROLL
+            1 OP_ROLL
+            2 OP_ROLL
+            4 OP_PICK
+        };
+        let optimized = optimize_pick_roll(&script);
+        assert_eq!(
+            optimized,
+            script! {
+                1 2 3
+                OP_DUP
+                OP_OVER
+                OP_SWAP
+                OP_ROT
+                4 OP_PICK
+            }
+        );
+
+        // the gadgets that move qm31 elements around do not get larger
+        let roll = qm31_roll(1);
+        assert!(optimize_pick_roll(&roll).len() <= roll.len());
+
+        let check = |script: Script| {
+            let exec_result = execute_script(script! {
+                { script }
+                3 OP_EQUALVERIFY
+                2 OP_EQUALVERIFY
+                1 OP_EQUAL
+            });
+            assert!(exec_result.success);
+        };
+        check(script! { 2 1 3 1 OP_ROLL 2 OP_ROLL });
+        check(optimize_pick_roll(&script! { 2 1 3 1 OP_ROLL 2 OP_ROLL }));
+    }
 }
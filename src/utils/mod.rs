@@ -3,12 +3,39 @@ mod bitcoin_script;
 use crate::treepp::*;
 pub use bitcoin_script::*;
 use num_traits::Zero;
-use rand::RngCore;
+use rand::{Rng, RngCore};
 use sha2::{Digest, Sha256};
 use std::cmp::min;
 use stwo_prover::core::circle::CirclePointIndex;
 use stwo_prover::core::fields::m31::M31;
 use stwo_prover::core::fields::qm31::QM31;
+use stwo_prover::core::vcs::bws_sha256_hash::BWSSha256Hash;
+
+/// Hash each chunk of `chunk_size` consecutive elements of a layer, given the index of the chunk,
+/// which runs on all cores with the `parallel` feature.
+pub(crate) fn hash_chunks<T, F>(layer: &[T], chunk_size: usize, hash: F) -> Vec<[u8; 32]>
+where
+    T: Sync,
+    F: Fn(usize, &[T]) -> [u8; 32] + Sync + Send,
+{
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        layer
+            .par_chunks_exact(chunk_size)
+            .enumerate()
+            .map(|(i, v)| hash(i, v))
+            .collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        layer
+            .chunks_exact(chunk_size)
+            .enumerate()
+            .map(|(i, v)| hash(i, v))
+            .collect()
+    }
+}
 
 /// Convert a m31 element to its Bitcoin integer representation.
 pub fn num_to_bytes(v: M31) -> Vec<u8> {
@@ -237,3 +264,10 @@ pub fn get_rand_qm31<R: RngCore>(prng: &mut R) -> QM31 {
         M31::reduce(prng.next_u64()),
     )
 }
+
+/// Get a random BWS-SHA256 hash, e.g., as a channel initial state.
+pub fn get_rand_bws_sha256_hash<R: Rng>(prng: &mut R) -> BWSSha256Hash {
+    let mut bytes = [0u8; 32];
+    bytes.iter_mut().for_each(|v| *v = prng.gen());
+    BWSSha256Hash::from(bytes.to_vec())
+}
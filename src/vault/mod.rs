@@ -0,0 +1,245 @@
+use crate::fibonacci::{FibonacciConfig, VerifierHints};
+use crate::pipeline::verifier_leaf_script;
+use crate::taproot::{VerifierTaproot, VerifierTaprootBuilder};
+use crate::treepp::*;
+use crate::witness::hints_to_witness;
+use bitcoin::hashes::Hash;
+use bitcoin::key::{Keypair, XOnlyPublicKey};
+use bitcoin::secp256k1::{Message, Secp256k1};
+use bitcoin::sighash::{Prevouts, SighashCache, TapSighashType};
+use bitcoin::taproot::{LeafVersion, TapLeafHash};
+use bitcoin::{Address, Amount, FeeRate, Network, OutPoint, TapSighash, Transaction, TxOut};
+
+/// The size of a Schnorr signature with the default sighash type.
+const SIGNATURE_SIZE: usize = 64;
+
+/// A vault: coins locked to a taproot output whose script path requires the hints of a proof of
+/// a designated Fibonacci statement and a signature of the beneficiary, and optionally refunded
+/// after a delay.
+///
+/// The lifecycle is:
+/// - the depositor sends coins to [`Vault::funding_output`], e.g., to [`Vault::address`];
+/// - the beneficiary, with a proof of the statement, e.g., from
+///   [`crate::fibonacci::prove_and_hint`], builds and signs the reveal transaction with
+///   [`Vault::unlock`].
+///
+/// The proof alone does not protect the coins:
+/// - the statement is public, so anyone can prove it from the [`FibonacciConfig`];
+/// - the verifier leaf (see [`verifier_leaf_script`]) replays the Fiat-Shamir transcript, the
+///   composition check, the PoW, and the query sampling, but it does not open the commitments
+///   nor check the FRI queries.
+///
+/// It is the signature of the beneficiary that restricts who can spend the coins, and, as it
+/// commits to the outputs of the reveal transaction, where they go: a copy of the witness from
+/// the mempool cannot be used to redirect them.
+pub struct Vault {
+    /// The designated statement.
+    pub config: FibonacciConfig,
+    /// The key of the beneficiary, which signs the reveal transaction.
+    pub beneficiary: XOnlyPublicKey,
+    /// The leaf, which runs the verifier on the hints of a proof of the statement and then checks
+    /// the signature of the beneficiary.
+    pub leaf_script: Script,
+    /// The taproot output.
+    pub taproot: VerifierTaproot,
+}
+
+impl Vault {
+    /// Create a vault for the statement that pays to `beneficiary`, with a refund leaf if
+    /// `refund` provides the key and the delay, see [`VerifierTaprootBuilder::refund`].
+    pub fn new(
+        config: FibonacciConfig,
+        beneficiary: XOnlyPublicKey,
+        refund: Option<(XOnlyPublicKey, u16)>,
+    ) -> Self {
+        let leaf_script = script! {
+            // the signature is the last element of the witness, above the hints
+            OP_TOALTSTACK
+            { verifier_leaf_script(&config.initial_channel(), &config) }
+            OP_VERIFY
+            OP_FROMALTSTACK
+            { beneficiary.serialize().to_vec() }
+            OP_CHECKSIG
+        };
+
+        let mut builder = VerifierTaprootBuilder::new().add_verifier_leaf(leaf_script.clone());
+        if let Some((refund_key, delay)) = refund {
+            builder = builder.refund(refund_key, delay);
+        }
+
+        Self {
+            config,
+            beneficiary,
+            leaf_script,
+            taproot: builder.finalize(),
+        }
+    }
+
+    /// The address of the vault on the given network.
+    pub fn address(&self, network: Network) -> Address {
+        self.taproot.address(network)
+    }
+
+    /// The output that deposits `amount` into the vault.
+    pub fn funding_output(&self, amount: Amount) -> TxOut {
+        TxOut {
+            value: amount,
+            script_pubkey: self.taproot.script_pubkey(),
+        }
+    }
+
+    /// The message that the beneficiary signs to spend a deposit of `amount` through the leaf
+    /// with `tx`, which commits to all the outputs of `tx`.
+    pub fn sighash(&self, tx: &Transaction, amount: Amount) -> Result<TapSighash, String> {
+        let prevouts = [self.funding_output(amount)];
+        SighashCache::new(tx)
+            .taproot_script_spend_signature_hash(
+                0,
+                &Prevouts::All(&prevouts),
+                TapLeafHash::from_script(&self.leaf_script, LeafVersion::TapScript),
+                TapSighashType::Default,
+            )
+            .map_err(|err| err.to_string())
+    }
+
+    /// Build the reveal transaction, which spends the deposit at `outpoint` to `destination`
+    /// with the hints of a proof of the statement, and sign it with the key of the beneficiary.
+    pub fn unlock(
+        &self,
+        outpoint: OutPoint,
+        amount: Amount,
+        hints: VerifierHints,
+        destination: Script,
+        fee_rate: FeeRate,
+        beneficiary: &Keypair,
+    ) -> Result<Transaction, String> {
+        if beneficiary.x_only_public_key().0 != self.beneficiary {
+            return Err("the key is not the one of the beneficiary".to_string());
+        }
+
+        // a placeholder of the size of the signature, so that the fee covers it
+        let mut witness = hints_to_witness(script! { { hints } })?;
+        witness.push([0u8; SIGNATURE_SIZE]);
+
+        let mut tx = self
+            .taproot
+            .script_path_spend(
+                outpoint,
+                amount,
+                &self.leaf_script,
+                &witness,
+                destination,
+                fee_rate,
+            )
+            .ok_or_else(|| "the amount does not cover the fee".to_string())?;
+
+        let sighash = self.sighash(&tx, amount)?;
+        let signature = Secp256k1::signing_only()
+            .sign_schnorr_no_aux_rand(&Message::from_digest(sighash.to_byte_array()), beneficiary);
+
+        // the witness ends with the signature, the leaf, and the control block
+        let mut witness = tx.input[0].witness.to_vec();
+        let index = witness.len() - 3;
+        witness[index] = signature.serialize().to_vec();
+        tx.input[0].witness = witness.into();
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::{prove_and_hint, FibonacciConfig};
+    use crate::pipeline::verifier_leaf_script;
+    use crate::treepp::*;
+    use crate::vault::Vault;
+    use bitcoin::hashes::Hash;
+    use bitcoin::key::Keypair;
+    use bitcoin::secp256k1::{schnorr, Message, Secp256k1, SecretKey};
+    use bitcoin::{Amount, FeeRate, OutPoint, Transaction, Txid};
+    use bitcoin_scriptexec::execute_script_with_witness_unlimited_stack;
+
+    #[test]
+    fn test_vault() {
+        let secp = Secp256k1::new();
+        let beneficiary =
+            Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[1; 32]).unwrap());
+
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let vault = Vault::new(config, beneficiary.x_only_public_key().0, None);
+
+        let amount = Amount::from_sat(1_000_000);
+        let funding_output = vault.funding_output(amount);
+        assert_eq!(funding_output.script_pubkey, vault.taproot.script_pubkey());
+
+        // the beneficiary unlocks the deposit with a proof of the statement
+        let (_, hints) = prove_and_hint(&config).unwrap();
+        let outpoint = OutPoint::new(Txid::all_zeros(), 0);
+        let reveal = vault
+            .unlock(
+                outpoint,
+                amount,
+                hints,
+                script! { OP_TRUE },
+                FeeRate::BROADCAST_MIN,
+                &beneficiary,
+            )
+            .unwrap();
+        assert_eq!(reveal.input[0].previous_output, outpoint);
+        assert!(reveal.output[0].value < amount);
+
+        // the witness is the hints, the signature, the leaf, and the control block
+        let witness = reveal.input[0].witness.to_vec();
+        assert_eq!(witness[witness.len() - 2], vault.leaf_script.to_bytes());
+        let signature = schnorr::Signature::from_slice(&witness[witness.len() - 3]).unwrap();
+        let hints = witness[..witness.len() - 3].to_vec();
+
+        // the signature commits to the outputs, so the reveal cannot be redirected
+        let message = |tx: &Transaction| {
+            Message::from_digest(vault.sighash(tx, amount).unwrap().to_byte_array())
+        };
+        let beneficiary_key = beneficiary.x_only_public_key().0;
+        assert!(secp
+            .verify_schnorr(&signature, &message(&reveal), &beneficiary_key)
+            .is_ok());
+        let mut redirected = reveal.clone();
+        redirected.output[0].script_pubkey = script! { OP_FALSE };
+        assert!(secp
+            .verify_schnorr(&signature, &message(&redirected), &beneficiary_key)
+            .is_err());
+
+        // the hints pass the verifier of the leaf
+        let exec_result = execute_script_with_witness_unlimited_stack(
+            verifier_leaf_script(&config.initial_channel(), &config),
+            hints.clone(),
+        );
+        assert!(exec_result.success);
+
+        // the same proof does not pass the verifier for another statement
+        let other_config = FibonacciConfig {
+            log_size: 5,
+            claim: 1,
+        };
+        let exec_result = execute_script_with_witness_unlimited_stack(
+            verifier_leaf_script(&other_config.initial_channel(), &other_config),
+            hints,
+        );
+        assert!(!exec_result.success);
+
+        // only the beneficiary can unlock the deposit
+        let other = Keypair::from_secret_key(&secp, &SecretKey::from_slice(&[2; 32]).unwrap());
+        let (_, hints) = prove_and_hint(&config).unwrap();
+        assert!(vault
+            .unlock(
+                outpoint,
+                amount,
+                hints,
+                script! { OP_TRUE },
+                FeeRate::BROADCAST_MIN,
+                &other,
+            )
+            .is_err());
+    }
+}
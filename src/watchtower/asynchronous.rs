@@ -0,0 +1,87 @@
+use crate::watchtower::{Alert, ChainBackend, Watchtower, WatchtowerError};
+use bitcoin::{OutPoint, Transaction, Txid};
+use std::future::{ready, Future};
+use std::pin::pin;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+/// The view of the chain through an async client, e.g., of a node or an indexer on the runtime
+/// of the caller.
+///
+/// Every [`ChainBackend`] is also an [`AsyncChainBackend`], whose futures are ready at once, so
+/// the crate does not depend on a runtime.
+pub trait AsyncChainBackend {
+    /// The error of the backend.
+    type Error;
+
+    /// The number of confirmations of the transaction, see [`ChainBackend::confirmations`].
+    fn confirmations(&self, txid: &Txid) -> impl Future<Output = Result<Option<u32>, Self::Error>>;
+
+    /// Whether the output exists and is not spent, see [`ChainBackend::is_unspent`].
+    fn is_unspent(&self, outpoint: &OutPoint) -> impl Future<Output = Result<bool, Self::Error>>;
+
+    /// Broadcast a transaction.
+    fn broadcast(&self, tx: &Transaction) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+impl<B: ChainBackend> AsyncChainBackend for B {
+    type Error = <B as ChainBackend>::Error;
+
+    fn confirmations(&self, txid: &Txid) -> impl Future<Output = Result<Option<u32>, Self::Error>> {
+        ready(ChainBackend::confirmations(self, txid))
+    }
+
+    fn is_unspent(&self, outpoint: &OutPoint) -> impl Future<Output = Result<bool, Self::Error>> {
+        ready(ChainBackend::is_unspent(self, outpoint))
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> impl Future<Output = Result<(), Self::Error>> {
+        ready(ChainBackend::broadcast(self, tx))
+    }
+}
+
+impl Watchtower {
+    /// Poll the chain until the watch is over, handing the alerts of each poll to `on_alerts`,
+    /// and waiting for `sleep` between two polls, e.g., a timer of the runtime or the next
+    /// block.
+    pub async fn run<B, S, F>(
+        &mut self,
+        backend: &B,
+        mut sleep: S,
+        mut on_alerts: impl FnMut(&[Alert]),
+    ) -> Result<(), WatchtowerError<B::Error>>
+    where
+        B: AsyncChainBackend,
+        S: FnMut() -> F,
+        F: Future<Output = ()>,
+    {
+        loop {
+            let alerts = self.poll_async(backend).await?;
+            on_alerts(&alerts);
+            if alerts.contains(&Alert::Completed) {
+                return Ok(());
+            }
+            sleep().await;
+        }
+    }
+}
+
+// Run a future that never waits, such as the ones of a synchronous backend.
+pub(crate) fn block_on_ready<F: Future>(future: F) -> F::Output {
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    match pin!(future).poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("the future should be ready at once"),
+    }
+}
+
+fn noop_waker() -> Waker {
+    unsafe fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+    unsafe fn noop(_: *const ()) {}
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    // the waker does nothing, so the contract of `RawWaker` holds for any data pointer
+    unsafe { Waker::from_raw(RawWaker::new(std::ptr::null(), &VTABLE)) }
+}
@@ -0,0 +1,579 @@
+mod asynchronous;
+pub use asynchronous::*;
+
+use crate::fri::{FriIncrementalVerifier, FriProof};
+use crate::orchestrator::{StepKind, TxRole, VerificationChain};
+use crate::treepp::*;
+use bitcoin::key::Keypair;
+use bitcoin::{FeeRate, OutPoint, Transaction, Txid, Witness};
+use bitcoin_scriptexec::convert_to_witness;
+use std::collections::HashSet;
+
+/// The view of the chain that the watchtower polls, which can be a node, an indexer, or a mock.
+pub trait ChainBackend {
+    /// The error of the backend.
+    type Error;
+
+    /// The number of confirmations of the transaction, with `Some(0)` for a transaction in the
+    /// mempool, or `None` if the transaction is unknown.
+    fn confirmations(&self, txid: &Txid) -> Result<Option<u32>, Self::Error>;
+
+    /// Whether the output exists and is not spent, including by a transaction in the mempool.
+    fn is_unspent(&self, outpoint: &OutPoint) -> Result<bool, Self::Error>;
+
+    /// Broadcast a transaction.
+    fn broadcast(&self, tx: &Transaction) -> Result<(), Self::Error>;
+}
+
+/// What the watchtower observes, or does, when polling a chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Alert {
+    /// The commit transaction is not broadcast yet, which is up to the user as it is unsigned.
+    AwaitingCommit,
+    /// The transaction at this index is broadcast, as the output it spends is confirmed and
+    /// unspent.
+    Broadcast(usize),
+    /// The transaction at this index has been in the mempool for more polls than the patience
+    /// of the watchtower.
+    Stalled(usize),
+    /// The output spent by the transaction at this index is spent by another transaction, e.g.,
+    /// a step that is not the one of the chain, or a claim of the challenger, so the chain
+    /// cannot progress.
+    UnexpectedSpend(usize, OutPoint),
+    /// The output spent by the transaction at this index has been unspent for the delay of the
+    /// timeout of the chain, so the challenger can claim it.
+    TimedOut(usize),
+    /// The claim of the output spent by the transaction at this index is broadcast.
+    Claimed(usize),
+    /// The watch is over: all the transactions of the chain are confirmed, or the claim of the
+    /// challenger is.
+    Completed,
+}
+
+/// An error of the watchtower.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchtowerError<E> {
+    /// An error of the backend.
+    Backend(E),
+    /// The transaction of a response cannot be built.
+    Response(String),
+}
+
+/// The builder of the transactions of the operator, which computes the hints of a step again
+/// from the state of the Fiat-Shamir transcript stored with the step, see [`ChainStep::state`].
+///
+/// [`ChainStep::state`]: crate::orchestrator::ChainStep::state
+pub struct Responder {
+    logn: usize,
+    proof: FriProof,
+    twiddle_merkle_tree_root: [u8; 32],
+}
+
+impl Responder {
+    /// Respond with the steps of the verification of the given FRI proof.
+    pub fn new(logn: usize, proof: FriProof, twiddle_merkle_tree_root: [u8; 32]) -> Self {
+        Self {
+            logn,
+            proof,
+            twiddle_merkle_tree_root,
+        }
+    }
+
+    /// Build the transaction at `index` of the chain, with the witness of its step.
+    pub fn respond(&self, chain: &VerificationChain, index: usize) -> Result<Transaction, String> {
+        let chain_tx = chain
+            .txs
+            .get(index)
+            .ok_or_else(|| "the index is out of the chain".to_string())?;
+        let TxRole::Verify(kind) = chain_tx.role else {
+            return Err("the commit transaction is signed by the user".to_string());
+        };
+        let step = &chain.steps[index - 1];
+
+        let mut verifier = FriIncrementalVerifier::resume(
+            step.state.clone(),
+            self.logn,
+            &self.proof,
+            self.twiddle_merkle_tree_root,
+        );
+        let verification = match kind {
+            StepKind::FriLayer(i) => verifier.verify_fri_layer(i),
+            StepKind::LastLayer => verifier.verify_last_layer(),
+            StepKind::Query(j) => verifier.verify_query(j),
+        };
+        let hints = convert_to_witness(verification.hints)?;
+
+        // the witness does not change the txid, so the transaction still spends the output of
+        // the previous step and is spent by the next one
+        let mut tx = chain_tx.tx.clone();
+        tx.input[0].witness =
+            Witness::from_slice(&step.taproot.script_path_witness(hints, &step.leaf_script));
+        Ok(tx)
+    }
+}
+
+/// The party that the watchtower acts for.
+pub enum Role {
+    /// The operator, which broadcasts the transaction of each step as soon as it is due, with
+    /// the witness built by the responder.
+    Operator(Responder),
+    /// The challenger, which claims the output of a step once it has timed out, see
+    /// [`VerificationChain::claim`].
+    Challenger {
+        /// The key of the challenger.
+        key: Keypair,
+        /// The scriptPubKey that the claim pays to.
+        destination: Script,
+        /// The fee rate of the claim.
+        fee_rate: FeeRate,
+    },
+}
+
+/// A watchtower that follows a verification chain, and reports the steps that are stalled,
+/// spent elsewhere, or timed out.
+///
+/// For the operator, it broadcasts each step transaction as soon as the previous one is
+/// confirmed, with the hints computed from the stored state of the step. For the challenger, it
+/// claims the output of a step that the operator has not spent within the timeout of the chain.
+pub struct Watchtower {
+    chain: VerificationChain,
+    patience: usize,
+    role: Role,
+    pending: Option<(usize, usize)>,
+    claim: Option<Txid>,
+}
+
+impl Watchtower {
+    /// Watch the chain for the given role, and report a transaction as stalled after `patience`
+    /// polls in the mempool.
+    pub fn new(chain: VerificationChain, patience: usize, role: Role) -> Self {
+        Self {
+            chain,
+            patience,
+            role,
+            pending: None,
+            claim: None,
+        }
+    }
+
+    /// The chain under watch.
+    pub fn chain(&self) -> &VerificationChain {
+        &self.chain
+    }
+
+    /// Look at the state of the chain once, broadcast the next transaction if it is due, and
+    /// return what is observed.
+    pub fn poll<B: ChainBackend>(
+        &mut self,
+        backend: &B,
+    ) -> Result<Vec<Alert>, WatchtowerError<B::Error>> {
+        block_on_ready(self.poll_async(backend))
+    }
+
+    /// Look at the state of the chain once through an async backend, see [`Watchtower::poll`].
+    pub async fn poll_async<B: AsyncChainBackend>(
+        &mut self,
+        backend: &B,
+    ) -> Result<Vec<Alert>, WatchtowerError<B::Error>> {
+        if let Some(claim) = self.claim {
+            match backend
+                .confirmations(&claim)
+                .await
+                .map_err(WatchtowerError::Backend)?
+            {
+                Some(0) => return Ok(vec![]),
+                Some(_) => return Ok(vec![Alert::Completed]),
+                // the claim is replaced, e.g., by the transaction of the step
+                None => self.claim = None,
+            }
+        }
+
+        let mut confirmed = HashSet::new();
+        let mut next = None;
+        let mut last_confirmations = 0;
+        for (i, chain_tx) in self.chain.txs.iter().enumerate() {
+            let txid = chain_tx.tx.compute_txid();
+            match backend
+                .confirmations(&txid)
+                .await
+                .map_err(WatchtowerError::Backend)?
+            {
+                Some(n) if n > 0 => {
+                    confirmed.insert(txid);
+                    last_confirmations = n;
+                }
+                confirmations => {
+                    next = Some((i, confirmations.is_some()));
+                    break;
+                }
+            }
+        }
+        debug_assert_eq!(self.chain.resume_from(&confirmed), next.map(|(i, _)| i));
+
+        let Some((index, in_mempool)) = next else {
+            self.pending = None;
+            return Ok(vec![Alert::Completed]);
+        };
+
+        let mut alerts = vec![];
+        if in_mempool {
+            let polls = match self.pending {
+                Some((i, polls)) if i == index => polls + 1,
+                _ => 1,
+            };
+            self.pending = Some((index, polls));
+            if polls > self.patience {
+                alerts.push(Alert::Stalled(index));
+            }
+            return Ok(alerts);
+        }
+        self.pending = None;
+
+        let chain_tx = &self.chain.txs[index];
+        if chain_tx.role == TxRole::Commit {
+            alerts.push(Alert::AwaitingCommit);
+            return Ok(alerts);
+        }
+
+        let outpoint = chain_tx.tx.input[0].previous_output;
+        if !backend
+            .is_unspent(&outpoint)
+            .await
+            .map_err(WatchtowerError::Backend)?
+        {
+            alerts.push(Alert::UnexpectedSpend(index, outpoint));
+            return Ok(alerts);
+        }
+
+        // the output can be claimed in the next block once it has `delay` confirmations
+        let timed_out = matches!(
+            self.chain.timeout,
+            Some((_, delay)) if last_confirmations >= delay as u32
+        );
+        if timed_out {
+            alerts.push(Alert::TimedOut(index));
+        }
+
+        match &self.role {
+            // the operator responds even after the timeout, as long as the output is unspent
+            Role::Operator(responder) => {
+                let tx = responder
+                    .respond(&self.chain, index)
+                    .map_err(WatchtowerError::Response)?;
+                backend
+                    .broadcast(&tx)
+                    .await
+                    .map_err(WatchtowerError::Backend)?;
+                alerts.push(Alert::Broadcast(index));
+            }
+            Role::Challenger {
+                key,
+                destination,
+                fee_rate,
+            } => {
+                if timed_out {
+                    let claim = self
+                        .chain
+                        .claim(index, destination.clone(), *fee_rate, key)
+                        .map_err(WatchtowerError::Response)?;
+                    backend
+                        .broadcast(&claim)
+                        .await
+                        .map_err(WatchtowerError::Backend)?;
+                    self.claim = Some(claim.compute_txid());
+                    alerts.push(Alert::Claimed(index));
+                }
+            }
+        }
+        Ok(alerts)
+    }
+}
+
+/// A [`ChainBackend`] on a bitcoind node.
+///
+/// The confirmations are looked up with `getrawtransaction`, which only finds a confirmed
+/// transaction if the node runs with `-txindex`. Without the index, a confirmed step would be
+/// unknown, and the spend of its output by the next step would be reported as unexpected, so the
+/// index is checked when the backend is created.
+#[cfg(feature = "rpc")]
+pub struct BitcoindBackend {
+    node: crate::rpc::BitcoindClient,
+}
+
+#[cfg(feature = "rpc")]
+impl BitcoindBackend {
+    /// Use the node as the backend, which fails if its transaction index is disabled or not
+    /// synced yet.
+    pub fn new(node: crate::rpc::BitcoindClient) -> bitcoincore_rpc::Result<Self> {
+        use bitcoincore_rpc::RpcApi;
+
+        let info: serde_json::Value = node.client.call("getindexinfo", &[])?;
+        match info["txindex"]["synced"].as_bool() {
+            Some(true) => Ok(Self { node }),
+            Some(false) => Err(bitcoincore_rpc::Error::ReturnedError(
+                "the transaction index of the node is not synced yet".to_string(),
+            )),
+            None => Err(bitcoincore_rpc::Error::ReturnedError(
+                "the node must run with -txindex to look up the confirmed steps".to_string(),
+            )),
+        }
+    }
+
+    /// The node.
+    pub fn node(&self) -> &crate::rpc::BitcoindClient {
+        &self.node
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl ChainBackend for BitcoindBackend {
+    type Error = bitcoincore_rpc::Error;
+
+    fn confirmations(&self, txid: &Txid) -> Result<Option<u32>, Self::Error> {
+        use bitcoincore_rpc::{jsonrpc, RpcApi};
+
+        // RPC_INVALID_ADDRESS_OR_KEY, returned for an unknown transaction
+        const UNKNOWN_TRANSACTION: i32 = -5;
+
+        match self.node.client.get_raw_transaction_info(txid, None) {
+            Ok(info) => Ok(Some(info.confirmations.unwrap_or(0))),
+            Err(bitcoincore_rpc::Error::JsonRpc(jsonrpc::Error::Rpc(err)))
+                if err.code == UNKNOWN_TRANSACTION =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn is_unspent(&self, outpoint: &OutPoint) -> Result<bool, Self::Error> {
+        use bitcoincore_rpc::RpcApi;
+
+        Ok(self
+            .node
+            .client
+            .get_tx_out(&outpoint.txid, outpoint.vout, Some(true))?
+            .is_some())
+    }
+
+    fn broadcast(&self, tx: &Transaction) -> Result<(), Self::Error> {
+        use bitcoincore_rpc::RpcApi;
+
+        self.node.client.send_raw_transaction(tx)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fri;
+    use crate::orchestrator::{ChainParameters, VerificationChain};
+    use crate::treepp::*;
+    use crate::twiddle_merkle_tree::TWIDDLE_MERKLE_TREE_ROOT_4;
+    use crate::utils::permute_eval;
+    use crate::watchtower::{block_on_ready, Alert, ChainBackend, Responder, Role, Watchtower};
+    use bitcoin::hashes::Hash;
+    use bitcoin::key::{Keypair, XOnlyPublicKey};
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use bitcoin::{Amount, FeeRate, OutPoint, Transaction, Txid};
+    use num_traits::One;
+    use std::cell::RefCell;
+    use std::collections::{HashMap, HashSet};
+    use stwo_prover::core::circle::CirclePointIndex;
+    use stwo_prover::core::fields::m31::M31;
+    use stwo_prover::core::fields::FieldExpOps;
+
+    // A chain where broadcast transactions enter the mempool, and are confirmed on `mine`.
+    #[derive(Default)]
+    struct MockChain {
+        confirmations: RefCell<HashMap<Txid, u32>>,
+        spent: RefCell<HashSet<OutPoint>>,
+    }
+
+    impl MockChain {
+        fn mine(&self) {
+            for confirmations in self.confirmations.borrow_mut().values_mut() {
+                *confirmations += 1;
+            }
+        }
+
+        fn add(&self, tx: &Transaction) {
+            self.spent.borrow_mut().insert(tx.input[0].previous_output);
+            self.confirmations.borrow_mut().insert(tx.compute_txid(), 0);
+        }
+    }
+
+    impl ChainBackend for MockChain {
+        type Error = ();
+
+        fn confirmations(&self, txid: &Txid) -> Result<Option<u32>, ()> {
+            Ok(self.confirmations.borrow().get(txid).copied())
+        }
+
+        fn is_unspent(&self, outpoint: &OutPoint) -> Result<bool, ()> {
+            let created = self.confirmations.borrow().contains_key(&outpoint.txid);
+            Ok(created && !self.spent.borrow().contains(outpoint))
+        }
+
+        fn broadcast(&self, tx: &Transaction) -> Result<(), ()> {
+            self.add(tx);
+            Ok(())
+        }
+    }
+
+    fn test_chain(timeout: Option<(XOnlyPublicKey, u16)>) -> (VerificationChain, Responder) {
+        let logn = 5;
+        let p = CirclePointIndex::subgroup_gen(logn as u32 + 1).to_point();
+        let evaluation = (0..(1 << logn))
+            .map(|i| (p.mul(i * 2 + 1).x.square().square() + M31::one()).into())
+            .collect();
+        let (channel_init_state, proof) = fri::fri_prove_with_seed(0, permute_eval(evaluation));
+
+        let chain = VerificationChain::new(
+            channel_init_state,
+            logn,
+            &proof,
+            TWIDDLE_MERKLE_TREE_ROOT_4,
+            &ChainParameters {
+                funding_outpoint: OutPoint::new(Txid::all_zeros(), 0),
+                funding_amount: Amount::from_sat(100_000),
+                fee_per_tx: Amount::from_sat(1_000),
+                destination: script! { OP_TRUE },
+                timeout,
+            },
+        )
+        .unwrap();
+        (
+            chain,
+            Responder::new(logn, proof, TWIDDLE_MERKLE_TREE_ROOT_4),
+        )
+    }
+
+    fn challenger() -> Keypair {
+        Keypair::from_secret_key(&Secp256k1::new(), &SecretKey::from_slice(&[1; 32]).unwrap())
+    }
+
+    #[test]
+    fn test_responder() {
+        let (chain, responder) = test_chain(None);
+
+        // the hints computed from the stored states are the ones of the chain
+        assert!(responder.respond(&chain, 0).is_err());
+        for i in 1..chain.txs.len() {
+            assert_eq!(responder.respond(&chain, i).unwrap(), chain.txs[i].tx);
+        }
+    }
+
+    #[test]
+    fn test_watchtower() {
+        let (chain, responder) = test_chain(None);
+        let mut watchtower = Watchtower::new(chain, 1, Role::Operator(responder));
+        let n_txs = watchtower.chain().txs.len();
+        let backend = MockChain::default();
+
+        assert_eq!(watchtower.poll(&backend), Ok(vec![Alert::AwaitingCommit]));
+
+        // the user signs and broadcasts the commit transaction
+        backend.add(&watchtower.chain().txs[0].tx);
+        assert_eq!(watchtower.poll(&backend), Ok(vec![]));
+        assert_eq!(watchtower.poll(&backend), Ok(vec![Alert::Stalled(0)]));
+
+        for i in 1..n_txs {
+            backend.mine();
+            assert_eq!(watchtower.poll(&backend), Ok(vec![Alert::Broadcast(i)]));
+            // the transaction is in the mempool now
+            assert_eq!(watchtower.poll(&backend), Ok(vec![]));
+        }
+
+        backend.mine();
+        assert_eq!(watchtower.poll(&backend), Ok(vec![Alert::Completed]));
+    }
+
+    #[test]
+    fn test_watchtower_unexpected_spend() {
+        let (chain, responder) = test_chain(None);
+        let mut watchtower = Watchtower::new(chain, 1, Role::Operator(responder));
+        let backend = MockChain::default();
+
+        backend.add(&watchtower.chain().txs[0].tx);
+        backend.mine();
+
+        // the output of the first step is spent by another transaction, e.g., a refund
+        let outpoint = watchtower.chain().txs[1].tx.input[0].previous_output;
+        backend.spent.borrow_mut().insert(outpoint);
+        assert_eq!(
+            watchtower.poll(&backend),
+            Ok(vec![Alert::UnexpectedSpend(1, outpoint)])
+        );
+    }
+
+    #[test]
+    fn test_watchtower_timeout() {
+        let timeout = Some((challenger().x_only_public_key().0, 2));
+
+        // the operator is late, but responds as long as the output is not claimed
+        let (chain, responder) = test_chain(timeout);
+        let mut operator = Watchtower::new(chain, 1, Role::Operator(responder));
+        let backend = MockChain::default();
+        backend.add(&operator.chain().txs[0].tx);
+        backend.mine();
+        backend.mine();
+        assert_eq!(
+            operator.poll(&backend),
+            Ok(vec![Alert::TimedOut(1), Alert::Broadcast(1)])
+        );
+
+        // the challenger waits for the delay, and then claims the output
+        let (chain, _) = test_chain(timeout);
+        let mut challenger = Watchtower::new(
+            chain,
+            1,
+            Role::Challenger {
+                key: challenger(),
+                destination: script! { OP_TRUE },
+                fee_rate: FeeRate::from_sat_per_vb(1).unwrap(),
+            },
+        );
+        let backend = MockChain::default();
+        backend.add(&challenger.chain().txs[0].tx);
+        backend.mine();
+        assert_eq!(challenger.poll(&backend), Ok(vec![]));
+        backend.mine();
+        assert_eq!(
+            challenger.poll(&backend),
+            Ok(vec![Alert::TimedOut(1), Alert::Claimed(1)])
+        );
+
+        // the claim is in the mempool, and then confirmed
+        assert_eq!(challenger.poll(&backend), Ok(vec![]));
+        backend.mine();
+        assert_eq!(challenger.poll(&backend), Ok(vec![Alert::Completed]));
+    }
+
+    #[test]
+    fn test_watchtower_run() {
+        let (chain, responder) = test_chain(None);
+        let mut watchtower = Watchtower::new(chain, 1, Role::Operator(responder));
+        let n_txs = watchtower.chain().txs.len();
+        let backend = MockChain::default();
+        backend.add(&watchtower.chain().txs[0].tx);
+
+        // a block is mined between two polls
+        let mut broadcast = vec![];
+        block_on_ready(watchtower.run(
+            &backend,
+            || {
+                backend.mine();
+                std::future::ready(())
+            },
+            |alerts| {
+                broadcast.extend(alerts.iter().filter_map(|alert| match alert {
+                    Alert::Broadcast(i) => Some(*i),
+                    _ => None,
+                }))
+            },
+        ))
+        .unwrap();
+        assert_eq!(broadcast, (1..n_txs).collect::<Vec<_>>());
+    }
+}
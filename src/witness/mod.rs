@@ -0,0 +1,205 @@
+use crate::fibonacci::VerifierHints;
+use crate::treepp::*;
+use bitcoin::Witness;
+use bitcoin_scriptexec::convert_to_witness;
+use serde::{Deserialize, Serialize};
+
+/// Convert the hints, given as the script that pushes them, into a witness.
+///
+/// The witness items are the elements that the script leaves on the stack, so the hints can be
+/// consumed by a leaf script without any change.
+pub fn hints_to_witness(hints: Script) -> Result<Witness, String> {
+    Ok(Witness::from_slice(&convert_to_witness(hints)?))
+}
+
+/// Convert a witness back into the script that pushes its items.
+///
+/// This is the inverse of [`hints_to_witness`]: the script may encode a small number with a
+/// data push instead of an opcode, but it leaves exactly the same elements on the stack.
+pub fn witness_to_hints(witness: &Witness) -> Script {
+    script! {
+        for item in witness.iter() {
+            { item.to_vec() }
+        }
+    }
+}
+
+/// The type of a hint field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HintKind {
+    /// A hash, in one item.
+    Hash,
+    /// Hints for drawing elements from the channel.
+    DrawHints,
+    /// The OODS hint: the drawing hints, followed by the point.
+    OodsHint,
+    /// qm31 elements, in four items each.
+    Qm31,
+    /// The PoW hint.
+    PowHint,
+    /// Sorted queries: their number, followed by the queries.
+    Queries,
+}
+
+/// A hint field, which occupies `count` consecutive witness items starting at `offset`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HintLayoutEntry {
+    /// The name of the field.
+    pub name: String,
+    /// The type of the field.
+    pub kind: HintKind,
+    /// The index of the first item.
+    pub offset: usize,
+    /// The number of items.
+    pub count: usize,
+}
+
+/// The layout of the hints in the witness, which tells which items belong to which field.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HintLayout {
+    /// The fields, in the order in which the verifier consumes them.
+    pub entries: Vec<HintLayoutEntry>,
+    /// The total number of items.
+    pub total_items: usize,
+}
+
+impl HintLayout {
+    fn append(
+        &mut self,
+        items: &mut Vec<Vec<u8>>,
+        name: impl ToString,
+        kind: HintKind,
+        hint: Script,
+    ) -> Result<(), String> {
+        let field_items = convert_to_witness(hint)?;
+        self.entries.push(HintLayoutEntry {
+            name: name.to_string(),
+            kind,
+            offset: self.total_items,
+            count: field_items.len(),
+        });
+        self.total_items += field_items.len();
+        items.extend(field_items);
+        Ok(())
+    }
+
+    /// The entry of a field.
+    pub fn entry(&self, name: &str) -> Option<&HintLayoutEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// The items of a field, out of all the witness items.
+    pub fn items<'a>(&self, items: &'a [Vec<u8>], name: &str) -> Option<&'a [Vec<u8>]> {
+        let entry = self.entry(name)?;
+        items.get(entry.offset..entry.offset + entry.count)
+    }
+}
+
+/// Convert the verifier hints into a witness, along with the layout of its items.
+///
+/// The witness is the same as the one from [`hints_to_witness`], as both follow
+/// [`VerifierHints::fields`].
+pub fn partition_hints(hints: &VerifierHints) -> Result<(Witness, HintLayout), String> {
+    let mut layout = HintLayout::default();
+    let mut items = vec![];
+
+    for (name, kind, hint) in hints.fields() {
+        layout.append(&mut items, name, kind, hint)?;
+    }
+
+    Ok((Witness::from_slice(&items), layout))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fibonacci::{prove_and_hint, FibonacciConfig};
+    use crate::treepp::*;
+    use crate::utils::{get_rand_bws_sha256_hash, get_rand_qm31};
+    use crate::witness::{hints_to_witness, partition_hints, witness_to_hints, HintLayout};
+    use bitcoin_scriptexec::execute_script_with_witness_unlimited_stack;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use rust_bitcoin_m31::{qm31_equalverify, qm31_from_bottom};
+
+    #[test]
+    fn test_witness_roundtrip() {
+        let mut prng = ChaCha20Rng::seed_from_u64(0);
+
+        let a = get_rand_qm31(&mut prng);
+        let hash = get_rand_bws_sha256_hash(&mut prng);
+
+        let hints = script! {
+            { a }
+            { hash }
+            0
+            5
+            { -1 }
+        };
+        let witness = hints_to_witness(hints).unwrap();
+        assert_eq!(witness.len(), 8);
+
+        let witness_again = hints_to_witness(witness_to_hints(&witness)).unwrap();
+        assert_eq!(witness_again, witness);
+
+        let script = script! {
+            qm31_from_bottom
+            { a }
+            qm31_equalverify
+            OP_DEPTH OP_1SUB OP_ROLL
+            { hash }
+            OP_EQUALVERIFY
+            OP_DEPTH OP_1SUB OP_ROLL
+            OP_NOT OP_VERIFY
+            OP_DEPTH OP_1SUB OP_ROLL
+            5 OP_EQUALVERIFY
+            OP_DEPTH OP_1SUB OP_ROLL
+            OP_1NEGATE OP_EQUAL
+        };
+
+        let exec_result = execute_script_with_witness_unlimited_stack(script, witness.to_vec());
+        assert!(exec_result.success);
+    }
+
+    #[test]
+    fn test_partition_hints() {
+        let config = FibonacciConfig {
+            log_size: 5,
+            claim: 443693538,
+        };
+        let (_, hints) = prove_and_hint(&config).unwrap();
+
+        let (witness, layout) = partition_hints(&hints).unwrap();
+        let items = witness.to_vec();
+        assert_eq!(layout.total_items, items.len());
+
+        // the fields are contiguous
+        let mut offset = 0;
+        for entry in layout.entries.iter() {
+            assert_eq!(entry.offset, offset);
+            offset += entry.count;
+        }
+        assert_eq!(offset, layout.total_items);
+
+        assert_eq!(
+            layout.items(&items, "last_layer").unwrap(),
+            hints_to_witness(script! { { hints.last_layer } })
+                .unwrap()
+                .to_vec()
+        );
+        assert_eq!(
+            layout.items(&items, "test_only").unwrap(),
+            vec![hints.test_only.as_ref().to_vec()]
+        );
+        assert!(layout
+            .entry(&format!("fri_folding_hint_{}", config.log_size - 1))
+            .is_some());
+
+        // the manifest survives a roundtrip through JSON
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(serde_json::from_str::<HintLayout>(&json).unwrap(), layout);
+
+        // the witness is the same as the one from all the hints at once
+        assert_eq!(witness, hints_to_witness(script! { { hints } }).unwrap());
+    }
+}